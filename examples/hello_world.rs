@@ -1,3 +1,5 @@
+use std::time::{Duration, Instant};
+
 use pembejeo::{Event, Pembejeo};
 
 fn main() {
@@ -5,8 +7,10 @@ fn main() {
     println!("Hello, World!");
 
     loop {
+        // Handle input until the next frame is due
+        let next_frame = Instant::now() + Duration::from_millis(16);
         let mut event = Event::default();
-        while pembejeo.wait(&mut event) {
+        while pembejeo.wait_until(&mut event, next_frame) {
             //println!("Event: {:?}", event);
         }
     }
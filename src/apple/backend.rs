@@ -0,0 +1,582 @@
+
+use core::slice;
+use std::{collections::HashMap, sync::{Arc, Mutex, OnceLock}, thread::{self, JoinHandle}, time::Duration};
+
+use core_foundation::{base::{CFIndex, TCFType}, runloop::{kCFRunLoopDefaultMode, CFRunLoopGetCurrent, CFRunLoopRef, CFRunLoopRun, CFRunLoopStop}};
+use libc::{c_void, c_int};
+
+use crate::{apple::iohid::{IOHIDManagerOpen, IOHIDManagerRegisterDeviceMatchingCallback, IOHIDManagerRegisterDeviceRemovalCallback, IOHIDManagerScheduleWithRunLoop, IOHIDManagerSetDeviceMatchingMultiple, IOHIDManagerUnscheduleFromRunLoop}, interpreter::ReportInterpreter, device::{stable_id, unique_id}, Backend, Capabilities, Context, DeviceFilter, DeviceHandle, DeviceInfo, Event, HidReport, HidReportEvent, ReportDescriptor, ReportKind, SdlGuid, Timestamp, Transport, Usage};
+
+/// The macOS backend, built on an IOHIDManager scheduled on its own run loop thread.
+pub struct IOHIDBackend {
+    iohid_manager: *mut c_void,
+    input_thread: Option<JoinHandle<()>>,
+    input_run_loop: Arc<Mutex<Option<usize>>>,
+
+    /// Boxed so the IOKit callbacks can hold on to it
+    state: Option<Box<IOHIDState>>,
+}
+
+/// What the IOKit callbacks receive as their context.
+struct IOHIDState {
+    context: Context,
+
+    /// Matched devices by their IOHIDDeviceRef
+    devices: Mutex<HashMap<usize, IOHIDDevice>>,
+}
+
+struct IOHIDDevice {
+    /// The IOHIDDeviceRef
+    device: usize,
+    /// What the device was announced with, to match it against the filters again
+    info: DeviceInfo,
+
+    descriptor: Option<ReportDescriptor>,
+    interpreter: ReportInterpreter,
+    /// Whether a filter asked for the device's `Event::HidReport`s
+    hid_reports: bool,
+
+    /// IOKit writes input reports here, so it has to live as long as the device is matched
+    report_buffer: Vec<u8>,
+}
+
+// The manager is only touched from the input thread once it is started.
+unsafe impl Send for IOHIDBackend {}
+
+impl IOHIDBackend {
+    pub fn new() -> Result<Self, crate::Error> {
+        Ok(IOHIDBackend {
+            iohid_manager: create_iohid_manager()?,
+            input_thread: None,
+            input_run_loop: Arc::new(Mutex::new(None)),
+            state: None,
+        })
+    }
+
+    /// Schedule the manager on a run loop of its own thread, where the IOKit callbacks run.
+    fn spawn_input_thread(&mut self) {
+        let iohid_manager_usize_ref = self.iohid_manager as usize;
+        let input_run_loop_clone = self.input_run_loop.clone();
+        self.input_thread = Some(thread::spawn(move || unsafe {
+            let run_loop = CFRunLoopGetCurrent();
+            let iohid_manager = iohid_manager_usize_ref as *mut c_void;
+            IOHIDManagerScheduleWithRunLoop(iohid_manager, run_loop, kCFRunLoopDefaultMode);
+            IOHIDManagerOpen(iohid_manager, 0x00);
+
+            // Set the run loop
+            {
+                let mut input_run_loop = input_run_loop_clone.lock().unwrap();
+                *input_run_loop = Some(run_loop as usize);
+            }
+
+            CFRunLoopRun();
+
+            // The run loop goes away with the thread
+            IOHIDManagerUnscheduleFromRunLoop(iohid_manager, run_loop, kCFRunLoopDefaultMode);
+        }));
+    }
+
+    /// Stop the input thread's run loop and wait for it, after which no callbacks run.
+    fn stop_input_thread(&mut self) {
+        // Never started, so there is no run loop to wait for
+        let Some(thread) = self.input_thread.take() else {
+            return;
+        };
+
+        // Stopping a run loop that hasn't started running yet does nothing, so keep at it until the thread ends
+        while !thread.is_finished() {
+            if let Some(input_run_loop) = *self.input_run_loop.lock().unwrap() {
+                unsafe { CFRunLoopStop(input_run_loop as CFRunLoopRef) };
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        thread.join().unwrap();
+        *self.input_run_loop.lock().unwrap() = None;
+    }
+
+    fn device(&self, device_id: &str) -> Result<*mut c_void, crate::Error> {
+        let devices = self.state.as_ref().map(|state| state.devices.lock().unwrap());
+        match devices.as_ref().and_then(|devices| devices.values().find(|device| device.info.id == device_id)) {
+            Some(device) => Ok(device.device as *mut c_void),
+            None => Err(crate::Error::DeviceNotFound(device_id.to_string())),
+        }
+    }
+}
+
+impl Backend for IOHIDBackend {
+    fn start(&mut self, context: Context) -> Result<(), crate::Error> {
+        let matching_array = create_matching_array(&context.filters());
+        let state = self.state.insert(Box::new(IOHIDState {
+            context,
+            devices: Mutex::new(HashMap::new()),
+        }));
+        let in_context = state.as_mut() as *mut IOHIDState as *mut c_void;
+
+        // Setup the matching and callbacks
+        unsafe {
+            IOHIDManagerSetDeviceMatchingMultiple(self.iohid_manager, matching_array.as_CFTypeRef());
+
+            IOHIDManagerRegisterDeviceMatchingCallback(self.iohid_manager, handle_device_matching_callback, in_context);
+            IOHIDManagerRegisterDeviceRemovalCallback(self.iohid_manager, handle_device_removal_callback, in_context);
+        }
+
+        self.spawn_input_thread();
+
+        Ok(())
+    }
+
+    fn shutdown(&mut self) {
+        self.stop_input_thread();
+    }
+
+    fn apply_filters(&mut self) -> Result<(), crate::Error> {
+        use core_foundation::set::{CFSet, CFSetGetCount, CFSetGetValues};
+
+        use crate::apple::iohid::{IOHIDDeviceRegisterInputReportWithTimeStampCallback, IOHIDManagerCopyDevices};
+
+        if self.state.is_none() {
+            return Ok(());
+        }
+
+        // IOKit writes reports into the buffers of the devices about to be dropped, so hold the callbacks off
+        self.stop_input_thread();
+        let state = self.state.as_ref().unwrap();
+        let in_context = state.as_ref() as *const IOHIDState as *mut c_void;
+
+        let matching_array = create_matching_array(&state.context.filters());
+        unsafe { IOHIDManagerSetDeviceMatchingMultiple(self.iohid_manager, matching_array.as_CFTypeRef()) };
+
+        let removed: Vec<usize> = state.devices.lock().unwrap().values()
+            .filter(|iohid_device| !state.context.matches_device(&iohid_device.info))
+            .map(|iohid_device| iohid_device.device)
+            .collect();
+        for device in removed {
+            unsafe { IOHIDDeviceRegisterInputReportWithTimeStampCallback(device as *mut c_void, std::ptr::null_mut(), 0, None, std::ptr::null_mut()) };
+            remove_device(state, device);
+        }
+        for iohid_device in state.devices.lock().unwrap().values_mut() {
+            iohid_device.hid_reports = state.context.wants_hid_reports(&iohid_device.info);
+        }
+
+        // The manager only calls back for devices it didn't have yet, so offer it the rest again
+        unsafe {
+            let devices_ref = IOHIDManagerCopyDevices(self.iohid_manager);
+            if !devices_ref.is_null() {
+                let devices: CFSet = CFSet::wrap_under_create_rule(devices_ref);
+                let mut values = vec![std::ptr::null(); CFSetGetCount(devices.as_concrete_TypeRef()) as usize];
+                CFSetGetValues(devices.as_concrete_TypeRef(), values.as_mut_ptr());
+                for device in values {
+                    handle_device_matching_callback(in_context, 0, std::ptr::null_mut(), device as *mut c_void);
+                }
+            }
+        }
+
+        self.spawn_input_thread();
+        Ok(())
+    }
+
+    fn set_feature_report(&mut self, device_id: &str, report: &[u8]) -> Result<(), crate::Error> {
+        set_report(self.device(device_id)?, IOHID_REPORT_TYPE_FEATURE, report)
+    }
+
+    fn set_output_report(&mut self, device_id: &str, report: &[u8]) -> Result<(), crate::Error> {
+        set_report(self.device(device_id)?, IOHID_REPORT_TYPE_OUTPUT, report)
+    }
+
+    fn get_feature_report(&mut self, device_id: &str, report: &mut [u8]) -> Result<usize, crate::Error> {
+        get_feature_report(self.device(device_id)?, report)
+    }
+
+    fn set_high_resolution_scroll(&mut self, device_id: &str, enabled: bool) -> Result<(), crate::Error> {
+        let Some(state) = self.state.as_ref() else {
+            return Err(crate::Error::DeviceNotFound(device_id.to_string()));
+        };
+        let mut devices = state.devices.lock().unwrap();
+        let Some(iohid_device) = devices.values_mut().find(|device| device.info.id == device_id) else {
+            return Err(crate::Error::DeviceNotFound(device_id.to_string()));
+        };
+        let Some(descriptor) = iohid_device.descriptor.as_ref() else {
+            return Err(crate::Error::NotSupported(format!("{} has no report descriptor", device_id)));
+        };
+
+        let device = iohid_device.device as *mut c_void;
+        iohid_device.interpreter.set_high_resolution_scroll(
+            device_id,
+            descriptor,
+            enabled,
+            |report| get_feature_report(device, report).is_ok(),
+            |report| set_report(device, IOHID_REPORT_TYPE_FEATURE, report),
+        )
+    }
+}
+
+fn set_report(device: *mut c_void, report_type: u32, report: &[u8]) -> Result<(), crate::Error> {
+    use crate::apple::iohid::IOHIDDeviceSetReport;
+
+    let Some(report_id) = report.first() else {
+        return Err(crate::Error::FailedSettingReport("The report is empty".to_string()));
+    };
+
+    // IOKit wants the report ID byte left off when the device doesn't use them
+    let mut data = if *report_id == 0 { report[1..].to_vec() } else { report.to_vec() };
+    let res = unsafe { IOHIDDeviceSetReport(device, report_type, *report_id as isize, data.as_mut_ptr(), data.len() as isize) };
+    if res != 0x00 {
+        return Err(crate::Error::FailedSettingReport(format!("IOHIDDeviceSetReport returned 0x{:x}", res)));
+    }
+    Ok(())
+}
+
+fn get_feature_report(device: *mut c_void, report: &mut [u8]) -> Result<usize, crate::Error> {
+    use crate::apple::iohid::IOHIDDeviceGetReport;
+
+    let Some(report_id) = report.first().copied() else {
+        return Err(crate::Error::FailedGettingReport("The report buffer is empty".to_string()));
+    };
+
+    // Keep the report ID byte in front like hidraw does
+    let data = if report_id == 0 { &mut report[1..] } else { &mut report[..] };
+    let mut length = data.len() as isize;
+    let res = unsafe { IOHIDDeviceGetReport(device, IOHID_REPORT_TYPE_FEATURE, report_id as isize, data.as_mut_ptr(), &mut length) };
+    if res != 0x00 {
+        return Err(crate::Error::FailedGettingReport(format!("IOHIDDeviceGetReport returned 0x{:x}", res)));
+    }
+    Ok(if report_id == 0 { length as usize + 1 } else { length as usize })
+}
+
+/// `kIOHIDReportTypeOutput`
+const IOHID_REPORT_TYPE_OUTPUT: u32 = 1;
+/// `kIOHIDReportTypeFeature`
+const IOHID_REPORT_TYPE_FEATURE: u32 = 2;
+
+fn create_iohid_manager() -> Result<*mut c_void, crate::Error> {
+    use core_foundation::base::kCFAllocatorDefault;
+
+    use crate::apple::iohid::IOHIDManagerCreate;
+    let manager = unsafe { IOHIDManagerCreate(kCFAllocatorDefault, 0x00) };
+    if manager.is_null() {
+        return Err(crate::Error::FailedCreatingPembejeo("IOHIDManagerCreate returned nullptr!".to_string()));
+    }
+    Ok(manager)
+}
+
+fn create_matching_dictionary(page: Option<u16>, usage: Option<u16>, vendor_id: Option<u16>, product_id: Option<u16>) ->
+    core_foundation::dictionary::CFDictionary<
+    core_foundation::string::CFString,
+    core_foundation::number::CFNumber>
+{
+    use std::str::FromStr;
+
+    use core_foundation::{dictionary::CFMutableDictionary, number::CFNumber, string::CFString};
+
+    let mut dict = CFMutableDictionary::<CFString, CFNumber>::new();
+
+    // A dictionary without keys matches every device
+    let keys = [("DeviceUsagePage", page), ("DeviceUsage", usage), ("VendorID", vendor_id), ("ProductID", product_id)];
+    for (key, value) in keys {
+        if let Some(value) = value {
+            dict.set(CFString::from_str(key).unwrap(), CFNumber::from(value as i32));
+        }
+    }
+
+    dict.to_immutable()
+}
+
+/// The devices to match: the filters' usages and ids, or the mice, keyboards, touchpads and gamepads
+/// when there are no filters. Transports and names are checked once a device is matched.
+fn create_matching_array(filters: &[DeviceFilter]) ->
+    core_foundation::array::CFArray<
+        core_foundation::dictionary::CFDictionary<
+            core_foundation::string::CFString,
+            core_foundation::number::CFNumber,
+        >
+    >
+{
+    use core_foundation::{array::CFArray, dictionary::CFDictionary, number::CFNumber, string::CFString};
+
+    let dicts: Vec<CFDictionary<CFString, CFNumber>> = if filters.is_empty() {
+        [(0xFF00, 0x0C), (0x01, 0x02), (0x01, 0x06), (0x0D, 0x05), (0x01, 0x04), (0x01, 0x05)].into_iter()
+            .map(|(page, usage)| create_matching_dictionary(Some(page), Some(usage), None, None))
+            .collect()
+    } else {
+        filters.iter()
+            .map(|filter| create_matching_dictionary(filter.usage_page, filter.usage, filter.vendor_id, filter.product_id))
+            .collect()
+    };
+
+    CFArray::from_CFTypes(&dicts)
+}
+
+extern "C" fn handle_device_matching_callback(in_context: *mut c_void, _in_return: c_int, _sender: *mut c_void, device: *mut c_void) {
+    use crate::apple::iohid::{IOHIDDeviceGetProperty, IOHIDDeviceRegisterInputReportWithTimeStampCallback, IOHIDDeviceSetReport};
+    use core_foundation::{data::{CFData, CFDataRef}, number::{CFNumber, CFNumberRef}, string::{CFString, CFStringRef}};
+
+    let state = unsafe { &*(in_context as *const IOHIDState) };
+    let pembejeo = &*state.context;
+
+    // Get the device's usage page property
+    let usage_page = unsafe {
+        let usage_page_ref: CFNumberRef = IOHIDDeviceGetProperty(device, CFString::from("PrimaryUsagePage").as_concrete_TypeRef()) as CFNumberRef;
+        let usage_page = CFNumber::wrap_under_get_rule(usage_page_ref);
+        usage_page.to_i32().unwrap() as u16
+    };
+
+    // Matching again after the filters changed offers the devices that are already there
+    if state.devices.lock().unwrap().contains_key(&(device as usize)) {
+        return;
+    }
+
+    // Get the device's usage property
+    let usage = unsafe {
+        let usage_ref: CFNumberRef = IOHIDDeviceGetProperty(device, CFString::from("PrimaryUsage").as_concrete_TypeRef()) as CFNumberRef;
+        let usage = CFNumber::wrap_under_get_rule(usage_ref);
+        usage.to_i32().unwrap() as u16
+    };
+
+    // Get the device's product id
+    let vendor_id = unsafe {
+        let vendor_id_ref: CFNumberRef = IOHIDDeviceGetProperty(device, CFString::from("VendorID").as_concrete_TypeRef()) as CFNumberRef;
+        let vendor = CFNumber::wrap_under_get_rule(vendor_id_ref);
+        vendor.to_i32().unwrap() as u16
+    };
+
+    // Get the device's product id
+    let product_id = unsafe {
+        let product_id_ref: CFNumberRef = IOHIDDeviceGetProperty(device, CFString::from("ProductID").as_concrete_TypeRef()) as CFNumberRef;
+        let product = CFNumber::wrap_under_get_rule(product_id_ref);
+        product.to_i32().unwrap() as u16
+    };
+
+    // Get the device's product name
+    let product = unsafe {
+        let product_ref: CFStringRef = IOHIDDeviceGetProperty(device, CFString::new("Product").as_concrete_TypeRef()) as CFStringRef;
+        if !product_ref.is_null() {
+            let product = CFString::wrap_under_get_rule(product_ref);
+            product.to_string()
+        } else {
+            "".to_string()
+        }
+        
+    };
+
+    // Get the device's manufacturer name
+    let manufacturer = unsafe {
+        let manufacturer_ref: CFStringRef = IOHIDDeviceGetProperty(device, CFString::new("Manufacturer").as_concrete_TypeRef()) as CFStringRef;
+        if !manufacturer_ref.is_null() {
+            let manufacturer = CFString::wrap_under_get_rule(manufacturer_ref);
+            manufacturer.to_string()
+        } else {
+            "".to_string()
+        }
+    };
+
+    // Get the device's report descriptor
+    let report_descriptor = unsafe {
+        let desc_ref: CFDataRef = IOHIDDeviceGetProperty(device, CFString::new("ReportDescriptor").as_concrete_TypeRef()) as CFDataRef;
+        if !desc_ref.is_null() {
+            let desc_data = CFData::wrap_under_get_rule(desc_ref);
+            slice::from_raw_parts(desc_data.as_ptr(), desc_data.len() as usize).to_vec()
+        } else {
+            Vec::new()
+        }
+    };
+
+    // Get the device's transport
+    let transport = unsafe {
+        let transport_ref: CFStringRef = IOHIDDeviceGetProperty(device, CFString::new("Transport").as_concrete_TypeRef()) as CFStringRef;
+        if !transport_ref.is_null() {
+            match CFString::wrap_under_get_rule(transport_ref).to_string().as_str() {
+                "USB" => Transport::Usb,
+                "Bluetooth" | "Bluetooth Low Energy" => Transport::Bluetooth,
+                "I2C" => Transport::I2c,
+                "SPI" => Transport::Spi,
+                _ => Transport::Unknown,
+            }
+        } else {
+            Transport::Unknown
+        }
+    };
+
+    // The top-level collections, or the primary usage when the descriptor can't be parsed
+    let mut usages = ReportDescriptor::parse(&report_descriptor).map(|descriptor| descriptor.application_usages()).unwrap_or_default();
+    if usages.is_empty() {
+        usages.push(Usage::new(usage_page, usage));
+    }
+
+    // Get the device's release number, serial number and location
+    let version = unsafe {
+        let version_ref: CFNumberRef = IOHIDDeviceGetProperty(device, CFString::from("VersionNumber").as_concrete_TypeRef()) as CFNumberRef;
+        if !version_ref.is_null() {
+            CFNumber::wrap_under_get_rule(version_ref).to_i32().unwrap_or(0) as u16
+        } else {
+            0
+        }
+    };
+    let serial_number = unsafe {
+        let serial_number_ref: CFStringRef = IOHIDDeviceGetProperty(device, CFString::new("SerialNumber").as_concrete_TypeRef()) as CFStringRef;
+        if !serial_number_ref.is_null() {
+            CFString::wrap_under_get_rule(serial_number_ref).to_string()
+        } else {
+            String::new()
+        }
+    };
+    let location = unsafe {
+        let location_ref: CFNumberRef = IOHIDDeviceGetProperty(device, CFString::from("LocationID").as_concrete_TypeRef()) as CFNumberRef;
+        if !location_ref.is_null() {
+            format!("0x{:08x}", CFNumber::wrap_under_get_rule(location_ref).to_i64().unwrap_or(0) as u32)
+        } else {
+            String::new()
+        }
+    };
+
+    // Composite devices show up once per top-level collection
+    let id = stable_id("iohid", vendor_id, product_id, &serial_number, &location, &format!("{:04x}:{:04x}", usage_page, usage));
+    let id = unique_id(id, |id| pembejeo.devices.lock().unwrap().contains_key(id));
+
+    let mut info = DeviceInfo {
+        id: id.clone(),
+        handle: DeviceHandle::default(),
+        vendor_id,
+        product_id,
+        version,
+        transport,
+        location,
+        usages,
+        capabilities: Capabilities::default(),
+        // SDL files macOS controllers under the USB or Bluetooth bus type
+        guid: Some(SdlGuid::new(transport.bus_type(), vendor_id, product_id, version, &product)),
+        product,
+        manufacturer,
+        serial_number,
+        report_descriptor,
+    };
+
+    // IOKit knows what the primary usage is even when the descriptor can't be parsed
+    info.capabilities = info.report_capabilities();
+    match (usage_page, usage) {
+        (0x01, 0x02) => info.capabilities.pointer = true,
+        (0x01, 0x06) => info.capabilities.keys = true,
+        (0x01, 0x04 | 0x05) => info.capabilities.gamepad = true,
+        _ => {},
+    }
+    if !info.capabilities.gamepad {
+        info.guid = None;
+    }
+
+    // The matching dictionaries only cover usages and ids
+    if !pembejeo.matches_device(&info) {
+        return;
+    }
+    // Reports can arrive before the device is announced, so take its handle now
+    info.handle = pembejeo.assign_handle(&id);
+
+    // Send a feature report to enable multitouch, only the vendor trackpad interface understands it.
+    // Should it fail the trackpad keeps reporting as a plain mouse.
+    if usage_page == 0xFF00 {
+        let mut report_data = [0x02_u8, 0x01_u8, 0x01u8];
+        unsafe {
+            IOHIDDeviceSetReport(
+                device,
+                2,
+                0x02,
+                report_data.as_mut_ptr(),
+                report_data.len() as isize
+            )
+        };
+    }
+
+    // Size the report buffer for the largest input report the device declares
+    let descriptor = info.parse_report_descriptor().ok();
+    let report_size = descriptor.as_ref()
+        .and_then(|descriptor| descriptor.reports.iter()
+            .filter(|report| report.kind == ReportKind::Input)
+            .map(|report| report.byte_length())
+            .max())
+        .unwrap_or(64);
+
+    let mut iohid_device = IOHIDDevice {
+        device: device as usize,
+        info: info.clone(),
+        descriptor,
+        interpreter: ReportInterpreter::default(),
+        hid_reports: pembejeo.wants_hid_reports(&info),
+        report_buffer: vec![0; report_size],
+    };
+
+    // Setup the callbacks
+    unsafe {
+        IOHIDDeviceRegisterInputReportWithTimeStampCallback(device, iohid_device.report_buffer.as_mut_ptr(), report_size as CFIndex, Some(handle_hid_report), in_context);
+    };
+    state.devices.lock().unwrap().insert(device as usize, iohid_device);
+    pembejeo.add_device(info);
+}
+
+extern "C" fn handle_device_removal_callback(in_context: *mut c_void, _in_return: c_int, _sender: *mut c_void, device: *mut c_void) {
+    let state = unsafe { &*(in_context as *const IOHIDState) };
+
+    remove_device(state, device as usize);
+}
+
+/// Forget a matched device and announce that it is gone. Devices the filters turned down were never matched.
+fn remove_device(state: &IOHIDState, device: usize) {
+    let iohid_device = state.devices.lock().unwrap().remove(&device);
+    if let Some(iohid_device) = iohid_device {
+        state.context.remove_device(&iohid_device.info.id);
+    }
+}
+
+extern "C" fn handle_hid_report(
+    in_context: *mut c_void,
+    result: i32,
+    sender: *mut c_void,
+    _type: u32, _report_id: u32,
+    report: *mut u8,
+    report_length: CFIndex,
+    time_stamp: u64,
+) {
+    if result != 0 {
+        return;
+    }
+
+    let state = unsafe { &*(in_context as *const IOHIDState) };
+    let pembejeo = &*state.context;
+    let timestamp = mach_timestamp(time_stamp);
+
+    // The report keeps its report ID byte in front, the same as hidraw
+    let report = unsafe { slice::from_raw_parts(report, report_length as usize) };
+
+    // Interpret the whole report at once so related values end up in the same event
+    let mut devices = state.devices.lock().unwrap();
+    let Some(device) = devices.get_mut(&(sender as usize)) else {
+        return;
+    };
+    let handle = device.info.handle;
+    if let Some(descriptor) = &device.descriptor {
+        device.interpreter.handle_report(pembejeo, handle, timestamp, descriptor, report);
+    }
+    let hid_reports = device.hid_reports;
+    drop(devices);
+    if !hid_reports {
+        return;
+    }
+
+    let hid_report_event = HidReportEvent {
+        device: handle,
+        timestamp,
+        report: HidReport::new(report),
+    };
+    pembejeo.push_event(Event::HidReport(hid_report_event));
+}
+
+/// A `mach_absolute_time` reading as a `Timestamp`.
+fn mach_timestamp(time: u64) -> Timestamp {
+    use crate::apple::iohid::{mach_timebase_info, MachTimebaseInfo};
+
+    // Intel Macs tick every nanosecond, Apple silicon every 125/3 of one
+    static TIMEBASE: OnceLock<MachTimebaseInfo> = OnceLock::new();
+    let timebase = TIMEBASE.get_or_init(|| {
+        let mut timebase = MachTimebaseInfo::default();
+        unsafe { mach_timebase_info(&mut timebase) };
+        timebase
+    });
+    Timestamp::from_nanos((time as u128 * timebase.numer as u128 / timebase.denom.max(1) as u128) as u64)
+}
@@ -1 +1,4 @@
-pub mod iohid;
\ No newline at end of file
+pub mod iohid;
+mod backend;
+
+pub use backend::*;
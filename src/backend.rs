@@ -0,0 +1,183 @@
+use std::ops::Deref;
+
+use crate::{LedState, Pembejeo, Rumble};
+
+/// A platform layer that discovers devices and delivers their input into a `Pembejeo`.
+///
+/// A backend announces devices through `Pembejeo::add_device` and `Pembejeo::remove_device`, and
+/// calls `Pembejeo::push_event` from whatever thread it reads input on.
+pub trait Backend: Send {
+    /// Start device discovery and event delivery into the `Pembejeo` behind `context`.
+    fn start(&mut self, context: Context) -> Result<(), crate::Error>;
+
+    /// Stop delivering events and release any platform resources.
+    /// Called once when the owning `Pembejeo` is dropped, even if `start` failed.
+    fn shutdown(&mut self);
+
+    /// Match the devices again after `Pembejeo`'s filters changed, removing the ones they no longer
+    /// match and adding the ones they now do. Newly discovered devices are checked against
+    /// `Pembejeo::matches_device` before the backend reports them.
+    fn apply_filters(&mut self) -> Result<(), crate::Error> {
+        Ok(())
+    }
+
+    /// Send a feature report to one of this backend's devices.
+    /// Backends without raw HID access report every device as not found.
+    fn set_feature_report(&mut self, device_id: &str, _report: &[u8]) -> Result<(), crate::Error> {
+        Err(crate::Error::DeviceNotFound(device_id.to_string()))
+    }
+
+    /// Send an output report to one of this backend's devices, report ID byte first.
+    fn set_output_report(&mut self, device_id: &str, _report: &[u8]) -> Result<(), crate::Error> {
+        Err(crate::Error::DeviceNotFound(device_id.to_string()))
+    }
+
+    /// Read a feature report from one of this backend's devices, returning its length.
+    fn get_feature_report(&mut self, device_id: &str, _report: &mut [u8]) -> Result<usize, crate::Error> {
+        Err(crate::Error::DeviceNotFound(device_id.to_string()))
+    }
+
+    /// Turn the Resolution Multipliers of one of this backend's devices on or off.
+    fn set_high_resolution_scroll(&mut self, device_id: &str, _enabled: bool) -> Result<(), crate::Error> {
+        Err(crate::Error::DeviceNotFound(device_id.to_string()))
+    }
+
+    /// Play `rumble` through the platform's own force feedback, replacing the device's last one.
+    /// `Pembejeo` rumbles the devices backends don't know with HID output reports instead.
+    fn rumble(&mut self, device_id: &str, _rumble: &Rumble) -> Result<(), crate::Error> {
+        Err(crate::Error::DeviceNotFound(device_id.to_string()))
+    }
+
+    /// Light a keyboard's LEDs through the platform's own LED support.
+    /// `Pembejeo` sets the LEDs of devices backends don't know with HID output reports instead.
+    fn set_leds(&mut self, device_id: &str, _leds: LedState) -> Result<(), crate::Error> {
+        Err(crate::Error::DeviceNotFound(device_id.to_string()))
+    }
+}
+
+/// The `Pembejeo` a backend delivers into.
+///
+/// `Pembejeo` is always boxed and shuts its backend down before it is freed,
+/// so the context stays valid on any thread until `Backend::shutdown` returns.
+#[derive(Clone, Copy)]
+pub struct Context {
+    pembejeo: *const Pembejeo,
+}
+
+unsafe impl Send for Context {}
+unsafe impl Sync for Context {}
+
+impl Context {
+    pub(crate) fn new(pembejeo: &Pembejeo) -> Self {
+        Self { pembejeo }
+    }
+
+    /// The raw pointer, for platform callbacks that take a `void *` context.
+    pub fn as_ptr(&self) -> *const Pembejeo {
+        self.pembejeo
+    }
+}
+
+impl Deref for Context {
+    type Target = Pembejeo;
+
+    fn deref(&self) -> &Pembejeo {
+        unsafe { &*self.pembejeo }
+    }
+}
+
+/// A backend that finds no devices and delivers no events.
+/// Used on platforms without a native backend so the crate still builds and runs there.
+#[derive(Debug, Default)]
+pub struct NullBackend;
+
+impl Backend for NullBackend {
+    fn start(&mut self, _context: Context) -> Result<(), crate::Error> {
+        Ok(())
+    }
+
+    fn shutdown(&mut self) {}
+}
+
+/// Several backends driven as one, such as evdev and hidraw on Linux.
+/// Device requests go to the first backend that knows the device.
+pub struct CompositeBackend {
+    backends: Vec<Box<dyn Backend>>,
+}
+
+impl CompositeBackend {
+    pub fn new(backends: Vec<Box<dyn Backend>>) -> Self {
+        Self { backends }
+    }
+
+    fn first_found<T>(&mut self, device_id: &str, mut request: impl FnMut(&mut dyn Backend) -> Result<T, crate::Error>) -> Result<T, crate::Error> {
+        for backend in self.backends.iter_mut() {
+            match request(backend.as_mut()) {
+                Err(crate::Error::DeviceNotFound(_)) => continue,
+                res => return res,
+            }
+        }
+        Err(crate::Error::DeviceNotFound(device_id.to_string()))
+    }
+}
+
+impl Backend for CompositeBackend {
+    fn start(&mut self, context: Context) -> Result<(), crate::Error> {
+        for backend in self.backends.iter_mut() {
+            backend.start(context)?;
+        }
+        Ok(())
+    }
+
+    fn shutdown(&mut self) {
+        for backend in self.backends.iter_mut() {
+            backend.shutdown();
+        }
+    }
+
+    fn apply_filters(&mut self) -> Result<(), crate::Error> {
+        for backend in self.backends.iter_mut() {
+            backend.apply_filters()?;
+        }
+        Ok(())
+    }
+
+    fn set_feature_report(&mut self, device_id: &str, report: &[u8]) -> Result<(), crate::Error> {
+        self.first_found(device_id, |backend| backend.set_feature_report(device_id, report))
+    }
+
+    fn set_output_report(&mut self, device_id: &str, report: &[u8]) -> Result<(), crate::Error> {
+        self.first_found(device_id, |backend| backend.set_output_report(device_id, report))
+    }
+
+    fn get_feature_report(&mut self, device_id: &str, report: &mut [u8]) -> Result<usize, crate::Error> {
+        self.first_found(device_id, |backend| backend.get_feature_report(device_id, report))
+    }
+
+    fn set_high_resolution_scroll(&mut self, device_id: &str, enabled: bool) -> Result<(), crate::Error> {
+        self.first_found(device_id, |backend| backend.set_high_resolution_scroll(device_id, enabled))
+    }
+
+    fn rumble(&mut self, device_id: &str, rumble: &Rumble) -> Result<(), crate::Error> {
+        self.first_found(device_id, |backend| backend.rumble(device_id, rumble))
+    }
+
+    fn set_leds(&mut self, device_id: &str, leds: LedState) -> Result<(), crate::Error> {
+        self.first_found(device_id, |backend| backend.set_leds(device_id, leds))
+    }
+}
+
+/// The native backend for the current platform.
+pub fn default_backend() -> Result<Box<dyn Backend>, crate::Error> {
+    #[cfg(target_os = "macos")]
+    return Ok(Box::new(crate::apple::IOHIDBackend::new()?));
+
+    #[cfg(target_os = "linux")]
+    return Ok(Box::new(CompositeBackend::new(vec![
+        Box::new(crate::linux::EvdevBackend::new()?),
+        Box::new(crate::linux::HidrawBackend::new()?),
+    ])));
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    return Ok(Box::new(NullBackend));
+}
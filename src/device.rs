@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+
+use crate::{rumble::rumble_reports, LedState, ReportDescriptor, ReportKind, Rumble, SdlGuid, Usage};
+
+/// Identifies a device across reconnects and restarts, for keeping settings per device.
+///
+/// Made of the backend, the vendor and product ids, the serial number or where the device is
+/// attached when it has none, and what tells the device's interfaces apart. A device without a
+/// serial number gets another id on another port.
+pub type DeviceId = String;
+
+/// Identifies a device for the rest of the session, cheaper to copy and compare than its id.
+/// Events carry it, `Pembejeo::device` turns it back into the device's `DeviceInfo`. A device
+/// that reconnects gets its old handle back.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DeviceHandle(pub u32);
+
+/// Hands out a handle for every device id seen this session, starting at 1.
+#[derive(Debug, Default)]
+pub(crate) struct DeviceHandles {
+    handles: HashMap<DeviceId, DeviceHandle>,
+    /// The id of every handle, at the handle's number minus one
+    ids: Vec<DeviceId>,
+}
+
+impl DeviceHandles {
+    /// The handle of `id`, given out now if it has none yet.
+    pub(crate) fn assign(&mut self, id: &str) -> DeviceHandle {
+        if let Some(handle) = self.handles.get(id) {
+            return *handle;
+        }
+
+        self.ids.push(id.to_string());
+        let handle = DeviceHandle(self.ids.len() as u32);
+        self.handles.insert(id.to_string(), handle);
+        handle
+    }
+
+    pub(crate) fn get(&self, id: &str) -> Option<DeviceHandle> {
+        self.handles.get(id).copied()
+    }
+
+    pub(crate) fn id(&self, handle: DeviceHandle) -> Option<&str> {
+        let index = (handle.0 as usize).checked_sub(1)?;
+        self.ids.get(index).map(String::as_str)
+    }
+}
+
+/// The id of an interface of a device, see `DeviceId`.
+pub(crate) fn stable_id(backend: &str, vendor_id: u16, product_id: u16, serial_number: &str, location: &str, interface: &str) -> DeviceId {
+    let instance = if serial_number.is_empty() { location } else { serial_number };
+    format!("{}:{:04x}:{:04x}:{}:{}", backend, vendor_id, product_id, instance, interface)
+}
+
+/// `id`, with `#2`, `#3` and so on appended while `taken` says another device has it. Identical
+/// devices without serial numbers or locations, like two virtual ones, would share an id otherwise.
+pub(crate) fn unique_id(id: DeviceId, taken: impl Fn(&str) -> bool) -> DeviceId {
+    if !taken(&id) {
+        return id;
+    }
+    (2..).map(|number| format!("{}#{}", id, number)).find(|numbered| !taken(numbered)).unwrap()
+}
+
+/// What a device reports and accepts through its id. A composite device, like a keyboard with a
+/// touchpad, has the capabilities of all of its parts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Capabilities {
+    /// Moves the pointer, reported as `Event::MouseMotion` and `Event::MouseButton`
+    pub pointer: bool,
+    /// Has a wheel or pan, reported as `Event::Scroll`
+    pub scroll: bool,
+    /// Has keys, reported as `Event::KeyDown` and `Event::KeyUp`
+    pub keys: bool,
+    /// Reports contacts as `Event::Touch`
+    pub touch: bool,
+    /// Has joystick buttons, axes or hats, reported as gamepad events
+    pub gamepad: bool,
+    /// Accepts `Pembejeo::rumble`
+    pub rumble: bool,
+    /// Accepts `Pembejeo::set_leds`
+    pub leds: bool,
+}
+
+/// How a device is connected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Transport {
+    #[default]
+    Unknown,
+    Usb,
+    /// Classic Bluetooth and Bluetooth Low Energy
+    Bluetooth,
+    I2c,
+    Spi,
+    /// Created by software, such as through uinput or uhid
+    Virtual,
+}
+
+impl Transport {
+    /// The transport of a Linux `BUS_` type, which SDL uses for its GUIDs as well.
+    pub fn from_bus_type(bus_type: u16) -> Transport {
+        match bus_type {
+            0x03 => Transport::Usb,
+            0x05 => Transport::Bluetooth,
+            0x06 => Transport::Virtual,
+            0x18 => Transport::I2c,
+            0x1C => Transport::Spi,
+            _ => Transport::Unknown,
+        }
+    }
+
+    /// The Linux `BUS_` type of the transport, 0 when it is unknown.
+    pub fn bus_type(&self) -> u16 {
+        match self {
+            Transport::Unknown => 0x00,
+            Transport::Usb => 0x03,
+            Transport::Bluetooth => 0x05,
+            Transport::Virtual => 0x06,
+            Transport::I2c => 0x18,
+            Transport::Spi => 0x1C,
+        }
+    }
+}
+
+/// A connected device, as `Event::DeviceAdded` announces it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DeviceInfo {
+    pub id: DeviceId,
+    /// Given out by `Pembejeo::add_device`
+    pub handle: DeviceHandle,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    /// The release number, in binary-coded decimal for USB devices
+    pub version: u16,
+    pub transport: Transport,
+    /// Where the device is attached: its physical path on Linux, its IOKit location ID on macOS
+    pub location: String,
+    /// The usages of the device's top-level collections, such as Generic Desktop Mouse
+    pub usages: Vec<Usage>,
+    pub capabilities: Capabilities,
+
+    pub product: String,
+    pub manufacturer: String,
+    pub serial_number: String,
+
+    /// What SDL mappings for a gamepad are filed under
+    pub guid: Option<SdlGuid>,
+    /// The HID report descriptor, empty for devices that aren't reached as plain HID
+    pub report_descriptor: Vec<u8>,
+}
+
+impl DeviceInfo {
+    pub fn parse_report_descriptor(&self) -> Result<ReportDescriptor, crate::Error> {
+        ReportDescriptor::parse(&self.report_descriptor)
+    }
+
+    /// What the report descriptor declares, or nothing when it can't be parsed.
+    pub fn report_capabilities(&self) -> Capabilities {
+        let Ok(descriptor) = self.parse_report_descriptor() else {
+            return Capabilities::default();
+        };
+        let has_input = |usage: Usage| descriptor.fields(ReportKind::Input).any(|field| field.has_usage(usage));
+
+        let capabilities = Capabilities {
+            pointer: descriptor.fields(ReportKind::Input).any(|field| field.flags.is_relative() && field.has_usage(Usage::new(0x01, 0x30))),
+            scroll: has_input(Usage::new(0x01, 0x38)) || has_input(Usage::new(0x0C, 0x238)),
+            keys: descriptor.fields(ReportKind::Input).any(|field| field.usages.iter().any(|range| range.page == 0x07)),
+            // Contact Identifier
+            touch: has_input(Usage::new(0x0D, 0x51)),
+            // Joysticks, gamepads and multi-axis controllers
+            gamepad: descriptor.application_usages().iter().any(|usage| usage.page == 0x01 && matches!(usage.id, 0x04 | 0x05 | 0x08)),
+            rumble: rumble_reports(self, &descriptor, &Rumble::default()).is_ok(),
+            leds: descriptor.led_report(&LedState::default()).is_some(),
+        };
+        capabilities
+    }
+
+    pub fn is_mouse(&self) -> bool {
+        self.capabilities.pointer
+    }
+
+    pub fn is_keyboard(&self) -> bool {
+        self.capabilities.keys
+    }
+
+    pub fn is_gamepad(&self) -> bool {
+        self.capabilities.gamepad
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{stable_id, unique_id, DeviceHandle, DeviceHandles};
+    use crate::{hid::fixtures::{BOOT_KEYBOARD, BOOT_MOUSE, GAMEPAD, HIGH_RESOLUTION_MOUSE, PID_JOYSTICK, TOUCHPAD}, Capabilities, DeviceInfo, Event, NullBackend, Pembejeo};
+
+    #[test]
+    fn stable_ids() {
+        // The serial number wins over the location
+        assert_eq!(stable_id("iohid", 0x054C, 0x09CC, "a0:ab:51:00:00:01", "0x14100000", "0001:0005"), "iohid:054c:09cc:a0:ab:51:00:00:01:0001:0005");
+        assert_eq!(stable_id("hidraw", 0x046D, 0xC52B, "", "usb-0000:00:14.0-2/input0", ""), "hidraw:046d:c52b:usb-0000:00:14.0-2/input0:");
+
+        let taken = ["evdev:1234:5678::mouse".to_string(), "evdev:1234:5678::mouse#2".to_string()];
+        assert_eq!(unique_id("evdev:1234:5678::pad".to_string(), |id| taken.iter().any(|taken| taken == id)), "evdev:1234:5678::pad");
+        assert_eq!(unique_id("evdev:1234:5678::mouse".to_string(), |id| taken.iter().any(|taken| taken == id)), "evdev:1234:5678::mouse#3");
+    }
+
+    #[test]
+    fn handles() {
+        let mut handles = DeviceHandles::default();
+        assert_eq!(handles.assign("mouse"), DeviceHandle(1));
+        assert_eq!(handles.assign("keyboard"), DeviceHandle(2));
+        assert_eq!(handles.assign("mouse"), DeviceHandle(1));
+
+        assert_eq!(handles.get("keyboard"), Some(DeviceHandle(2)));
+        assert_eq!(handles.get("pad"), None);
+        assert_eq!(handles.id(DeviceHandle(2)), Some("keyboard"));
+        assert_eq!(handles.id(DeviceHandle::default()), None);
+        assert_eq!(handles.id(DeviceHandle(3)), None);
+    }
+
+    #[test]
+    fn hotplug_events() {
+        let pembejeo = Pembejeo::with_backend(Box::new(NullBackend)).unwrap();
+        let handle = pembejeo.add_device(DeviceInfo { id: "mouse".to_string(), ..Default::default() });
+        pembejeo.remove_device("mouse");
+        // Only devices that were added are announced as removed
+        pembejeo.remove_device("keyboard");
+
+        let mut event = Event::default();
+        assert!(pembejeo.poll(&mut event));
+        assert!(matches!(&event, Event::DeviceAdded(added) if added.info.handle == handle && added.info.id == "mouse"));
+        assert!(pembejeo.poll(&mut event));
+        assert!(matches!(&event, Event::DeviceRemoved(removed) if removed.device == handle && removed.id == "mouse"));
+        assert!(!pembejeo.poll(&mut event));
+        assert_eq!(pembejeo.device_handle("mouse"), Some(handle));
+    }
+
+    fn capabilities(report_descriptor: &[u8]) -> Capabilities {
+        DeviceInfo {
+            vendor_id: 0x1234,
+            product_id: 0x5678,
+            report_descriptor: report_descriptor.to_vec(),
+            ..Default::default()
+        }.report_capabilities()
+    }
+
+    #[test]
+    fn descriptor_capabilities() {
+        assert_eq!(capabilities(&BOOT_MOUSE), Capabilities { pointer: true, ..Default::default() });
+        assert_eq!(capabilities(&BOOT_KEYBOARD), Capabilities { keys: true, leds: true, ..Default::default() });
+        assert_eq!(capabilities(&GAMEPAD), Capabilities { gamepad: true, ..Default::default() });
+        assert!(capabilities(&HIGH_RESOLUTION_MOUSE).scroll);
+        assert!(capabilities(&TOUCHPAD).touch);
+        assert!(capabilities(&PID_JOYSTICK).rumble);
+        assert_eq!(capabilities(&[0xFF]), Capabilities::default());
+    }
+}
@@ -1,6 +1,14 @@
 #[derive(Debug)]
 pub enum Error {
     FailedCreatingPembejeo(std::string::String),
+    DeviceNotFound(std::string::String),
+    NotSupported(std::string::String),
+    FailedSettingReport(std::string::String),
+    FailedGettingReport(std::string::String),
+    InvalidReportDescriptor(std::string::String),
+    InvalidReport(std::string::String),
+    InvalidMapping(std::string::String),
+    FailedPlayingEffect(std::string::String),
 }
 
 
@@ -10,6 +18,14 @@ impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::FailedCreatingPembejeo(message) => write!(f, "Failed creating pembejeo: {}", message),
+            Self::DeviceNotFound(id) => write!(f, "Device not found: {}", id),
+            Self::NotSupported(message) => write!(f, "Not supported: {}", message),
+            Self::FailedSettingReport(message) => write!(f, "Failed setting report: {}", message),
+            Self::FailedGettingReport(message) => write!(f, "Failed getting report: {}", message),
+            Self::InvalidReportDescriptor(message) => write!(f, "Invalid report descriptor: {}", message),
+            Self::InvalidReport(message) => write!(f, "Invalid report: {}", message),
+            Self::InvalidMapping(message) => write!(f, "Invalid gamepad mapping: {}", message),
+            Self::FailedPlayingEffect(message) => write!(f, "Failed playing force feedback effect: {}", message),
         }
     }
 }
@@ -1,17 +1,344 @@
-use std::default;
+use std::{fmt, ops::Deref};
+
+use crate::{DeviceHandle, DeviceInfo, GamepadAxis, GamepadButton, GestureEvent, HatDirection, KeyCode, LedState, MouseButton, Timestamp};
 
 #[derive(Debug, Default, Clone, PartialEq)]
 pub enum Event {
     #[default]
     Empty,
 
-    MouseMotion(MouseMotionEvent)
+    /// A device was connected, or was already there when the backend started. It comes before
+    /// any of the device's input.
+    DeviceAdded(DeviceEvent),
+    /// A device was disconnected, after the last of its input.
+    DeviceRemoved(DeviceRemovedEvent),
+
+    MouseMotion(MouseMotionEvent),
+    MouseButton(MouseButtonEvent),
+    Scroll(ScrollEvent),
+    Touch(TouchEvent),
+    TouchFrame(TouchFrameEvent),
+    Gesture(GestureEvent),
+    KeyDown(KeyEvent),
+    KeyUp(KeyEvent),
+    LedChanged(LedEvent),
+    GamepadButton(GamepadButtonEvent),
+    GamepadAxis(GamepadAxisEvent),
+    GamepadHat(GamepadHatEvent),
+    HidReport(HidReportEvent),
+
+    /// The queue was full and `dropped` events were lost before the next one. See `OverflowPolicy`.
+    Overflow { dropped: usize },
+    /// The backend stopped on an error, so no more input or hotplug will arrive from it.
+    BackendFailed { message: String },
+}
+
+impl Event {
+    /// When the event happened, `None` for `Event::Empty`, `Event::Overflow` and `Event::BackendFailed`.
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        match self {
+            Event::Empty | Event::Overflow { .. } | Event::BackendFailed { .. } => None,
+            Event::DeviceAdded(device_event) => Some(device_event.timestamp),
+            Event::DeviceRemoved(removed_event) => Some(removed_event.timestamp),
+            Event::MouseMotion(mouse_motion_event) => Some(mouse_motion_event.timestamp),
+            Event::MouseButton(mouse_button_event) => Some(mouse_button_event.timestamp),
+            Event::Scroll(scroll_event) => Some(scroll_event.timestamp),
+            Event::Touch(touch_event) => Some(touch_event.timestamp),
+            Event::TouchFrame(frame_event) => Some(frame_event.timestamp),
+            Event::Gesture(gesture_event) => Some(gesture_event.timestamp),
+            Event::KeyDown(key_event) | Event::KeyUp(key_event) => Some(key_event.timestamp),
+            Event::LedChanged(led_event) => Some(led_event.timestamp),
+            Event::GamepadButton(button_event) => Some(button_event.timestamp),
+            Event::GamepadAxis(axis_event) => Some(axis_event.timestamp),
+            Event::GamepadHat(hat_event) => Some(hat_event.timestamp),
+            Event::HidReport(hid_report_event) => Some(hid_report_event.timestamp),
+        }
+    }
+
+    /// The device the event came from, `None` for `Event::Empty`, `Event::Overflow` and `Event::BackendFailed`.
+    pub fn device(&self) -> Option<DeviceHandle> {
+        match self {
+            Event::Empty | Event::Overflow { .. } | Event::BackendFailed { .. } => None,
+            Event::DeviceAdded(device_event) => Some(device_event.info.handle),
+            Event::DeviceRemoved(removed_event) => Some(removed_event.device),
+            Event::MouseMotion(mouse_motion_event) => Some(mouse_motion_event.device),
+            Event::MouseButton(mouse_button_event) => Some(mouse_button_event.device),
+            Event::Scroll(scroll_event) => Some(scroll_event.device),
+            Event::Touch(touch_event) => Some(touch_event.device),
+            Event::TouchFrame(frame_event) => Some(frame_event.device),
+            Event::Gesture(gesture_event) => Some(gesture_event.device),
+            Event::KeyDown(key_event) | Event::KeyUp(key_event) => Some(key_event.device),
+            Event::LedChanged(led_event) => Some(led_event.device),
+            Event::GamepadButton(button_event) => Some(button_event.device),
+            Event::GamepadAxis(axis_event) => Some(axis_event.device),
+            Event::GamepadHat(hat_event) => Some(hat_event.device),
+            Event::HidReport(hid_report_event) => Some(hid_report_event.device),
+        }
+    }
 }
 
+/// A device being connected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceEvent {
+    pub info: DeviceInfo,
+    pub timestamp: Timestamp,
+}
 
+/// A device being disconnected. `Pembejeo::devices` no longer has it, but the handle stays its
+/// own should it come back.
 #[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceRemovedEvent {
+    pub device: DeviceHandle,
+    /// The id the device had in `Pembejeo::devices`
+    pub id: String,
+    pub timestamp: Timestamp,
+}
+
+/// Everything one report or evdev frame says about pointer motion.
+#[derive(Debug, Default, Clone, PartialEq)]
 pub struct MouseMotionEvent {
-    pub device_id: String,
-    pub x: i16,
-    pub y: i16,
-}
\ No newline at end of file
+    pub device: DeviceHandle,
+    pub timestamp: Timestamp,
+    pub x: i32,
+    pub y: i32,
+
+    /// `x` and `y` in millimetres, when the report descriptor gives the axes a physical range and a length unit
+    pub scaled: Option<ScaledMotion>,
+    /// Where the pointer is, for devices that report an absolute position like tablets and the
+    /// pointers of virtual machines. `x` and `y` stay 0 for them.
+    pub position: Option<PointerPosition>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ScaledMotion {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// An absolute pointer position.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PointerPosition {
+    /// Across the surface from 0.0 to 1.0, left to right
+    pub x: f64,
+    /// Across the surface from 0.0 to 1.0, top to bottom
+    pub y: f64,
+    /// The position in millimetres, when the report descriptor gives the surface a physical size
+    pub millimetres: Option<ScaledMotion>,
+}
+
+impl MouseMotionEvent {
+    pub fn has_motion(&self) -> bool {
+        self.x != 0 || self.y != 0 || self.position.is_some()
+    }
+
+    /// Clear the deltas, keeping the device.
+    pub fn reset(&mut self) {
+        *self = MouseMotionEvent {
+            device: self.device,
+            ..Default::default()
+        };
+    }
+}
+
+/// Everything one report or evdev frame says about scrolling.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ScrollEvent {
+    pub device: DeviceHandle,
+    pub timestamp: Timestamp,
+
+    /// Whole detents, positive away from the user
+    pub vertical: i32,
+    /// Whole detents, positive to the right
+    pub horizontal: i32,
+
+    /// The vertical distance in 120ths of a detent. Wheels without high resolution scrolling
+    /// move in multiples of 120.
+    pub vertical_v120: i32,
+    /// The horizontal distance in 120ths of a detent
+    pub horizontal_v120: i32,
+}
+
+impl ScrollEvent {
+    pub fn has_scroll(&self) -> bool {
+        self.vertical_v120 != 0 || self.horizontal_v120 != 0
+    }
+
+    /// Clear the distances, keeping the device.
+    pub fn reset(&mut self) {
+        *self = ScrollEvent {
+            device: self.device,
+            ..Default::default()
+        };
+    }
+}
+
+/// One contact on a touch surface changing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TouchEvent {
+    pub device: DeviceHandle,
+    pub timestamp: Timestamp,
+    /// The frame the contact was reported in. A `TouchFrame` with the same number follows the frame's last contact.
+    pub frame: u64,
+    /// Identifies the contact while it touches the surface, the device reuses ids afterwards
+    pub contact_id: u32,
+    pub phase: TouchPhase,
+
+    /// Position across the surface from 0.0 to 1.0, left to right
+    pub x: f64,
+    /// Position across the surface from 0.0 to 1.0, top to bottom
+    pub y: f64,
+    /// The position in millimetres, when the report descriptor gives the surface a physical size
+    pub millimetres: Option<ScaledMotion>,
+
+    /// Contact width in millimetres, when the device reports it with a unit
+    pub width: Option<f64>,
+    /// Contact height in millimetres, when the device reports it with a unit
+    pub height: Option<f64>,
+    /// From 0.0 to 1.0, when the device reports it
+    pub pressure: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TouchPhase {
+    Began,
+    Moved,
+    Ended,
+    /// The device decided the contact isn't a finger, e.g. a resting palm
+    Cancelled,
+}
+
+/// The end of a touch frame, after the `Touch` events of every contact that changed in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TouchFrameEvent {
+    pub device: DeviceHandle,
+    pub timestamp: Timestamp,
+    pub frame: u64,
+    /// Contacts on the surface once the frame is applied
+    pub contacts: u32,
+}
+
+/// A mouse button being pressed or released.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MouseButtonEvent {
+    pub device: DeviceHandle,
+    pub timestamp: Timestamp,
+    pub button: MouseButton,
+    pub pressed: bool,
+}
+
+/// A gamepad button being pressed or released.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GamepadButtonEvent {
+    pub device: DeviceHandle,
+    pub timestamp: Timestamp,
+    pub button: GamepadButton,
+    pub pressed: bool,
+}
+
+/// A gamepad axis moving.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GamepadAxisEvent {
+    pub device: DeviceHandle,
+    pub timestamp: Timestamp,
+    pub axis: GamepadAxis,
+    /// From -1.0 to 1.0 for sticks, positive right and down, and from 0.0 to 1.0 for triggers
+    pub value: f64,
+}
+
+/// A hat switch of a gamepad without a mapping changing direction. The first hat also presses and
+/// releases the d-pad buttons.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GamepadHatEvent {
+    pub device: DeviceHandle,
+    pub timestamp: Timestamp,
+    /// The hat's number, from 0
+    pub hat: u16,
+    pub direction: HatDirection,
+}
+
+/// A key changing state on one keyboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub device: DeviceHandle,
+    pub timestamp: Timestamp,
+    /// The Keyboard/Keypad page (0x07) usage
+    pub usage: u16,
+    pub key: KeyCode,
+}
+
+/// The LEDs of a keyboard changing, whether the system or `Pembejeo::set_leds` changed them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LedEvent {
+    pub device: DeviceHandle,
+    pub timestamp: Timestamp,
+    pub leds: LedState,
+}
+
+/// An input report exactly as the device sent it, including the report ID byte if the device uses them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HidReportEvent {
+    pub device: DeviceHandle,
+    pub timestamp: Timestamp,
+    pub report: HidReport,
+}
+
+/// The bytes of a report, kept inline so reporting one doesn't allocate.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HidReport {
+    bytes: [u8; HidReport::CAPACITY],
+    len: u8,
+    truncated: bool,
+}
+
+impl HidReport {
+    /// As many bytes as fit without making an `Event` larger than one announcing a device.
+    /// Longer reports are cut off.
+    pub const CAPACITY: usize = 192;
+
+    pub fn new(report: &[u8]) -> Self {
+        let len = report.len().min(Self::CAPACITY);
+        let mut bytes = [0; Self::CAPACITY];
+        bytes[..len].copy_from_slice(&report[..len]);
+        HidReport { bytes, len: len as u8, truncated: report.len() > Self::CAPACITY }
+    }
+
+    /// Whether the device sent more than `CAPACITY` bytes, the rest of which are missing.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+impl Deref for HidReport {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+}
+
+impl fmt::Debug for HidReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::size_of;
+
+    use super::{DeviceEvent, HidReport, HidReportEvent};
+
+    #[test]
+    fn inline_reports() {
+        let report = HidReport::new(&[0x01, 0xDE, 0xAD]);
+        assert_eq!(*report, [0x01, 0xDE, 0xAD]);
+        assert!(!report.is_truncated());
+
+        let long = HidReport::new(&[0xFF; 200]);
+        assert_eq!(long.len(), HidReport::CAPACITY);
+        assert!(long.is_truncated());
+
+        // Reports don't make every queued event larger
+        assert!(size_of::<HidReportEvent>() <= size_of::<DeviceEvent>());
+    }
+}
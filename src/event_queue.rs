@@ -0,0 +1,584 @@
+use std::{cell::UnsafeCell, hint, mem::MaybeUninit, sync::{atomic::{fence, AtomicBool, AtomicUsize, Ordering}, Condvar, Mutex}, time::Instant};
+
+use crate::{Event, ScaledMotion};
+
+/// How many events a `Pembejeo` keeps unless its builder says otherwise.
+pub(crate) const DEFAULT_EVENT_CAPACITY: usize = 4096;
+
+/// What happens to an event pushed while the queue is full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum OverflowPolicy {
+    /// Drop the oldest event to make room.
+    DropOldest,
+    /// Drop the event being pushed.
+    DropNewest,
+    /// Add mouse motion and scrolling to the latest event when that is motion or scrolling of the
+    /// same device, so no clicks or keys are lost to them. Other events drop the oldest.
+    #[default]
+    CoalesceMotion,
+    /// Make the backend wait until the application takes an event. While the application changes
+    /// the filters, reaches a device through the backend, or sets up gestures or gamepad mappings,
+    /// the backend drops the oldest instead, and so does it once the `Pembejeo` is dropped.
+    Block,
+}
+
+/// The events backends push for the application, which blocks on it while there are none.
+///
+/// A lock-free ring buffer allocated once at its capacity, so pushing and taking never allocate.
+/// The lock is only taken to sleep until there is an event or room for one, and to wake sleepers.
+pub(crate) struct EventQueue {
+    ring: Ring,
+    policy: OverflowPolicy,
+    /// Dropped since the application last saw an `Event::Overflow`
+    dropped: AtomicUsize,
+    /// Where the last event `OverflowPolicy::DropNewest` dropped would have been queued
+    dropped_at: AtomicUsize,
+
+    sleep: Mutex<()>,
+    /// Signalled for events pushed and wakes while `waiting` is above zero
+    ready: Condvar,
+    waiting: AtomicUsize,
+    /// Set by `wake` until a waiter returns for it
+    woken: AtomicBool,
+
+    /// Signalled for events taken while `blocked` producers wait on a full queue
+    space: Condvar,
+    blocked: AtomicUsize,
+    /// While above zero, a full queue drops events even with `OverflowPolicy::Block`
+    unblocked: AtomicUsize,
+    closed: AtomicBool,
+}
+
+impl EventQueue {
+    pub(crate) fn new(capacity: usize, policy: OverflowPolicy) -> Self {
+        EventQueue {
+            ring: Ring::new(capacity.max(1)),
+            policy,
+            dropped: AtomicUsize::new(0),
+            dropped_at: AtomicUsize::new(0),
+
+            sleep: Mutex::new(()),
+            ready: Condvar::new(),
+            waiting: AtomicUsize::new(0),
+            woken: AtomicBool::new(false),
+
+            space: Condvar::new(),
+            blocked: AtomicUsize::new(0),
+            unblocked: AtomicUsize::new(0),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn push(&self, mut event: Event) {
+        loop {
+            event = match self.ring.push(event) {
+                None => break,
+                Some(event) => event,
+            };
+
+            match self.policy {
+                OverflowPolicy::DropNewest => {
+                    self.dropped_at.store(self.ring.tail.load(Ordering::SeqCst), Ordering::SeqCst);
+                    self.dropped.fetch_add(1, Ordering::SeqCst);
+                    return;
+                },
+                OverflowPolicy::CoalesceMotion if self.ring.coalesce_latest(&event) => return,
+                OverflowPolicy::Block if !self.unblocked() => {
+                    let guard = self.sleep.lock().unwrap();
+                    self.blocked.fetch_add(1, Ordering::SeqCst);
+                    // Taking an event after this sees `blocked` and signals, so it can't be missed
+                    event = match self.ring.push(event) {
+                        None => {
+                            self.blocked.fetch_sub(1, Ordering::SeqCst);
+                            break;
+                        },
+                        Some(event) => event,
+                    };
+                    if !self.unblocked() {
+                        drop(self.space.wait(guard).unwrap());
+                    }
+                    self.blocked.fetch_sub(1, Ordering::SeqCst);
+                },
+                _ => {
+                    // Make room, unless the application just did
+                    if self.ring.pop().is_some() {
+                        self.dropped.fetch_add(1, Ordering::SeqCst);
+                    }
+                },
+            }
+        }
+
+        fence(Ordering::SeqCst);
+        if self.waiting.load(Ordering::SeqCst) > 0 {
+            drop(self.sleep.lock().unwrap());
+            self.ready.notify_one();
+        }
+    }
+
+    pub(crate) fn pop(&self) -> Option<Event> {
+        self.take()
+    }
+
+    /// Take the oldest event, blocking until there is one. `None` when `deadline` passes or `wake`
+    /// is called first.
+    pub(crate) fn wait(&self, deadline: Option<Instant>) -> Option<Event> {
+        loop {
+            if let Some(event) = self.take() {
+                return Some(event);
+            }
+            if self.woken.swap(false, Ordering::SeqCst) {
+                return None;
+            }
+
+            let guard = self.sleep.lock().unwrap();
+            self.waiting.fetch_add(1, Ordering::SeqCst);
+            // Pushing after this sees `waiting` and signals, so the event can't be missed
+            let event = self.take();
+            let now = Instant::now();
+            let expired = deadline.is_some_and(|deadline| now >= deadline);
+            if event.is_none() && !expired && !self.woken.load(Ordering::SeqCst) {
+                match deadline {
+                    Some(deadline) => drop(self.ready.wait_timeout(guard, deadline - now).unwrap()),
+                    None => drop(self.ready.wait(guard).unwrap()),
+                }
+            }
+            self.waiting.fetch_sub(1, Ordering::SeqCst);
+
+            if event.is_some() || expired {
+                return event;
+            }
+        }
+    }
+
+    /// Make a blocked `wait` return `None`, or the next one when nothing is waiting.
+    pub(crate) fn wake(&self) {
+        self.woken.store(true, Ordering::SeqCst);
+        drop(self.sleep.lock().unwrap());
+        // Every waiter checks, the first one takes it
+        self.ready.notify_all();
+    }
+
+    /// Run `f` without blocking producers, for when the application waits on a backend thread
+    /// that may be blocked on the queue.
+    pub(crate) fn without_blocking<T>(&self, f: impl FnOnce() -> T) -> T {
+        /// Blocks producers again once `f` returns or panics.
+        struct Unblocked<'a>(&'a EventQueue);
+
+        impl Drop for Unblocked<'_> {
+            fn drop(&mut self) {
+                self.0.unblocked.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+
+        self.unblocked.fetch_add(1, Ordering::SeqCst);
+        self.release_producers();
+        let _unblocked = Unblocked(self);
+        f()
+    }
+
+    /// Stop blocking producers for good, before the backend is shut down.
+    pub(crate) fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.release_producers();
+    }
+
+    fn unblocked(&self) -> bool {
+        self.unblocked.load(Ordering::SeqCst) > 0 || self.closed.load(Ordering::SeqCst)
+    }
+
+    fn release_producers(&self) {
+        drop(self.sleep.lock().unwrap());
+        self.space.notify_all();
+    }
+
+    /// The next event, announcing the events dropped before it first.
+    fn take(&self) -> Option<Event> {
+        // The newest events are dropped after everything still queued, the oldest before it
+        let dropped_before = self.policy != OverflowPolicy::DropNewest
+            || self.ring.head.load(Ordering::SeqCst) >= self.dropped_at.load(Ordering::SeqCst);
+        if dropped_before {
+            let dropped = self.dropped.swap(0, Ordering::SeqCst);
+            if dropped > 0 {
+                return Some(Event::Overflow { dropped });
+            }
+        }
+
+        let event = self.ring.pop()?;
+        fence(Ordering::SeqCst);
+        if self.blocked.load(Ordering::SeqCst) > 0 {
+            drop(self.sleep.lock().unwrap());
+            self.space.notify_one();
+        }
+        Some(event)
+    }
+}
+
+/// A bounded multi-producer multi-consumer queue after Dmitry Vyukov's, where every slot counts
+/// which position it holds. Slots can also be claimed in place, to add motion to queued events.
+struct Ring {
+    slots: Box<[Slot]>,
+    /// The position of the next event to take
+    head: AtomicUsize,
+    /// The position of the next event to push
+    tail: AtomicUsize,
+}
+
+struct Slot {
+    /// `2 * position` while free for the event at `position`, `2 * position + 1` once it holds
+    /// it, and `CLAIMED` while someone takes or changes the event
+    sequence: AtomicUsize,
+    event: UnsafeCell<MaybeUninit<Event>>,
+}
+
+const CLAIMED: usize = usize::MAX;
+
+// Slots hand their event over to one thread at a time, through `sequence`
+unsafe impl Sync for Ring {}
+
+impl Ring {
+    fn new(capacity: usize) -> Self {
+        Ring {
+            slots: (0..capacity).map(|position| Slot {
+                sequence: AtomicUsize::new(2 * position),
+                event: UnsafeCell::new(MaybeUninit::uninit()),
+            }).collect(),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    fn slot(&self, position: usize) -> &Slot {
+        &self.slots[position % self.slots.len()]
+    }
+
+    /// Add `event` at the end, or hand it back when the ring is full.
+    fn push(&self, event: Event) -> Option<Event> {
+        let mut position = self.tail.load(Ordering::Relaxed);
+        loop {
+            let slot = self.slot(position);
+            let sequence = slot.sequence.load(Ordering::Acquire);
+            if sequence == 2 * position {
+                match self.tail.compare_exchange_weak(position, position + 1, Ordering::Relaxed, Ordering::Relaxed) {
+                    Ok(_) => {
+                        unsafe { (*slot.event.get()).write(event) };
+                        slot.sequence.store(2 * position + 1, Ordering::Release);
+                        return None;
+                    },
+                    Err(tail) => position = tail,
+                }
+            } else if sequence == CLAIMED {
+                // Being taken, which makes room, or changed in place
+                hint::spin_loop();
+                position = self.tail.load(Ordering::Relaxed);
+            } else if sequence < 2 * position {
+                // Still holds the event a lap behind
+                return Some(event);
+            } else {
+                position = self.tail.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Take the event at the front.
+    fn pop(&self) -> Option<Event> {
+        let mut position = self.head.load(Ordering::Acquire);
+        loop {
+            let slot = self.slot(position);
+            let sequence = slot.sequence.load(Ordering::Acquire);
+            if sequence == 2 * position + 1 {
+                if slot.sequence.compare_exchange_weak(sequence, CLAIMED, Ordering::Acquire, Ordering::Relaxed).is_ok() {
+                    self.head.store(position + 1, Ordering::Release);
+                    let event = unsafe { (*slot.event.get()).assume_init_read() };
+                    slot.sequence.store(2 * (position + self.slots.len()), Ordering::Release);
+                    return Some(event);
+                }
+            } else if sequence == CLAIMED {
+                hint::spin_loop();
+            } else if sequence < 2 * position + 1 {
+                // Empty, or the event is still being written
+                return None;
+            }
+            position = self.head.load(Ordering::Acquire);
+        }
+    }
+
+    /// Add `event` to the latest queued event, when both are motion or both scrolling of the same
+    /// device. Merging into anything older would put the queue out of order.
+    fn coalesce_latest(&self, event: &Event) -> bool {
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        if tail == head || event.device().is_none() {
+            return false;
+        }
+
+        // Claimed like for taking it, so nothing else reads the event meanwhile
+        let position = tail - 1;
+        let slot = self.slot(position);
+        let sequence = 2 * position + 1;
+        if slot.sequence.compare_exchange(sequence, CLAIMED, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            // Taken or still being written
+            return false;
+        }
+
+        let latest = unsafe { (*slot.event.get()).assume_init_mut() };
+        let merged = latest.device() == event.device() && merge(latest, event);
+        slot.sequence.store(sequence, Ordering::Release);
+        merged
+    }
+}
+
+impl Drop for Ring {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+/// Add motion or scrolling to the same kind of event.
+fn merge(latest: &mut Event, event: &Event) -> bool {
+    match (latest, event) {
+        (Event::MouseMotion(latest), Event::MouseMotion(motion)) => {
+            latest.timestamp = motion.timestamp;
+            latest.x = latest.x.saturating_add(motion.x);
+            latest.y = latest.y.saturating_add(motion.y);
+            latest.scaled = match (latest.scaled, motion.scaled) {
+                (Some(latest), Some(scaled)) => Some(ScaledMotion { x: latest.x + scaled.x, y: latest.y + scaled.y }),
+                (latest, scaled) => latest.or(scaled),
+            };
+            latest.position = motion.position.or(latest.position);
+            true
+        },
+        (Event::Scroll(latest), Event::Scroll(scroll)) => {
+            latest.timestamp = scroll.timestamp;
+            latest.vertical = latest.vertical.saturating_add(scroll.vertical);
+            latest.horizontal = latest.horizontal.saturating_add(scroll.horizontal);
+            latest.vertical_v120 = latest.vertical_v120.saturating_add(scroll.vertical_v120);
+            latest.horizontal_v120 = latest.horizontal_v120.saturating_add(scroll.horizontal_v120);
+            true
+        },
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::atomic::Ordering, thread, time::{Duration, Instant}};
+
+    use super::{EventQueue, OverflowPolicy};
+    use crate::{DeviceHandle, DeviceInfo, Event, GamepadButton, GamepadButtonEvent, HidReport, HidReportEvent, JoystickInput, MouseMotionEvent, NullBackend, Pembejeo, SdlGuid, Timestamp};
+
+    fn report(device: u32) -> Event {
+        Event::HidReport(HidReportEvent { device: DeviceHandle(device), timestamp: Timestamp::default(), report: HidReport::new(&[1]) })
+    }
+
+    fn motion(device: u32, x: i32, nanos: u64) -> Event {
+        Event::MouseMotion(MouseMotionEvent {
+            device: DeviceHandle(device),
+            timestamp: Timestamp::from_nanos(nanos),
+            x,
+            ..Default::default()
+        })
+    }
+
+    fn drain(queue: &EventQueue) -> Vec<Event> {
+        std::iter::from_fn(|| queue.pop()).collect()
+    }
+
+    #[test]
+    fn blocking() {
+        let queue = EventQueue::new(16, OverflowPolicy::DropOldest);
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                thread::sleep(Duration::from_millis(20));
+                queue.push(report(1));
+                queue.push(report(2));
+            });
+
+            // Events come out in order, the first one after the wait
+            assert_eq!(queue.wait(None), Some(report(1)));
+        });
+        assert_eq!(queue.pop(), Some(report(2)));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn deadlines() {
+        let queue = EventQueue::new(16, OverflowPolicy::DropOldest);
+        let start = Instant::now();
+        assert_eq!(queue.wait(Some(start + Duration::from_millis(20))), None);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        // Pending events don't wait at all, even past the deadline
+        queue.push(report(1));
+        assert_eq!(queue.wait(Some(start)), Some(report(1)));
+    }
+
+    #[test]
+    fn wake() {
+        let queue = EventQueue::new(16, OverflowPolicy::DropOldest);
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                thread::sleep(Duration::from_millis(20));
+                queue.wake();
+            });
+            assert_eq!(queue.wait(None), None);
+        });
+
+        // A wake nobody waited for ends the next wait, once
+        queue.wake();
+        assert_eq!(queue.wait(None), None);
+        assert_eq!(queue.wait(Some(Instant::now())), None);
+        queue.push(report(1));
+        assert_eq!(queue.wait(None), Some(report(1)));
+    }
+
+    #[test]
+    fn overflow_policies() {
+        let queue = EventQueue::new(2, OverflowPolicy::DropOldest);
+        for device in [1, 2, 3, 4] {
+            queue.push(report(device));
+        }
+        assert_eq!(drain(&queue), vec![Event::Overflow { dropped: 2 }, report(3), report(4)]);
+
+        // The newest events are lost after the queued ones
+        let queue = EventQueue::new(2, OverflowPolicy::DropNewest);
+        for device in [1, 2, 3] {
+            queue.push(report(device));
+        }
+        assert_eq!(queue.pop(), Some(report(1)));
+        queue.push(report(4));
+        queue.push(report(5));
+        assert_eq!(drain(&queue), vec![report(2), report(4), Event::Overflow { dropped: 2 }]);
+
+        // Motion adds up while it is the latest event
+        let queue = EventQueue::new(2, OverflowPolicy::CoalesceMotion);
+        queue.push(report(2));
+        queue.push(motion(1, 1, 1));
+        queue.push(motion(1, 2, 2));
+        queue.push(motion(1, 4, 3));
+        assert_eq!(drain(&queue), vec![report(2), motion(1, 7, 3)]);
+
+        // Anything else after it keeps the order by dropping the oldest, even another device's motion
+        queue.push(motion(1, 1, 1));
+        queue.push(report(1));
+        queue.push(motion(1, 2, 2));
+        queue.push(motion(2, 4, 3));
+        assert_eq!(drain(&queue), vec![Event::Overflow { dropped: 2 }, motion(1, 2, 2), motion(2, 4, 3)]);
+    }
+
+    #[test]
+    fn blocking_producers() {
+        let queue = EventQueue::new(1, OverflowPolicy::Block);
+        queue.push(report(1));
+        thread::scope(|scope| {
+            let producer = scope.spawn(|| queue.push(report(2)));
+            thread::sleep(Duration::from_millis(20));
+            assert!(!producer.is_finished());
+
+            // Taking an event makes room
+            assert_eq!(queue.pop(), Some(report(1)));
+            producer.join().unwrap();
+        });
+        assert_eq!(queue.pop(), Some(report(2)));
+
+        // Filters and shutdown don't wait for the application
+        queue.push(report(3));
+        queue.without_blocking(|| queue.push(report(4)));
+        // Even when changing the filters panics, producers wait again afterwards
+        assert!(thread::scope(|scope| scope.spawn(|| queue.without_blocking(|| panic!("filters failed"))).join()).is_err());
+        assert_eq!(queue.unblocked.load(Ordering::SeqCst), 0);
+        queue.close();
+        queue.push(report(5));
+        assert_eq!(drain(&queue), vec![Event::Overflow { dropped: 2 }, report(5)]);
+    }
+
+    #[test]
+    fn concurrent_producers() {
+        // Every producer's events arrive, in the order it pushed them
+        let queue = EventQueue::new(8, OverflowPolicy::Block);
+        let mut received = [0; 4];
+        thread::scope(|scope| {
+            for device in 0..4 {
+                let queue = &queue;
+                scope.spawn(move || (1..=1000).for_each(|x| queue.push(motion(device, x, 0))));
+            }
+
+            for _ in 0..4000 {
+                let Some(Event::MouseMotion(motion)) = queue.wait(Some(Instant::now() + Duration::from_secs(5))) else {
+                    panic!("events went missing");
+                };
+                let count = &mut received[motion.device.0 as usize];
+                *count += 1;
+                assert_eq!(motion.x, *count);
+            }
+        });
+        assert_eq!(queue.pop(), None);
+
+        // Dropped events are all accounted for
+        let queue = EventQueue::new(8, OverflowPolicy::DropOldest);
+        let mut seen = 0;
+        thread::scope(|scope| {
+            for device in 0..4 {
+                let queue = &queue;
+                scope.spawn(move || (0..1000).for_each(|_| queue.push(report(device))));
+            }
+            while seen < 4000 {
+                match queue.wait(Some(Instant::now() + Duration::from_secs(5))) {
+                    Some(Event::Overflow { dropped }) => seen += dropped,
+                    Some(_) => seen += 1,
+                    None => panic!("events went missing"),
+                }
+            }
+        });
+        assert_eq!(seen, 4000);
+    }
+
+    #[test]
+    fn capacity() {
+        let pembejeo = Pembejeo::builder()
+            .backend(Box::new(NullBackend))
+            .event_capacity(1)
+            .overflow_policy(OverflowPolicy::DropNewest)
+            .build()
+            .unwrap();
+        pembejeo.push_event(report(1));
+        pembejeo.push_event(report(2));
+
+        let mut event = Event::default();
+        assert!(pembejeo.wait(&mut event));
+        assert_eq!(event, report(1));
+        assert!(pembejeo.poll(&mut event));
+        assert_eq!(event, Event::Overflow { dropped: 1 });
+        assert!(!pembejeo.poll(&mut event));
+    }
+
+    #[test]
+    fn blocked_producers_give_way() {
+        let pembejeo = Pembejeo::builder()
+            .backend(Box::new(NullBackend))
+            .event_capacity(1)
+            .overflow_policy(OverflowPolicy::Block)
+            .build()
+            .unwrap();
+        let guid = SdlGuid::new(0x03, 0x1234, 0x5678, 0x0100, "Odd Pad");
+        let device = pembejeo.add_device(DeviceInfo { id: "gamepad".to_string(), guid: Some(guid), ..Default::default() });
+        pembejeo.add_gamepad_mappings("03000000341200007856000000010000,Odd Pad,a:b2,").unwrap();
+
+        // The mapped input waits for room with the gamepad mappers held, the queue being full
+        thread::scope(|scope| {
+            let producer = scope.spawn(|| pembejeo.map_joystick_input(device, JoystickInput::Button { index: 2, pressed: true }, Timestamp::default()));
+            thread::sleep(Duration::from_millis(20));
+            assert!(!producer.is_finished());
+
+            // Which the application needs too, and it isn't about to take events meanwhile
+            pembejeo.add_gamepad_mappings("03000000341200007856000000010000,Odd Pad,a:b1,").unwrap();
+            pembejeo.set_gestures(None);
+            assert!(producer.join().unwrap());
+        });
+
+        let mut event = Event::default();
+        assert!(pembejeo.poll(&mut event));
+        assert_eq!(event, Event::Overflow { dropped: 1 });
+        assert!(pembejeo.poll(&mut event));
+        assert!(matches!(event, Event::GamepadButton(GamepadButtonEvent { button: GamepadButton::South, pressed: true, .. })));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::{DeviceInfo, Transport};
+
+/// Picks the devices a `Pembejeo` reports. A filter matches devices that meet every criterion
+/// it sets, and a `Pembejeo` with filters only reports the devices one of them matches.
+///
+/// Criteria combine through struct update syntax:
+/// `DeviceFilter { transport: Some(Transport::Usb), ..DeviceFilter::usage(0x01, 0x05) }`.
+/// Raw reports are opt-in the same way: `DeviceFilter { hid_reports: true, ..DeviceFilter::vendor(0x054C) }`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DeviceFilter {
+    /// Matched against the usages of the device's top-level collections, along with `usage`
+    pub usage_page: Option<u16>,
+    pub usage: Option<u16>,
+    pub vendor_id: Option<u16>,
+    pub product_id: Option<u16>,
+    pub transport: Option<Transport>,
+    /// A part of the product name, ignoring case
+    pub name: Option<String>,
+    /// Also report the raw input reports of the devices it matches as `Event::HidReport`. On Linux
+    /// this announces the hidraw nodes of devices evdev reports as well, as devices of their own.
+    pub hid_reports: bool,
+}
+
+impl DeviceFilter {
+    /// Devices with a top-level collection of `usage` on `page`, e.g. `0x01, 0x02` for mice.
+    pub fn usage(page: u16, usage: u16) -> Self {
+        Self { usage_page: Some(page), usage: Some(usage), ..Default::default() }
+    }
+
+    /// Devices with a top-level collection on `page`.
+    pub fn usage_page(page: u16) -> Self {
+        Self { usage_page: Some(page), ..Default::default() }
+    }
+
+    /// Devices from one vendor.
+    pub fn vendor(vendor_id: u16) -> Self {
+        Self { vendor_id: Some(vendor_id), ..Default::default() }
+    }
+
+    /// One vendor's product.
+    pub fn product(vendor_id: u16, product_id: u16) -> Self {
+        Self { vendor_id: Some(vendor_id), product_id: Some(product_id), ..Default::default() }
+    }
+
+    pub fn transport(transport: Transport) -> Self {
+        Self { transport: Some(transport), ..Default::default() }
+    }
+
+    /// Devices whose product name contains `name`, ignoring case.
+    pub fn name(name: impl Into<String>) -> Self {
+        Self { name: Some(name.into()), ..Default::default() }
+    }
+
+    pub fn matches(&self, info: &DeviceInfo) -> bool {
+        let usage = (self.usage_page.is_none() && self.usage.is_none()) || info.usages.iter()
+            .any(|usage| self.usage_page.is_none_or(|page| page == usage.page) && self.usage.is_none_or(|id| id == usage.id));
+
+        usage
+            && self.vendor_id.is_none_or(|vendor_id| vendor_id == info.vendor_id)
+            && self.product_id.is_none_or(|product_id| product_id == info.product_id)
+            && self.transport.is_none_or(|transport| transport == info.transport)
+            && self.name.as_ref().is_none_or(|name| info.product.to_lowercase().contains(&name.to_lowercase()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DeviceFilter;
+    use crate::{Capabilities, DeviceHandle, DeviceInfo, NullBackend, Pembejeo, Transport, Usage};
+
+    fn gamepad() -> DeviceInfo {
+        DeviceInfo {
+            id: "gamepad".to_string(),
+            handle: DeviceHandle::default(),
+            vendor_id: 0x054C,
+            product_id: 0x09CC,
+            version: 0x0100,
+            transport: Transport::Usb,
+            location: String::new(),
+            usages: vec![Usage::new(0x01, 0x05), Usage::new(0xFF00, 0x01)],
+            capabilities: Capabilities { gamepad: true, rumble: true, ..Default::default() },
+            product: "Wireless Controller".to_string(),
+            manufacturer: "Sony Interactive Entertainment".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn criteria() {
+        let info = gamepad();
+        assert!(DeviceFilter::default().matches(&info));
+        assert!(DeviceFilter::usage(0x01, 0x05).matches(&info));
+        assert!(!DeviceFilter::usage(0x01, 0x02).matches(&info));
+        assert!(DeviceFilter::usage_page(0xFF00).matches(&info));
+        assert!(DeviceFilter::product(0x054C, 0x09CC).matches(&info));
+        assert!(!DeviceFilter::product(0x054C, 0x05C4).matches(&info));
+        assert!(DeviceFilter::name("wireless").matches(&info));
+        assert!(!DeviceFilter::transport(Transport::Bluetooth).matches(&info));
+    }
+
+    #[test]
+    fn combined_criteria() {
+        let info = gamepad();
+
+        // The usage page and usage have to come from the same collection
+        let filter = DeviceFilter { usage_page: Some(0xFF00), usage: Some(0x05), ..Default::default() };
+        assert!(!filter.matches(&info));
+
+        let filter = DeviceFilter { transport: Some(Transport::Usb), ..DeviceFilter::vendor(0x054C) };
+        assert!(filter.matches(&info));
+        let filter = DeviceFilter { transport: Some(Transport::Bluetooth), ..DeviceFilter::vendor(0x054C) };
+        assert!(!filter.matches(&info));
+    }
+
+    #[test]
+    fn live_filters() {
+        let info = gamepad();
+        let pembejeo = Pembejeo::builder()
+            .backend(Box::new(NullBackend))
+            .filter(DeviceFilter::usage(0x01, 0x02))
+            .build()
+            .unwrap();
+        assert!(!pembejeo.matches_device(&info));
+
+        pembejeo.add_filter(DeviceFilter::vendor(0x054C)).unwrap();
+        assert!(pembejeo.matches_device(&info));
+        assert!(pembejeo.remove_filter(&DeviceFilter::vendor(0x054C)).unwrap());
+        assert!(!pembejeo.remove_filter(&DeviceFilter::vendor(0x054C)).unwrap());
+        assert!(!pembejeo.matches_device(&info));
+
+        // Without filters every device is reported
+        assert!(pembejeo.remove_filter(&DeviceFilter::usage(0x01, 0x02)).unwrap());
+        assert!(pembejeo.filters().is_empty());
+        assert!(pembejeo.matches_device(&info));
+
+        // Raw reports are only reported when a matching filter asks for them
+        assert!(!pembejeo.wants_hid_reports(&info));
+        pembejeo.add_filter(DeviceFilter { hid_reports: true, ..DeviceFilter::usage(0x01, 0x02) }).unwrap();
+        assert!(!pembejeo.wants_hid_reports(&info));
+        pembejeo.add_filter(DeviceFilter { hid_reports: true, ..DeviceFilter::vendor(0x054C) }).unwrap();
+        assert!(pembejeo.wants_hid_reports(&info));
+    }
+}
@@ -0,0 +1,501 @@
+use std::mem;
+
+use crate::{interpreter::update_held, DeviceHandle, Event, FieldValue, GamepadAxisEvent, GamepadButtonEvent, GamepadHatEvent, HatDirection, JoystickInput, Pembejeo, Report, ReportDescriptor, ReportKind, Timestamp, Usage};
+
+/// Generic Desktop Joystick and Game Pad application collections
+const JOYSTICK: Usage = Usage::new(0x01, 0x04);
+const GAME_PAD: Usage = Usage::new(0x01, 0x05);
+/// Generic Desktop Hat Switch
+const HAT_SWITCH: Usage = Usage::new(0x01, 0x39);
+
+/// A gamepad button by where it sits on a standard controller. The face buttons are named by
+/// direction, so `South` is A on an Xbox controller and Cross on a PlayStation one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftShoulder,
+    RightShoulder,
+    /// Triggers that only report pressed or released. Analog ones also move `GamepadAxis::LeftTrigger`.
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    /// The guide or home button
+    Mode,
+    /// Clicking a stick in
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    /// A button outside the layout, by its button number starting at 1
+    Other(u16),
+}
+
+impl GamepadButton {
+    /// The button for a Button page usage of a game pad, numbered the way the Linux kernel lays
+    /// them out for HID game pads: A, B, C, X, Y, Z, TL, TR, TL2, TR2, Select, Start, Mode, ThumbL, ThumbR.
+    pub fn from_usage(usage: u16) -> GamepadButton {
+        match usage {
+            1 => GamepadButton::South,
+            2 => GamepadButton::East,
+            4 => GamepadButton::North,
+            5 => GamepadButton::West,
+            7 => GamepadButton::LeftShoulder,
+            8 => GamepadButton::RightShoulder,
+            9 => GamepadButton::LeftTrigger,
+            10 => GamepadButton::RightTrigger,
+            11 => GamepadButton::Select,
+            12 => GamepadButton::Start,
+            13 => GamepadButton::Mode,
+            14 => GamepadButton::LeftStick,
+            15 => GamepadButton::RightStick,
+            number => GamepadButton::Other(number),
+        }
+    }
+
+    /// The button for a Generic Desktop usage, such as Start or D-pad Up.
+    fn from_desktop_usage(usage: u16) -> Option<GamepadButton> {
+        match usage {
+            0x3D => Some(GamepadButton::Start),
+            0x3E => Some(GamepadButton::Select),
+            0x90 => Some(GamepadButton::DPadUp),
+            0x91 => Some(GamepadButton::DPadDown),
+            0x92 => Some(GamepadButton::DPadRight),
+            0x93 => Some(GamepadButton::DPadLeft),
+            _ => None,
+        }
+    }
+}
+
+/// A gamepad axis by where it sits on a standard controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+impl GamepadAxis {
+    /// The axis for a HID usage. HID game pads put the right stick on Z and Rz, and analog
+    /// triggers on Rx and Ry or on the Simulation page's Brake and Accelerator.
+    pub fn from_usage(usage: Usage) -> Option<GamepadAxis> {
+        match (usage.page, usage.id) {
+            (0x01, 0x30) => Some(GamepadAxis::LeftX),
+            (0x01, 0x31) => Some(GamepadAxis::LeftY),
+            (0x01, 0x32) => Some(GamepadAxis::RightX),
+            (0x01, 0x35) => Some(GamepadAxis::RightY),
+            (0x01, 0x33) | (0x02, 0xC5) => Some(GamepadAxis::LeftTrigger),
+            (0x01, 0x34) | (0x02, 0xC4) => Some(GamepadAxis::RightTrigger),
+            _ => None,
+        }
+    }
+
+    pub fn is_trigger(&self) -> bool {
+        matches!(self, GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger)
+    }
+
+    /// Scale `value` from the device's range to -1.0..=1.0 for sticks or 0.0..=1.0 for triggers.
+    pub fn normalize(&self, value: i32, minimum: i32, maximum: i32) -> f64 {
+        let span = maximum as f64 - minimum as f64;
+        if span <= 0.0 {
+            return 0.0;
+        }
+
+        let position = ((value as f64 - minimum as f64) / span).clamp(0.0, 1.0);
+        if self.is_trigger() { position } else { position * 2.0 - 1.0 }
+    }
+}
+
+/// Scale `value` from the device's range to -1.0..=1.0, the way joystick inputs are reported.
+pub(crate) fn centered(value: i32, minimum: i32, maximum: i32) -> f64 {
+    GamepadAxis::LeftX.normalize(value, minimum, maximum)
+}
+
+/// Turns the reports of HID game pads and joysticks into gamepad events.
+#[derive(Debug, Default)]
+pub(crate) struct GamepadInterpreter {
+    /// Set up by the first game pad or joystick report
+    elements: Option<JoystickElements>,
+
+    /// Button page usages currently held down
+    buttons: Vec<u16>,
+    /// Button page usages held down according to the report being handled
+    pressed: Vec<u16>,
+    /// Generic Desktop button usages currently held down
+    controls: Vec<u16>,
+    /// The last value of every axis usage
+    axes: Vec<(Usage, i32)>,
+    /// The last direction of every hat switch
+    hats: Vec<(u16, HatDirection)>,
+}
+
+impl GamepadInterpreter {
+    /// Handle a report of a game pad or joystick application collection. Returns false and does
+    /// nothing for reports of any other application.
+    ///
+    /// Every input goes through the device's SDL mapping first, and only falls back to the
+    /// built-in layout when it has none.
+    pub(crate) fn handle_report(
+        &mut self,
+        pembejeo: &Pembejeo,
+        device: DeviceHandle,
+        timestamp: Timestamp,
+        descriptor: &ReportDescriptor,
+        report: &Report,
+        values: &[FieldValue],
+    ) -> bool {
+        // Every field of a report belongs to the same application collection
+        let joystick = match report.fields.first().and_then(|field| descriptor.application(field.collection)) {
+            Some(GAME_PAD) => false,
+            Some(JOYSTICK) => true,
+            _ => return false,
+        };
+        let elements = self.elements.get_or_insert_with(|| JoystickElements::new(descriptor));
+
+        let push_button = |button, pressed| {
+            pembejeo.push_event(Event::GamepadButton(GamepadButtonEvent {
+                device,
+                timestamp,
+                button,
+                pressed,
+            }));
+        };
+
+        update_held(values, report, 0x09, &mut self.buttons, &mut self.pressed, |usage, pressed| {
+            let index = elements.button(Usage::new(0x09, usage));
+            if !pembejeo.map_joystick_input(device, JoystickInput::Button { index, pressed }, timestamp) {
+                // Joystick buttons have no layout to follow
+                push_button(if joystick { GamepadButton::Other(usage) } else { GamepadButton::from_usage(usage) }, pressed);
+            }
+        });
+
+        for value in values {
+            let field = &report.fields[value.field];
+
+            if value.usage.page == 0x01 {
+                if let Some(button) = GamepadButton::from_desktop_usage(value.usage.id) {
+                    let held = self.controls.iter().position(|control| *control == value.usage.id);
+                    let pressed = match (held, value.value != 0) {
+                        (None, true) => {
+                            self.controls.push(value.usage.id);
+                            true
+                        },
+                        (Some(index), false) => {
+                            self.controls.remove(index);
+                            false
+                        },
+                        _ => continue,
+                    };
+                    let index = elements.button(value.usage);
+                    if !pembejeo.map_joystick_input(device, JoystickInput::Button { index, pressed }, timestamp) {
+                        push_button(button, pressed);
+                    }
+                    continue;
+                }
+
+                if value.usage.id == HAT_SWITCH.id {
+                    // A value that means nothing leaves the hat where it was
+                    let Some(direction) = field.hat_direction(value.value) else {
+                        continue;
+                    };
+                    let index = elements.hat(report.id, value.field);
+                    let previous = match self.hats.iter_mut().find(|hat| hat.0 == index) {
+                        Some((_, last)) if *last == direction => continue,
+                        Some((_, last)) => mem::replace(last, direction),
+                        None => {
+                            self.hats.push((index, direction));
+                            HatDirection::Centered
+                        },
+                    };
+                    if !pembejeo.map_joystick_input(device, JoystickInput::Hat { index, mask: direction.mask() }, timestamp) {
+                        push_hat(pembejeo, device, timestamp, index, previous, direction);
+                    }
+                    continue;
+                }
+            }
+
+            // Values outside the logical range are null, the axis has nothing to say
+            if !is_axis(value.usage) || field.flags.is_relative() || value.value < field.logical_minimum || value.value > field.logical_maximum {
+                continue;
+            }
+            match self.axes.iter_mut().find(|(known, _)| *known == value.usage) {
+                Some((_, last)) if *last == value.value => continue,
+                Some((_, last)) => *last = value.value,
+                None => self.axes.push((value.usage, value.value)),
+            }
+
+            let index = elements.axis(value.usage);
+            let raw = centered(value.value, field.logical_minimum, field.logical_maximum);
+            if pembejeo.map_joystick_input(device, JoystickInput::Axis { index, value: raw }, timestamp) {
+                continue;
+            }
+            if let Some(axis) = GamepadAxis::from_usage(value.usage) {
+                pembejeo.push_event(Event::GamepadAxis(GamepadAxisEvent {
+                    device,
+                    timestamp,
+                    axis,
+                    value: axis.normalize(value.value, field.logical_minimum, field.logical_maximum),
+                }));
+            }
+        }
+
+        true
+    }
+}
+
+/// The inputs of a HID joystick in the order SDL mappings number them.
+#[derive(Debug, Default)]
+struct JoystickElements {
+    /// Button page usages by usage, then Generic Desktop buttons such as Start by usage
+    buttons: Vec<Usage>,
+    /// Axis usages by page and usage
+    axes: Vec<Usage>,
+    /// The report ID and field of every hat switch, in descriptor order
+    hats: Vec<(u8, usize)>,
+}
+
+impl JoystickElements {
+    fn new(descriptor: &ReportDescriptor) -> Self {
+        let mut elements = JoystickElements::default();
+        let mut controls = Vec::new();
+
+        for report in descriptor.reports.iter().filter(|report| report.kind == ReportKind::Input) {
+            for (index, field) in report.fields.iter().enumerate() {
+                if field.flags.is_constant() || !matches!(descriptor.application(field.collection), Some(GAME_PAD | JOYSTICK)) {
+                    continue;
+                }
+
+                for usage in (0..field.usage_count()).filter_map(|element| field.usage(element)) {
+                    if usage.page == 0x09 {
+                        elements.buttons.push(usage);
+                    } else if usage == HAT_SWITCH {
+                        elements.hats.push((report.id, index));
+                    } else if usage.page == 0x01 && GamepadButton::from_desktop_usage(usage.id).is_some() {
+                        controls.push(usage);
+                    } else if is_axis(usage) && field.flags.is_variable() && !field.flags.is_relative() {
+                        elements.axes.push(usage);
+                    }
+                }
+            }
+        }
+
+        for list in [&mut elements.buttons, &mut controls, &mut elements.axes] {
+            list.sort_by_key(|usage| (usage.page, usage.id));
+            list.dedup();
+        }
+        elements.buttons.append(&mut controls);
+        elements.hats.dedup();
+        elements
+    }
+
+    fn button(&self, usage: Usage) -> u16 {
+        index_of(&self.buttons, &usage)
+    }
+
+    fn axis(&self, usage: Usage) -> u16 {
+        index_of(&self.axes, &usage)
+    }
+
+    fn hat(&self, report_id: u8, field: usize) -> u16 {
+        index_of(&self.hats, &(report_id, field))
+    }
+}
+
+fn index_of<T: PartialEq>(list: &[T], item: &T) -> u16 {
+    list.iter().position(|known| known == item).unwrap_or(list.len()) as u16
+}
+
+/// Generic Desktop X to Wheel, and the Simulation page's controls.
+fn is_axis(usage: Usage) -> bool {
+    matches!((usage.page, usage.id), (0x01, 0x30..=0x38) | (0x02, 0xB0..=0xCF))
+}
+
+/// Report hat switch `hat` turning from `previous` to `direction` in the built-in layout, where
+/// the first hat is the d-pad.
+pub(crate) fn push_hat(pembejeo: &Pembejeo, device: DeviceHandle, timestamp: Timestamp, hat: u16, previous: HatDirection, direction: HatDirection) {
+    if hat == 0 {
+        let buttons = [
+            (GamepadButton::DPadUp, HatDirection::up as fn(&HatDirection) -> bool),
+            (GamepadButton::DPadRight, HatDirection::right),
+            (GamepadButton::DPadDown, HatDirection::down),
+            (GamepadButton::DPadLeft, HatDirection::left),
+        ];
+        for (button, held) in buttons {
+            let pressed = held(&direction);
+            if held(&previous) != pressed {
+                pembejeo.push_event(Event::GamepadButton(GamepadButtonEvent {
+                    device,
+                    timestamp,
+                    button,
+                    pressed,
+                }));
+            }
+        }
+    }
+
+    pembejeo.push_event(Event::GamepadHat(GamepadHatEvent {
+        device,
+        timestamp,
+        hat,
+        direction,
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GamepadAxis, GamepadButton, JoystickElements};
+    use crate::{hid::fixtures::{BOOT_MOUSE, GAMEPAD}, interpreter::ReportInterpreter, DeviceHandle, DeviceInfo, Event, NullBackend, Pembejeo, ReportDescriptor, SdlGuid, Timestamp, Usage};
+
+    fn drain(pembejeo: &Pembejeo) -> Vec<Event> {
+        let mut events = Vec::new();
+        let mut event = Event::default();
+        while pembejeo.poll(&mut event) {
+            events.push(event.clone());
+        }
+        events
+    }
+
+    fn describe(event: Event) -> String {
+        match event {
+            Event::GamepadButton(button_event) => format!("{:?} {}", button_event.button, button_event.pressed),
+            Event::GamepadAxis(axis_event) => format!("{:?} {:.2}", axis_event.axis, axis_event.value),
+            Event::GamepadHat(hat_event) => format!("Hat {} {:?}", hat_event.hat, hat_event.direction),
+            event => panic!("unexpected {:?}", event),
+        }
+    }
+
+    /// Input report with 15 buttons, a centered hat, X/Y/Z/Rz sticks and Brake/Accelerator triggers.
+    fn report(buttons: u16, sticks: [u8; 4], triggers: [u8; 2]) -> Vec<u8> {
+        let mut report = buttons.to_le_bytes().to_vec();
+        report.push(0x0F);
+        report.extend_from_slice(&sticks);
+        report.extend_from_slice(&triggers);
+        report
+    }
+
+    #[test]
+    fn standard_layout() {
+        assert_eq!(GamepadButton::from_usage(1), GamepadButton::South);
+        assert_eq!(GamepadButton::from_usage(5), GamepadButton::West);
+        assert_eq!(GamepadButton::from_usage(3), GamepadButton::Other(3));
+        assert_eq!(GamepadButton::from_usage(12), GamepadButton::Start);
+
+        assert_eq!(GamepadAxis::LeftX.normalize(0, 0, 255), -1.0);
+        assert_eq!(GamepadAxis::LeftY.normalize(255, 0, 255), 1.0);
+        assert_eq!(GamepadAxis::RightX.normalize(0, -127, 127), 0.0);
+        assert_eq!(GamepadAxis::LeftTrigger.normalize(0, 0, 255), 0.0);
+        assert_eq!(GamepadAxis::RightTrigger.normalize(1023, 0, 1023), 1.0);
+    }
+
+    #[test]
+    fn buttons_and_axes() {
+        let pembejeo = Pembejeo::with_backend(Box::new(NullBackend)).unwrap();
+        let descriptor = ReportDescriptor::parse(&GAMEPAD).unwrap();
+        let mut interpreter = ReportInterpreter::default();
+        let mut handle = |report: &[u8]| {
+            interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, report);
+            drain(&pembejeo).into_iter().map(describe).collect::<Vec<_>>()
+        };
+
+        // The first report says where every hat and axis is
+        assert_eq!(handle(&report(0, [0, 255, 128, 128], [0, 0])), vec![
+            "Hat 0 Centered", "LeftX -1.00", "LeftY 1.00", "RightX 0.00", "RightY 0.00", "LeftTrigger 0.00", "RightTrigger 0.00",
+        ]);
+
+        // Then only changes come through, never as mouse buttons
+        assert_eq!(handle(&report(0b1000_0000_0001, [0, 255, 128, 128], [0, 255])), vec![
+            "South true", "Start true", "RightTrigger 1.00",
+        ]);
+        assert_eq!(handle(&report(0b1000_0000_0000, [0, 255, 128, 128], [0, 255])), vec!["South false"]);
+        assert_eq!(handle(&report(0b1000_0000_0000, [0, 255, 128, 128], [0, 255])), Vec::<String>::new());
+        assert_eq!(handle(&report(0, [0, 128, 128, 128], [0, 255])), vec!["Start false", "LeftY 0.00"]);
+    }
+
+    #[test]
+    fn hat_is_the_dpad() {
+        let pembejeo = Pembejeo::with_backend(Box::new(NullBackend)).unwrap();
+        let descriptor = ReportDescriptor::parse(&GAMEPAD).unwrap();
+        let mut interpreter = ReportInterpreter::default();
+        let mut handle = |hat: u8| {
+            let mut report = report(0, [128, 128, 128, 128], [0, 0]);
+            report[2] = hat;
+            interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, &report);
+            drain(&pembejeo).into_iter().map(describe).filter(|event| !event.starts_with("Left") && !event.starts_with("Right")).collect::<Vec<_>>()
+        };
+
+        assert_eq!(handle(0x00), vec!["DPadUp true", "Hat 0 Up"]);
+        assert_eq!(handle(0x00), Vec::<String>::new());
+        // Only the buttons that changed
+        assert_eq!(handle(0x03), vec!["DPadUp false", "DPadRight true", "DPadDown true", "Hat 0 DownRight"]);
+        assert_eq!(handle(0x04), vec!["DPadRight false", "Hat 0 Down"]);
+        // The null state lets go
+        assert_eq!(handle(0x0F), vec!["DPadDown false", "Hat 0 Centered"]);
+    }
+
+    #[test]
+    fn mice_are_not_gamepads() {
+        let pembejeo = Pembejeo::with_backend(Box::new(NullBackend)).unwrap();
+        let descriptor = ReportDescriptor::parse(&BOOT_MOUSE).unwrap();
+        let mut interpreter = ReportInterpreter::default();
+
+        interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, &[0x01, 0, 0]);
+        assert!(matches!(drain(&pembejeo)[..], [Event::MouseButton(_)]));
+    }
+
+    #[test]
+    fn joystick_elements() {
+        let elements = JoystickElements::new(&ReportDescriptor::parse(&GAMEPAD).unwrap());
+        assert_eq!(elements.buttons.len(), 15);
+        assert_eq!(elements.button(Usage::new(0x09, 12)), 11);
+        // Simulation controls come after the Generic Desktop axes
+        assert_eq!(elements.axes, vec![
+            Usage::new(0x01, 0x30), Usage::new(0x01, 0x31), Usage::new(0x01, 0x32), Usage::new(0x01, 0x35),
+            Usage::new(0x02, 0xC4), Usage::new(0x02, 0xC5),
+        ]);
+        assert_eq!(elements.hats.len(), 1);
+    }
+
+    #[test]
+    fn sdl_mapping() {
+        let pembejeo = Pembejeo::with_backend(Box::new(NullBackend)).unwrap();
+        let descriptor = ReportDescriptor::parse(&GAMEPAD).unwrap();
+        let mut interpreter = ReportInterpreter::default();
+
+        let guid = SdlGuid::new(0x03, 0x1234, 0x5678, 0x0100, "Odd Pad");
+        let device = pembejeo.add_device(DeviceInfo {
+            id: "gamepad".to_string(),
+            vendor_id: 0x1234,
+            product_id: 0x5678,
+            product: "Odd Pad".to_string(),
+            guid: Some(guid),
+            ..Default::default()
+        });
+        assert!(matches!(drain(&pembejeo)[..], [Event::DeviceAdded(_)]));
+        let mapping = "03000000341200007856000000010000,Odd Pad,a:b2,start:b0,dpup:h0.1,dpright:h0.2,righttrigger:a4,";
+        assert_eq!(pembejeo.add_gamepad_mappings(mapping).unwrap(), 1);
+        assert_eq!(pembejeo.gamepad_mapping("gamepad").unwrap().name, "Odd Pad");
+
+        let mut handle = |report: &[u8]| {
+            interpreter.handle_report(&pembejeo, device, Timestamp::default(), &descriptor, report);
+            drain(&pembejeo).into_iter().map(describe).collect::<Vec<_>>()
+        };
+
+        // Button 1 is Start and button 3 is A, the hat is the d-pad, and Accelerator is axis 4
+        assert_eq!(handle(&report(0b101, [128, 128, 128, 128], [0, 0])), vec!["Start true", "South true", "RightTrigger 0.00"]);
+        let mut hat_up_right = report(0b101, [128, 128, 128, 128], [0, 255]);
+        hat_up_right[2] = 0x01;
+        assert_eq!(handle(&hat_up_right), vec!["DPadUp true", "DPadRight true", "RightTrigger 1.00"]);
+        // Unmapped inputs are dropped rather than reported in the built-in layout
+        assert_eq!(handle(&report(0b10, [0, 255, 128, 128], [0, 255])), vec![
+            "Start false", "South false", "DPadUp false", "DPadRight false",
+        ]);
+    }
+}
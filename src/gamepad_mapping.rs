@@ -0,0 +1,565 @@
+use std::{fmt, fs, path::Path, str::FromStr};
+
+use crate::{DeviceHandle, Event, GamepadAxis, GamepadAxisEvent, GamepadButton, GamepadButtonEvent, Timestamp};
+
+/// The platform name SDL mappings use for this build, matched against their `platform:` field
+const PLATFORM: &str = if cfg!(target_os = "macos") {
+    "Mac OS X"
+} else if cfg!(target_os = "windows") {
+    "Windows"
+} else if cfg!(target_os = "android") {
+    "Android"
+} else if cfg!(target_os = "ios") {
+    "iOS"
+} else {
+    "Linux"
+};
+
+/// SDL's joystick GUID, which identifies a kind of controller in `gamecontrollerdb.txt`.
+///
+/// Little endian 16 bit words of the bus type, a CRC-16 of the name, the vendor ID, 0, the product
+/// ID, 0 and the version, followed by a driver signature and its data. Devices without vendor and
+/// product IDs carry the start of their name instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SdlGuid(pub [u8; 16]);
+
+impl SdlGuid {
+    pub fn new(bus: u16, vendor: u16, product: u16, version: u16, name: &str) -> Self {
+        let mut data = [0_u8; 16];
+        data[0..2].copy_from_slice(&bus.to_le_bytes());
+        data[2..4].copy_from_slice(&crc16(name.as_bytes()).to_le_bytes());
+
+        if vendor != 0 && product != 0 {
+            data[4..6].copy_from_slice(&vendor.to_le_bytes());
+            data[8..10].copy_from_slice(&product.to_le_bytes());
+            data[12..14].copy_from_slice(&version.to_le_bytes());
+        } else {
+            // Like strlcpy into the last 12 bytes, which leaves room for the terminator
+            let length = name.len().min(11);
+            data[4..4 + length].copy_from_slice(&name.as_bytes()[..length]);
+        }
+
+        SdlGuid(data)
+    }
+
+    pub fn bus(&self) -> u16 {
+        self.word(0)
+    }
+
+    pub fn crc(&self) -> u16 {
+        self.word(2)
+    }
+
+    pub fn vendor_id(&self) -> u16 {
+        self.word(4)
+    }
+
+    pub fn product_id(&self) -> u16 {
+        self.word(8)
+    }
+
+    pub fn version(&self) -> u16 {
+        self.word(12)
+    }
+
+    fn word(&self, offset: usize) -> u16 {
+        u16::from_le_bytes([self.0[offset], self.0[offset + 1]])
+    }
+
+    /// Whether a mapping for `self` applies to `device`. Mappings with a CRC or version of 0 match
+    /// devices with any, which is how most of `gamecontrollerdb.txt` is written.
+    pub fn matches(&self, device: &SdlGuid) -> bool {
+        let mut device = *device;
+        if self.crc() == 0 {
+            device.0[2..4].fill(0);
+        }
+        if self.version() == 0 {
+            device.0[12..14].fill(0);
+        }
+        *self == device
+    }
+}
+
+impl fmt::Display for SdlGuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SdlGuid {
+    type Err = crate::Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        if text.len() != 32 || !text.is_ascii() {
+            return Err(crate::Error::InvalidMapping(format!("{:?} is not a 32 digit GUID", text)));
+        }
+
+        let mut data = [0_u8; 16];
+        for (index, byte) in data.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&text[index * 2..index * 2 + 2], 16)
+                .map_err(|_| crate::Error::InvalidMapping(format!("{:?} is not a hexadecimal GUID", text)))?;
+        }
+        Ok(SdlGuid(data))
+    }
+}
+
+/// SDL's CRC-16, the reflected 0x8005 polynomial starting from 0 also known as CRC-16/ARC.
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0_u16;
+    for byte in data {
+        crc ^= *byte as u16;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xA001 } else { crc >> 1 };
+        }
+    }
+    crc
+}
+
+/// Which part of an axis a binding uses.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AxisRange {
+    #[default]
+    Full,
+    /// `+` in a mapping, from the center to the maximum
+    Positive,
+    /// `-` in a mapping, from the center to the minimum
+    Negative,
+}
+
+/// An input of a joystick as SDL numbers them.
+///
+/// On Linux buttons are the `EV_KEY` codes the device has, from `BTN_JOYSTICK` up and then from 0,
+/// axes are its `EV_ABS` codes other than hats, and hats are its `ABS_HAT0X` to `ABS_HAT3Y` pairs.
+/// HID devices number their Button page usages by usage, then their other buttons, their axes by
+/// usage and their hat switches in descriptor order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JoystickInput {
+    Button { index: u16, pressed: bool },
+    /// `value` from -1.0 at the minimum to 1.0 at the maximum
+    Axis { index: u16, value: f64 },
+    /// `mask` with 1 for up, 2 for right, 4 for down and 8 for left, 0 when centered
+    Hat { index: u16, mask: u8 },
+}
+
+/// The joystick input a binding reads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MappingSource {
+    /// `b<index>`
+    Button(u16),
+    /// `a<index>`, with a `+` or `-` prefix for half of it and a `~` suffix when inverted
+    Axis { index: u16, range: AxisRange, inverted: bool },
+    /// `h<index>.<mask>`
+    Hat { index: u16, mask: u8 },
+}
+
+/// The standard control a binding drives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MappingTarget {
+    Button(GamepadButton),
+    /// A `+` or `-` prefix drives half of the axis only
+    Axis(GamepadAxis, AxisRange),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Binding {
+    pub source: MappingSource,
+    pub target: MappingTarget,
+}
+
+/// One line of `gamecontrollerdb.txt`, such as
+/// `030000005e0400008e02000014010000,Xbox 360 Controller,a:b0,b:b1,...,leftx:a0,platform:Linux,`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GamepadMapping {
+    pub guid: SdlGuid,
+    pub name: String,
+    /// The platform the mapping is for, or `None` for every platform
+    pub platform: Option<String>,
+    /// Controls outside the standard layout, such as `paddle1` or `touchpad`, are left out
+    pub bindings: Vec<Binding>,
+}
+
+impl FromStr for GamepadMapping {
+    type Err = crate::Error;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut parts = line.trim().split(',');
+        let guid = parts.next().unwrap_or_default().parse()?;
+        let name = parts.next()
+            .ok_or_else(|| crate::Error::InvalidMapping(format!("{} has no name", guid)))?
+            .to_string();
+
+        let mut mapping = GamepadMapping { guid, name, platform: None, bindings: Vec::new() };
+        for part in parts.filter(|part| !part.is_empty()) {
+            let Some((key, value)) = part.split_once(':') else {
+                return Err(crate::Error::InvalidMapping(format!("{}: {:?} is not a key:value pair", mapping.name, part)));
+            };
+
+            match key {
+                "platform" => mapping.platform = Some(value.to_string()),
+                // Hints, CRCs and SDK version limits only matter to SDL itself
+                "hint" | "crc" | "sdk>=" | "sdk<=" => {},
+                _ => {
+                    let source = parse_source(value)
+                        .ok_or_else(|| crate::Error::InvalidMapping(format!("{}: {:?} is not a joystick input", mapping.name, value)))?;
+                    if let Some(target) = parse_target(key) {
+                        mapping.bindings.push(Binding { source, target });
+                    }
+                },
+            }
+        }
+
+        Ok(mapping)
+    }
+}
+
+fn parse_source(text: &str) -> Option<MappingSource> {
+    let (range, text) = match text.as_bytes().first()? {
+        b'+' => (AxisRange::Positive, &text[1..]),
+        b'-' => (AxisRange::Negative, &text[1..]),
+        _ => (AxisRange::Full, text),
+    };
+    let (inverted, text) = match text.strip_suffix('~') {
+        Some(text) => (true, text),
+        None => (false, text),
+    };
+
+    let (kind, rest) = text.split_at_checked(1)?;
+    match kind {
+        "b" if range == AxisRange::Full && !inverted => Some(MappingSource::Button(rest.parse().ok()?)),
+        "a" => Some(MappingSource::Axis { index: rest.parse().ok()?, range, inverted }),
+        "h" if range == AxisRange::Full && !inverted => {
+            let (index, mask) = rest.split_once('.')?;
+            Some(MappingSource::Hat { index: index.parse().ok()?, mask: mask.parse().ok()? })
+        },
+        _ => None,
+    }
+}
+
+fn parse_target(name: &str) -> Option<MappingTarget> {
+    let (range, name) = match name.as_bytes().first()? {
+        b'+' => (AxisRange::Positive, &name[1..]),
+        b'-' => (AxisRange::Negative, &name[1..]),
+        _ => (AxisRange::Full, name),
+    };
+
+    let button = match name {
+        "a" => GamepadButton::South,
+        "b" => GamepadButton::East,
+        "x" => GamepadButton::West,
+        "y" => GamepadButton::North,
+        "back" => GamepadButton::Select,
+        "guide" => GamepadButton::Mode,
+        "start" => GamepadButton::Start,
+        "leftstick" => GamepadButton::LeftStick,
+        "rightstick" => GamepadButton::RightStick,
+        "leftshoulder" => GamepadButton::LeftShoulder,
+        "rightshoulder" => GamepadButton::RightShoulder,
+        "dpup" => GamepadButton::DPadUp,
+        "dpdown" => GamepadButton::DPadDown,
+        "dpleft" => GamepadButton::DPadLeft,
+        "dpright" => GamepadButton::DPadRight,
+        _ => {
+            let axis = match name {
+                "leftx" => GamepadAxis::LeftX,
+                "lefty" => GamepadAxis::LeftY,
+                "rightx" => GamepadAxis::RightX,
+                "righty" => GamepadAxis::RightY,
+                "lefttrigger" => GamepadAxis::LeftTrigger,
+                "righttrigger" => GamepadAxis::RightTrigger,
+                _ => return None,
+            };
+            return Some(MappingTarget::Axis(axis, range));
+        },
+    };
+    Some(MappingTarget::Button(button))
+}
+
+/// A set of mappings, as loaded from `gamecontrollerdb.txt`.
+#[derive(Clone, Debug, Default)]
+pub struct GamepadMappings {
+    mappings: Vec<GamepadMapping>,
+}
+
+impl GamepadMappings {
+    /// Parse mappings, one per line. Blank lines and `#` comments are skipped.
+    pub fn parse(text: &str) -> Result<Self, crate::Error> {
+        let mut mappings = GamepadMappings::default();
+        mappings.add_mappings(text)?;
+        Ok(mappings)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, crate::Error> {
+        let mut mappings = GamepadMappings::default();
+        mappings.load_mappings(path)?;
+        Ok(mappings)
+    }
+
+    /// Add every mapping in a file, like `add_mappings`.
+    pub fn load_mappings(&mut self, path: impl AsRef<Path>) -> Result<usize, crate::Error> {
+        let text = fs::read_to_string(path.as_ref())
+            .map_err(|err| crate::Error::InvalidMapping(format!("Failed to read {}: {}", path.as_ref().display(), err)))?;
+        self.add_mappings(&text)
+    }
+
+    /// Add every mapping in `text`, returning how many there were. Nothing is added when a line is invalid.
+    pub fn add_mappings(&mut self, text: &str) -> Result<usize, crate::Error> {
+        let mut parsed = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mapping = line.parse::<GamepadMapping>()
+                .map_err(|err| crate::Error::InvalidMapping(format!("line {}: {}", number + 1, err)))?;
+            parsed.push(mapping);
+        }
+
+        let count = parsed.len();
+        for mapping in parsed {
+            self.add(mapping);
+        }
+        Ok(count)
+    }
+
+    /// Add a mapping, replacing any earlier one for the same GUID and platform.
+    pub fn add(&mut self, mapping: GamepadMapping) {
+        match self.mappings.iter_mut().find(|existing| existing.guid == mapping.guid && existing.platform == mapping.platform) {
+            Some(existing) => *existing = mapping,
+            None => self.mappings.push(mapping),
+        }
+    }
+
+    /// The mapping for a device on this platform, preferring one for its exact GUID.
+    pub fn find(&self, guid: &SdlGuid) -> Option<&GamepadMapping> {
+        let candidates = || self.mappings.iter()
+            .filter(|mapping| mapping.platform.as_deref().is_none_or(|platform| platform == PLATFORM));
+        candidates().find(|mapping| mapping.guid == *guid)
+            .or_else(|| candidates().find(|mapping| mapping.guid.matches(guid)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &GamepadMapping> {
+        self.mappings.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.mappings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mappings.is_empty()
+    }
+}
+
+/// Applies a device's mapping to its joystick inputs, remembering what it reported so only changes produce events.
+#[derive(Debug)]
+pub(crate) struct GamepadMapper {
+    /// The device the mapping was looked up for
+    pub(crate) guid: SdlGuid,
+    mapping: Option<GamepadMapping>,
+
+    hats: Vec<(u16, u8)>,
+    buttons: Vec<GamepadButton>,
+    axes: Vec<(GamepadAxis, f64)>,
+}
+
+impl GamepadMapper {
+    pub(crate) fn new(guid: SdlGuid, mapping: Option<GamepadMapping>) -> Self {
+        GamepadMapper { guid, mapping, hats: Vec::new(), buttons: Vec::new(), axes: Vec::new() }
+    }
+
+    /// Push the events `input` maps to. Returns false without doing anything when there is no mapping.
+    pub(crate) fn handle_input(&mut self, device: DeviceHandle, input: JoystickInput, timestamp: Timestamp, mut push: impl FnMut(Event)) -> bool {
+        let Some(mapping) = &self.mapping else {
+            return false;
+        };
+
+        // Hats only drive the bindings of directions that changed
+        let changed_hat = match input {
+            JoystickInput::Hat { index, mask } => {
+                let previous = match self.hats.iter_mut().find(|hat| hat.0 == index) {
+                    Some(hat) => std::mem::replace(&mut hat.1, mask),
+                    None => {
+                        self.hats.push((index, mask));
+                        0
+                    },
+                };
+                previous ^ mask
+            },
+            _ => 0,
+        };
+
+        for binding in &mapping.bindings {
+            // Where the input is within the range the binding reads, from 0.0 to 1.0
+            let position = match (binding.source, input) {
+                (MappingSource::Button(source), JoystickInput::Button { index, pressed }) if source == index => {
+                    if pressed { 1.0 } else { 0.0 }
+                },
+                (MappingSource::Axis { index: source, range, inverted }, JoystickInput::Axis { index, value }) if source == index => {
+                    let value = if inverted { -value } else { value };
+                    match range {
+                        AxisRange::Full => (value + 1.0) / 2.0,
+                        AxisRange::Positive => value.clamp(0.0, 1.0),
+                        AxisRange::Negative => (-value).clamp(0.0, 1.0),
+                    }
+                },
+                (MappingSource::Hat { index: source, mask: bits }, JoystickInput::Hat { index, mask }) if source == index && changed_hat & bits != 0 => {
+                    if mask & bits != 0 { 1.0 } else { 0.0 }
+                },
+                _ => continue,
+            };
+
+            match binding.target {
+                MappingTarget::Button(button) => {
+                    let pressed = position >= 0.5;
+                    let held = self.buttons.iter().position(|held| *held == button);
+                    match (held, pressed) {
+                        (None, true) => self.buttons.push(button),
+                        (Some(index), false) => {
+                            self.buttons.remove(index);
+                        },
+                        _ => continue,
+                    }
+                    push(Event::GamepadButton(GamepadButtonEvent { device, timestamp, button, pressed }));
+                },
+                MappingTarget::Axis(axis, range) => {
+                    let value = match range {
+                        AxisRange::Full if axis.is_trigger() => position,
+                        AxisRange::Full => position * 2.0 - 1.0,
+                        AxisRange::Positive => position,
+                        AxisRange::Negative => -position,
+                    };
+                    match self.axes.iter_mut().find(|(known, _)| *known == axis) {
+                        Some((_, last)) if *last == value => continue,
+                        Some((_, last)) => *last = value,
+                        None => self.axes.push((axis, value)),
+                    }
+                    push(Event::GamepadAxis(GamepadAxisEvent { device, timestamp, axis, value }));
+                },
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crc16, AxisRange, Binding, GamepadMapper, GamepadMapping, GamepadMappings, JoystickInput, MappingSource, MappingTarget, SdlGuid};
+    use crate::{DeviceHandle, Event, GamepadAxis, GamepadButton, Timestamp};
+
+    const XBOX_360: &str = "030000005e0400008e02000014010000,Xbox 360 Controller,a:b0,b:b1,back:b6,dpdown:h0.4,dpleft:h0.8,\
+        dpright:h0.2,dpup:h0.1,guide:b8,leftshoulder:b4,leftstick:b9,lefttrigger:a2,leftx:a0,lefty:a1,rightshoulder:b5,\
+        rightstick:b10,righttrigger:a5,rightx:a3,righty:a4,start:b7,x:b2,y:b3,platform:Linux,";
+
+    #[test]
+    fn guids() {
+        // The standard check value of CRC-16/ARC
+        assert_eq!(crc16(b"123456789"), 0xBB3D);
+
+        let guid = SdlGuid::new(0x03, 0x045E, 0x028E, 0x0114, "Microsoft X-Box 360 pad");
+        assert_eq!((guid.bus(), guid.vendor_id(), guid.product_id(), guid.version()), (0x03, 0x045E, 0x028E, 0x0114));
+        assert_eq!(guid.crc(), crc16(b"Microsoft X-Box 360 pad"));
+        let text = guid.to_string();
+        assert_eq!(&text[..4], "0300");
+        assert_eq!(&text[8..], "5e0400008e02000014010000");
+        assert_eq!(text.parse::<SdlGuid>().unwrap(), guid);
+
+        // Without IDs the name is kept instead, as much as fits
+        let guid = SdlGuid::new(0x05, 0, 0, 0, "Wireless Gamepad");
+        assert_eq!(&guid.0[4..], b"Wireless Ga\0");
+
+        assert!("0300".parse::<SdlGuid>().is_err());
+        assert!("zz0000005e0400008e02000014010000".parse::<SdlGuid>().is_err());
+    }
+
+    #[test]
+    fn parse_mapping() {
+        let mapping: GamepadMapping = XBOX_360.parse().unwrap();
+        assert_eq!(mapping.name, "Xbox 360 Controller");
+        assert_eq!(mapping.platform.as_deref(), Some("Linux"));
+        assert_eq!(mapping.bindings.len(), 21);
+        assert!(mapping.bindings.contains(&Binding {
+            source: MappingSource::Hat { index: 0, mask: 8 },
+            target: MappingTarget::Button(GamepadButton::DPadLeft),
+        }));
+        assert!(mapping.bindings.contains(&Binding {
+            source: MappingSource::Axis { index: 5, range: AxisRange::Full, inverted: false },
+            target: MappingTarget::Axis(GamepadAxis::RightTrigger, AxisRange::Full),
+        }));
+
+        let mapping: GamepadMapping = "03000000ff1100003133000000000000,Odd Pad,+leftx:b1,-leftx:b2,lefty:-a1~,misc1:b9,paddle1:b10,".parse().unwrap();
+        assert_eq!(mapping.platform, None);
+        assert_eq!(mapping.bindings, vec![
+            Binding { source: MappingSource::Button(1), target: MappingTarget::Axis(GamepadAxis::LeftX, AxisRange::Positive) },
+            Binding { source: MappingSource::Button(2), target: MappingTarget::Axis(GamepadAxis::LeftX, AxisRange::Negative) },
+            Binding {
+                source: MappingSource::Axis { index: 1, range: AxisRange::Negative, inverted: true },
+                target: MappingTarget::Axis(GamepadAxis::LeftY, AxisRange::Full),
+            },
+        ]);
+
+        assert!("030000005e0400008e02000014010000".parse::<GamepadMapping>().is_err());
+        assert!("030000005e0400008e02000014010000,Pad,a:x0,".parse::<GamepadMapping>().is_err());
+        assert!("030000005e0400008e02000014010000,Pad,a".parse::<GamepadMapping>().is_err());
+    }
+
+    #[test]
+    fn find_mappings() {
+        let text = format!("# Comment\n\n{}\n{}\n", XBOX_360, XBOX_360.replace("Linux", "Mac OS X").replace("a:b0", "a:b11"));
+        let mut mappings = GamepadMappings::parse(&text).unwrap();
+        assert_eq!(mappings.len(), 2);
+
+        // Mappings without a CRC match any name
+        let device = SdlGuid::new(0x03, 0x045E, 0x028E, 0x0114, "Microsoft X-Box 360 pad");
+        let found = mappings.find(&device).unwrap();
+        assert_eq!(found.platform.as_deref(), Some(if cfg!(target_os = "macos") { "Mac OS X" } else { "Linux" }));
+        assert!(mappings.find(&SdlGuid::new(0x03, 0x045E, 0x028E, 0x0115, "")).is_none());
+
+        // A later mapping for the same GUID and platform replaces the earlier one
+        assert_eq!(mappings.add_mappings(&XBOX_360.replace("Xbox 360 Controller", "Renamed")).unwrap(), 1);
+        assert_eq!(mappings.len(), 2);
+
+        // Invalid lines are reported by number, and nothing from the text is added
+        let err = mappings.add_mappings(&format!("{}\nnot a mapping", XBOX_360.replace("0300", "0500"))).unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+        assert_eq!(mappings.len(), 2);
+    }
+
+    #[test]
+    fn apply_mapping() {
+        let mapping: GamepadMapping = format!("{}-lefty:b12,+lefty:b13,", XBOX_360).parse().unwrap();
+        let mut mapper = GamepadMapper::new(mapping.guid, Some(mapping));
+        let mut handle = |input| {
+            let mut events = Vec::new();
+            assert!(mapper.handle_input(DeviceHandle(1), input, Timestamp::default(), |event| events.push(match event {
+                Event::GamepadButton(button_event) => format!("{:?} {}", button_event.button, button_event.pressed),
+                Event::GamepadAxis(axis_event) => format!("{:?} {:.2}", axis_event.axis, axis_event.value),
+                event => panic!("unexpected {:?}", event),
+            })));
+            events
+        };
+
+        assert_eq!(handle(JoystickInput::Button { index: 7, pressed: true }), vec!["Start true"]);
+        assert_eq!(handle(JoystickInput::Axis { index: 1, value: -1.0 }), vec!["LeftY -1.00"]);
+        // Full axes become triggers from 0.0 to 1.0
+        assert_eq!(handle(JoystickInput::Axis { index: 2, value: 0.0 }), vec!["LeftTrigger 0.50"]);
+        // Only the directions that changed are reported
+        assert_eq!(handle(JoystickInput::Hat { index: 0, mask: 0b0011 }), vec!["DPadRight true", "DPadUp true"]);
+        assert_eq!(handle(JoystickInput::Hat { index: 0, mask: 0b0010 }), vec!["DPadUp false"]);
+        // Buttons on half axes, and only changes are reported
+        assert_eq!(handle(JoystickInput::Axis { index: 1, value: 0.0 }), vec!["LeftY 0.00"]);
+        assert_eq!(handle(JoystickInput::Button { index: 12, pressed: true }), vec!["LeftY -1.00"]);
+        assert_eq!(handle(JoystickInput::Axis { index: 1, value: -1.0 }), Vec::<String>::new());
+        assert_eq!(handle(JoystickInput::Button { index: 13, pressed: true }), vec!["LeftY 1.00"]);
+        assert_eq!(handle(JoystickInput::Button { index: 13, pressed: false }), vec!["LeftY 0.00"]);
+        // Unmapped inputs do nothing
+        assert_eq!(handle(JoystickInput::Button { index: 20, pressed: true }), Vec::<String>::new());
+
+        let mut unmapped = GamepadMapper::new(SdlGuid::default(), None);
+        assert!(!unmapped.handle_input(DeviceHandle(1), JoystickInput::Button { index: 0, pressed: true }, Timestamp::default(), |_| panic!()));
+    }
+}
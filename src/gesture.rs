@@ -0,0 +1,444 @@
+use std::f64::consts::PI;
+
+use crate::{DeviceHandle, Event, Timestamp, TouchEvent, TouchPhase};
+
+/// When the recognizer decides fingers are making a gesture.
+///
+/// Distances are in millimetres on surfaces that report their size, and in hundredths of the
+/// surface on those that don't.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GestureConfig {
+    /// How far two fingers move together before they pan
+    pub pan_threshold: f64,
+    /// How much the distance between two fingers changes before they pinch
+    pub pinch_threshold: f64,
+    /// How far two fingers turn around each other, in radians, before they rotate
+    pub rotate_threshold: f64,
+    /// How far the fingers of a swipe move before it is recognized
+    pub swipe_threshold: f64,
+    /// The fewest fingers that swipe instead of panning
+    pub swipe_fingers: u32,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        GestureConfig {
+            pan_threshold: 2.0,
+            pinch_threshold: 4.0,
+            rotate_threshold: 0.2,
+            swipe_threshold: 10.0,
+            swipe_fingers: 3,
+        }
+    }
+}
+
+/// A recognized gesture and how it changed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GestureEvent {
+    pub device: DeviceHandle,
+    /// The touch frame that completed the gesture event
+    pub timestamp: Timestamp,
+    pub gesture: Gesture,
+    pub phase: GesturePhase,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gesture {
+    /// Two fingers moving apart or together. `scale` is the relative change in their distance
+    /// since the last event, 0.1 when they moved 10% apart.
+    Pinch { scale: f64 },
+    /// Two fingers turning around each other. `angle` is the change since the last event in
+    /// radians, positive clockwise.
+    Rotate { angle: f64 },
+    /// Several fingers moving together in one direction.
+    Swipe { fingers: u32, direction: SwipeDirection },
+    /// Two fingers moving together. `x` and `y` are the distance moved since the last event.
+    Pan { x: f64, y: f64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GesturePhase {
+    Began,
+    Changed,
+    Ended,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SwipeDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// Turns `Touch` and `TouchFrame` events into `Gesture` events.
+///
+/// Feed it every event of a touch surface in order, e.g. through `Pembejeo::set_gestures`, or on
+/// its own with recorded or synthetic contacts.
+#[derive(Debug, Default)]
+pub struct GestureRecognizer {
+    config: GestureConfig,
+    devices: Vec<DeviceGestures>,
+}
+
+#[derive(Debug, Default)]
+struct DeviceGestures {
+    device: DeviceHandle,
+
+    /// Contact ids and positions as of the latest `Touch` events
+    contacts: Vec<(u32, f64, f64)>,
+    /// Where the fingers were when their number last changed
+    start: Option<Pose>,
+    /// Where the fingers were in the last frame
+    last: Option<Pose>,
+    active: Active,
+    /// The frame being finished
+    timestamp: Timestamp,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum Active {
+    #[default]
+    None,
+    Pan,
+    /// Pinching and rotating can happen at the same time
+    Transform { pinch: bool, rotate: bool },
+    Swipe { fingers: u32, direction: SwipeDirection },
+}
+
+/// What the recognizer measures of the fingers in one frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Pose {
+    fingers: usize,
+    x: f64,
+    y: f64,
+    /// Distance and angle between the first two fingers
+    distance: f64,
+    angle: f64,
+}
+
+impl GestureRecognizer {
+    pub fn new(config: GestureConfig) -> Self {
+        GestureRecognizer { config, devices: Vec::new() }
+    }
+
+    pub fn config(&self) -> &GestureConfig {
+        &self.config
+    }
+
+    /// Follow `event`, calling `emit` for every gesture event it completes. Other events are ignored.
+    pub fn handle_event(&mut self, event: &Event, mut emit: impl FnMut(Event)) {
+        match event {
+            Event::Touch(touch_event) => self.device(touch_event.device).update_contact(touch_event),
+            Event::TouchFrame(frame_event) => {
+                let config = self.config;
+                self.device(frame_event.device).finish_frame(&config, frame_event.timestamp, &mut emit);
+            },
+            _ => {}
+        }
+    }
+
+    fn device(&mut self, device: DeviceHandle) -> &mut DeviceGestures {
+        match self.devices.iter().position(|gestures| gestures.device == device) {
+            Some(index) => &mut self.devices[index],
+            None => {
+                self.devices.push(DeviceGestures { device, ..Default::default() });
+                self.devices.last_mut().unwrap()
+            },
+        }
+    }
+}
+
+impl DeviceGestures {
+    fn update_contact(&mut self, touch_event: &TouchEvent) {
+        // Millimetres keep angles true on surfaces that aren't square
+        let (x, y) = match touch_event.millimetres {
+            Some(millimetres) => (millimetres.x, millimetres.y),
+            None => (touch_event.x * 100.0, touch_event.y * 100.0),
+        };
+
+        let index = self.contacts.iter().position(|contact| contact.0 == touch_event.contact_id);
+        match (index, touch_event.phase) {
+            (Some(index), TouchPhase::Ended | TouchPhase::Cancelled) => {
+                self.contacts.remove(index);
+            },
+            (Some(index), _) => self.contacts[index] = (touch_event.contact_id, x, y),
+            (None, TouchPhase::Began | TouchPhase::Moved) => self.contacts.push((touch_event.contact_id, x, y)),
+            (None, _) => {},
+        }
+    }
+
+    fn finish_frame(&mut self, config: &GestureConfig, timestamp: Timestamp, emit: &mut impl FnMut(Event)) {
+        self.timestamp = timestamp;
+        let pose = self.pose();
+
+        // A finger landing or lifting ends whatever the others were doing
+        if self.start.map(|start| start.fingers) != pose.map(|pose| pose.fingers) {
+            self.end(emit);
+            self.start = pose;
+            self.last = pose;
+        }
+        let (Some(pose), Some(start), Some(last)) = (pose, self.start, self.last) else {
+            return;
+        };
+
+        if pose.fingers == 2 {
+            self.two_fingers(config, &start, &last, &pose, emit);
+        } else if pose.fingers >= config.swipe_fingers.max(2) as usize {
+            self.swipe(config, &start, &pose, emit);
+        }
+        self.last = Some(pose);
+    }
+
+    fn two_fingers(&mut self, config: &GestureConfig, start: &Pose, last: &Pose, pose: &Pose, emit: &mut impl FnMut(Event)) {
+        let pinching = (pose.distance - start.distance).abs() > config.pinch_threshold;
+        let rotating = turn(start.angle, pose.angle).abs() > config.rotate_threshold;
+
+        match self.active {
+            Active::None => {
+                // Gestures begin with everything since the fingers landed. Pinching and rotating
+                // win over panning, since the fingers' midpoint drifts a little while they do.
+                if pinching || rotating {
+                    self.active = Active::Transform { pinch: pinching, rotate: rotating };
+                    if pinching {
+                        self.emit(emit, Gesture::Pinch { scale: scale(start.distance, pose.distance) }, GesturePhase::Began);
+                    }
+                    if rotating {
+                        self.emit(emit, Gesture::Rotate { angle: turn(start.angle, pose.angle) }, GesturePhase::Began);
+                    }
+                } else if (pose.x - start.x).hypot(pose.y - start.y) > config.pan_threshold {
+                    self.active = Active::Pan;
+                    self.emit(emit, Gesture::Pan { x: pose.x - start.x, y: pose.y - start.y }, GesturePhase::Began);
+                }
+            },
+            Active::Transform { pinch, rotate } => {
+                if pinch && pose.distance != last.distance {
+                    self.emit(emit, Gesture::Pinch { scale: scale(last.distance, pose.distance) }, GesturePhase::Changed);
+                } else if !pinch && pinching {
+                    self.emit(emit, Gesture::Pinch { scale: scale(start.distance, pose.distance) }, GesturePhase::Began);
+                }
+
+                if rotate && pose.angle != last.angle {
+                    self.emit(emit, Gesture::Rotate { angle: turn(last.angle, pose.angle) }, GesturePhase::Changed);
+                } else if !rotate && rotating {
+                    self.emit(emit, Gesture::Rotate { angle: turn(start.angle, pose.angle) }, GesturePhase::Began);
+                }
+
+                self.active = Active::Transform { pinch: pinch || pinching, rotate: rotate || rotating };
+            },
+            Active::Pan => {
+                if (pose.x, pose.y) != (last.x, last.y) {
+                    self.emit(emit, Gesture::Pan { x: pose.x - last.x, y: pose.y - last.y }, GesturePhase::Changed);
+                }
+            },
+            Active::Swipe { .. } => {},
+        }
+    }
+
+    fn swipe(&mut self, config: &GestureConfig, start: &Pose, pose: &Pose, emit: &mut impl FnMut(Event)) {
+        if self.active != Active::None {
+            return;
+        }
+
+        let (x, y) = (pose.x - start.x, pose.y - start.y);
+        if x.hypot(y) <= config.swipe_threshold {
+            return;
+        }
+
+        // The direction is settled once, along the axis the fingers moved furthest on
+        let direction = if x.abs() >= y.abs() {
+            if x > 0.0 { SwipeDirection::Right } else { SwipeDirection::Left }
+        } else if y > 0.0 {
+            SwipeDirection::Down
+        } else {
+            SwipeDirection::Up
+        };
+        let fingers = pose.fingers as u32;
+        self.active = Active::Swipe { fingers, direction };
+        self.emit(emit, Gesture::Swipe { fingers, direction }, GesturePhase::Began);
+    }
+
+    /// End the active gesture, if any.
+    fn end(&mut self, emit: &mut impl FnMut(Event)) {
+        match std::mem::take(&mut self.active) {
+            Active::Pan => self.emit(emit, Gesture::Pan { x: 0.0, y: 0.0 }, GesturePhase::Ended),
+            Active::Transform { pinch, rotate } => {
+                if pinch {
+                    self.emit(emit, Gesture::Pinch { scale: 0.0 }, GesturePhase::Ended);
+                }
+                if rotate {
+                    self.emit(emit, Gesture::Rotate { angle: 0.0 }, GesturePhase::Ended);
+                }
+            },
+            Active::Swipe { fingers, direction } => self.emit(emit, Gesture::Swipe { fingers, direction }, GesturePhase::Ended),
+            Active::None => {},
+        }
+    }
+
+    fn emit(&self, emit: &mut impl FnMut(Event), gesture: Gesture, phase: GesturePhase) {
+        emit(Event::Gesture(GestureEvent { device: self.device, timestamp: self.timestamp, gesture, phase }));
+    }
+
+    fn pose(&self) -> Option<Pose> {
+        let fingers = self.contacts.len();
+        if fingers == 0 {
+            return None;
+        }
+
+        let x = self.contacts.iter().map(|contact| contact.1).sum::<f64>() / fingers as f64;
+        let y = self.contacts.iter().map(|contact| contact.2).sum::<f64>() / fingers as f64;
+        let (distance, angle) = match self.contacts.as_slice() {
+            [first, second, ..] => {
+                let (dx, dy) = (second.1 - first.1, second.2 - first.2);
+                (dx.hypot(dy), dy.atan2(dx))
+            },
+            _ => (0.0, 0.0),
+        };
+
+        Some(Pose { fingers, x, y, distance, angle })
+    }
+}
+
+/// The relative change from `from` to `to`.
+fn scale(from: f64, to: f64) -> f64 {
+    if from > 0.0 { to / from - 1.0 } else { 0.0 }
+}
+
+/// The smallest turn from angle `from` to angle `to`, in -PI..=PI.
+fn turn(from: f64, to: f64) -> f64 {
+    let mut angle = to - from;
+    while angle > PI {
+        angle -= 2.0 * PI;
+    }
+    while angle < -PI {
+        angle += 2.0 * PI;
+    }
+    angle
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use super::{Gesture, GestureConfig, GesturePhase, GestureRecognizer, SwipeDirection};
+    use crate::{DeviceHandle, Event, ScaledMotion, Timestamp, TouchEvent, TouchFrameEvent, TouchPhase};
+
+    /// Feeds synthetic frames of `(contact id, x, y)` in millimetres, working out the phases.
+    struct Surface {
+        recognizer: GestureRecognizer,
+        frame: u64,
+        contacts: Vec<u32>,
+    }
+
+    impl Surface {
+        fn new() -> Self {
+            Surface { recognizer: GestureRecognizer::new(GestureConfig::default()), frame: 0, contacts: Vec::new() }
+        }
+
+        fn frame(&mut self, contacts: &[(u32, f64, f64)]) -> Vec<(Gesture, GesturePhase)> {
+            self.frame += 1;
+            let mut events = Vec::new();
+            for &(contact_id, x, y) in contacts {
+                let phase = if self.contacts.contains(&contact_id) { TouchPhase::Moved } else { TouchPhase::Began };
+                events.push(touch(self.frame, contact_id, phase, x, y));
+            }
+            for &contact_id in &self.contacts {
+                if !contacts.iter().any(|contact| contact.0 == contact_id) {
+                    events.push(touch(self.frame, contact_id, TouchPhase::Ended, 0.0, 0.0));
+                }
+            }
+            events.push(Event::TouchFrame(TouchFrameEvent {
+                device: DeviceHandle(1),
+                timestamp: Timestamp::default(),
+                frame: self.frame,
+                contacts: contacts.len() as u32,
+            }));
+            self.contacts = contacts.iter().map(|contact| contact.0).collect();
+
+            let mut gestures = Vec::new();
+            for event in &events {
+                self.recognizer.handle_event(event, |gesture| match gesture {
+                    Event::Gesture(gesture_event) => gestures.push((gesture_event.gesture, gesture_event.phase)),
+                    other => panic!("expected a gesture, got {:?}", other),
+                });
+            }
+            gestures
+        }
+    }
+
+    fn touch(frame: u64, contact_id: u32, phase: TouchPhase, x: f64, y: f64) -> Event {
+        Event::Touch(TouchEvent {
+            device: DeviceHandle(1),
+            timestamp: Timestamp::default(),
+            frame,
+            contact_id,
+            phase,
+            x: x / 100.0,
+            y: y / 50.0,
+            millimetres: Some(ScaledMotion { x, y }),
+            width: None,
+            height: None,
+            pressure: None,
+        })
+    }
+
+    #[test]
+    fn two_finger_pan() {
+        let mut surface = Surface::new();
+        assert!(surface.frame(&[(1, 40.0, 20.0), (2, 60.0, 20.0)]).is_empty());
+        // Below the threshold
+        assert!(surface.frame(&[(1, 40.0, 21.0), (2, 60.0, 21.0)]).is_empty());
+        assert_eq!(surface.frame(&[(1, 40.0, 23.0), (2, 60.0, 23.0)]), vec![(Gesture::Pan { x: 0.0, y: 3.0 }, GesturePhase::Began)]);
+        assert_eq!(surface.frame(&[(1, 41.0, 24.0), (2, 61.0, 24.0)]), vec![(Gesture::Pan { x: 1.0, y: 1.0 }, GesturePhase::Changed)]);
+        assert_eq!(surface.frame(&[(1, 41.0, 24.0)]), vec![(Gesture::Pan { x: 0.0, y: 0.0 }, GesturePhase::Ended)]);
+    }
+
+    #[test]
+    fn pinch_and_rotate() {
+        let mut surface = Surface::new();
+        surface.frame(&[(1, 40.0, 20.0), (2, 60.0, 20.0)]);
+
+        // 20mm apart to 30mm apart
+        assert_eq!(surface.frame(&[(1, 35.0, 20.0), (2, 65.0, 20.0)]), vec![(Gesture::Pinch { scale: 0.5 }, GesturePhase::Began)]);
+        // Turning a quarter clockwise around the midpoint also starts a rotation
+        let gestures = surface.frame(&[(1, 50.0, 5.0), (2, 50.0, 35.0)]);
+        assert_eq!(gestures.len(), 1);
+        let (Gesture::Rotate { angle }, GesturePhase::Began) = gestures[0] else {
+            panic!("expected a rotation, got {:?}", gestures);
+        };
+        assert!((angle - FRAC_PI_2).abs() < 1e-9);
+
+        // Both end when the fingers lift
+        assert_eq!(surface.frame(&[]), vec![
+            (Gesture::Pinch { scale: 0.0 }, GesturePhase::Ended),
+            (Gesture::Rotate { angle: 0.0 }, GesturePhase::Ended),
+        ]);
+    }
+
+    #[test]
+    fn three_finger_swipe() {
+        let mut surface = Surface::new();
+        surface.frame(&[(1, 40.0, 20.0), (2, 50.0, 20.0), (3, 60.0, 20.0)]);
+        assert!(surface.frame(&[(1, 35.0, 21.0), (2, 45.0, 21.0), (3, 55.0, 21.0)]).is_empty());
+
+        let swipe = Gesture::Swipe { fingers: 3, direction: SwipeDirection::Left };
+        assert_eq!(surface.frame(&[(1, 25.0, 22.0), (2, 35.0, 22.0), (3, 45.0, 22.0)]), vec![(swipe, GesturePhase::Began)]);
+        // The direction doesn't change once recognized
+        assert!(surface.frame(&[(1, 25.0, 42.0), (2, 35.0, 42.0), (3, 45.0, 42.0)]).is_empty());
+        assert_eq!(surface.frame(&[(1, 25.0, 42.0), (2, 35.0, 42.0)]), vec![(swipe, GesturePhase::Ended)]);
+    }
+
+    #[test]
+    fn configurable_thresholds() {
+        let mut surface = Surface::new();
+        surface.recognizer = GestureRecognizer::new(GestureConfig { swipe_fingers: 4, pan_threshold: 0.5, ..Default::default() });
+
+        // Three fingers are too few to swipe
+        surface.frame(&[(1, 40.0, 20.0), (2, 50.0, 20.0), (3, 60.0, 20.0)]);
+        assert!(surface.frame(&[(1, 10.0, 20.0), (2, 20.0, 20.0), (3, 30.0, 20.0)]).is_empty());
+
+        // And a small two-finger move already pans
+        surface.frame(&[(1, 40.0, 20.0), (2, 60.0, 20.0)]);
+        assert_eq!(surface.frame(&[(1, 40.0, 21.0), (2, 60.0, 21.0)]), vec![(Gesture::Pan { x: 0.0, y: 1.0 }, GesturePhase::Began)]);
+    }
+}
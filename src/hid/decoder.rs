@@ -0,0 +1,254 @@
+use crate::{Field, Report, ReportDescriptor, ReportKind, Usage};
+
+/// One decoded element of a report.
+///
+/// Variable fields produce one value per element. Array fields produce one value of 1
+/// for every usage currently listed in the array; usages that are absent are released.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FieldValue {
+    pub usage: Usage,
+    pub value: i32,
+
+    /// Index of the field in `Report::fields`
+    pub field: usize,
+    /// Index of the field's innermost collection in `ReportDescriptor::collections`
+    pub collection: Option<usize>,
+}
+
+impl ReportDescriptor {
+    /// Decode an input report exactly as read from the device, report ID byte included when the descriptor uses them.
+    /// `values` is cleared first so one buffer can be reused for every report.
+    pub fn decode_input<'a>(&'a self, data: &[u8], values: &mut Vec<FieldValue>) -> Result<&'a Report, crate::Error> {
+        self.decode(ReportKind::Input, data, values)
+    }
+
+    pub fn decode<'a>(&'a self, kind: ReportKind, data: &[u8], values: &mut Vec<FieldValue>) -> Result<&'a Report, crate::Error> {
+        values.clear();
+
+        let (id, payload) = if self.uses_report_ids() {
+            match data.split_first() {
+                Some((id, payload)) => (*id, payload),
+                None => return Err(crate::Error::InvalidReport("empty report".to_string())),
+            }
+        } else {
+            (0, data)
+        };
+
+        let report = self.report(kind, id)
+            .ok_or_else(|| crate::Error::InvalidReport(format!("no {:?} report with ID {}", kind, id)))?;
+        if (payload.len() * 8) < report.bit_length as usize {
+            return Err(crate::Error::InvalidReport(format!(
+                "report {} is {} bytes, expected {}", id, payload.len(), report.bit_length.div_ceil(8)
+            )));
+        }
+
+        for (index, field) in report.fields.iter().enumerate() {
+            if field.flags.is_constant() || field.report_size == 0 {
+                continue;
+            }
+
+            for element in 0..field.report_count {
+                let raw = extract_bits(payload, field.bit_offset + element * field.report_size, field.report_size);
+                let value = field.logical_value(raw);
+
+                let usage = if field.flags.is_variable() {
+                    field.usage(element)
+                } else {
+                    // Array elements hold an index into the usages, anything outside the logical range is empty
+                    field.array_usage(value)
+                };
+                let Some(usage) = usage else {
+                    continue;
+                };
+
+                values.push(FieldValue {
+                    usage,
+                    value: if field.flags.is_variable() { value } else { 1 },
+                    field: index,
+                    collection: field.collection,
+                });
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+impl Field {
+    /// Interpret the raw bits of one element, sign extending when the logical range is signed.
+    pub fn logical_value(&self, raw: u32) -> i32 {
+        if self.logical_minimum < 0 && self.report_size > 0 && self.report_size < 32 {
+            let shift = 32 - self.report_size;
+            ((raw << shift) as i32) >> shift
+        } else {
+            raw as i32
+        }
+    }
+
+    /// The usage selected by an array element's value, or `None` for an empty slot.
+    pub fn array_usage(&self, value: i32) -> Option<Usage> {
+        if value < self.logical_minimum || value > self.logical_maximum {
+            return None;
+        }
+
+        let index = (value - self.logical_minimum) as u32;
+        if index >= self.usage_count() {
+            return None;
+        }
+
+        // Usage 0 is reserved on every page and means "no event" in arrays
+        self.usage(index).filter(|usage| usage.id != 0)
+    }
+}
+
+/// Read `size` bits starting at bit `offset`, little endian and least significant bit first as HID packs them.
+pub(crate) fn extract_bits(data: &[u8], offset: u32, size: u32) -> u32 {
+    let mut value = 0_u64;
+    let first_byte = (offset / 8) as usize;
+    let last_byte = (offset.saturating_add(size).div_ceil(8) as usize).min(data.len());
+    // 32 bits starting anywhere within a byte span at most 5 bytes, wider fields are cut off
+    for (shift, byte) in data[first_byte.min(last_byte)..last_byte].iter().take(5).enumerate() {
+        value |= (*byte as u64) << (shift * 8);
+    }
+
+    let value = value >> (offset % 8);
+    if size >= 32 {
+        value as u32
+    } else {
+        (value & ((1_u64 << size) - 1)) as u32
+    }
+}
+
+/// Write the low `size` bits of `value` starting at bit `offset`, the inverse of `extract_bits`.
+pub(crate) fn insert_bits(data: &mut [u8], offset: u32, size: u32, value: u32) {
+    for bit in 0..size.min(32) {
+        let position = offset + bit;
+        let Some(byte) = data.get_mut((position / 8) as usize) else {
+            return;
+        };
+
+        let mask = 1 << (position % 8);
+        if value & (1 << bit) != 0 {
+            *byte |= mask;
+        } else {
+            *byte &= !mask;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extract_bits, insert_bits, FieldValue};
+    use crate::{hid::fixtures::{BOOT_KEYBOARD, BOOT_MOUSE, QEMU_TABLET, REPORT_ID_MOUSE}, ReportDescriptor, Usage};
+
+    fn usages_and_values(values: &[FieldValue]) -> Vec<(Usage, i32)> {
+        values.iter().map(|value| (value.usage, value.value)).collect()
+    }
+
+    #[test]
+    fn bits() {
+        let data = [0b1010_1100, 0b0101_0011, 0xFF];
+        assert_eq!(extract_bits(&data, 0, 1), 0);
+        assert_eq!(extract_bits(&data, 2, 2), 0b11);
+        assert_eq!(extract_bits(&data, 4, 8), 0b0011_1010);
+        assert_eq!(extract_bits(&data, 12, 12), 0xFF5);
+        assert_eq!(extract_bits(&data, 0, 24), 0xFF53AC);
+
+        // Fields wider than 32 bits keep their low 32
+        let wide = [0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF, 0xFF];
+        assert_eq!(extract_bits(&wide, 0, 72), 0x67452301);
+        assert_eq!(extract_bits(&wide, 4, 68), 0x96745230);
+
+        let mut data = [0xFF, 0x00, 0x00];
+        insert_bits(&mut data, 4, 12, 0xABC);
+        assert_eq!(data, [0xCF, 0xAB, 0x00]);
+        assert_eq!(extract_bits(&data, 4, 12), 0xABC);
+    }
+
+    #[test]
+    fn boot_mouse() {
+        let descriptor = ReportDescriptor::parse(&BOOT_MOUSE).unwrap();
+        let mut values = Vec::new();
+
+        // Left and middle buttons, X +5, Y -3
+        descriptor.decode_input(&[0b101, 0x05, 0xFD], &mut values).unwrap();
+        assert_eq!(usages_and_values(&values), vec![
+            (Usage::new(0x09, 1), 1),
+            (Usage::new(0x09, 2), 0),
+            (Usage::new(0x09, 3), 1),
+            (Usage::new(0x01, 0x30), 5),
+            (Usage::new(0x01, 0x31), -3),
+        ]);
+        assert_eq!(values[3].field, 2);
+        assert_eq!(values[3].collection, Some(1));
+
+        assert!(descriptor.decode_input(&[0x00, 0x05], &mut values).is_err());
+    }
+
+    #[test]
+    fn boot_keyboard_arrays() {
+        let descriptor = ReportDescriptor::parse(&BOOT_KEYBOARD).unwrap();
+        let mut values = Vec::new();
+
+        // Left shift held with A and B, the other slots empty
+        descriptor.decode_input(&[0x02, 0x00, 0x04, 0x05, 0x00, 0x00, 0x00, 0x00], &mut values).unwrap();
+        let pressed: Vec<_> = values.iter().filter(|value| value.value != 0).map(|value| value.usage).collect();
+        assert_eq!(pressed, vec![Usage::new(0x07, 0xE1), Usage::new(0x07, 0x04), Usage::new(0x07, 0x05)]);
+
+        // The eight modifiers are variables and always reported
+        assert_eq!(values.iter().filter(|value| value.field == 0).count(), 8);
+    }
+
+    #[test]
+    fn report_ids_and_packed_fields() {
+        let descriptor = ReportDescriptor::parse(&REPORT_ID_MOUSE).unwrap();
+        let mut values = Vec::new();
+
+        // Report 2: button 16, X -2047 and Y 2047 packed into three bytes, wheel -1, pan 2
+        let x = (-2047_i32 as u32) & 0xFFF;
+        let y = 2047_u32;
+        let packed = x | (y << 12);
+        let report = [
+            0x02, 0x00, 0x80,
+            packed as u8, (packed >> 8) as u8, (packed >> 16) as u8,
+            0xFF, 0x02,
+        ];
+        let decoded = descriptor.decode_input(&report, &mut values).unwrap();
+        assert_eq!(decoded.id, 2);
+
+        let values = usages_and_values(&values);
+        assert!(values.contains(&(Usage::new(0x09, 16), 1)));
+        assert!(values.contains(&(Usage::new(0x09, 15), 0)));
+        assert!(values.contains(&(Usage::new(0x01, 0x30), -2047)));
+        assert!(values.contains(&(Usage::new(0x01, 0x31), 2047)));
+        assert!(values.contains(&(Usage::new(0x01, 0x38), -1)));
+        assert!(values.contains(&(Usage::new(0x0C, 0x0238), 2)));
+
+        // Unknown report IDs are rejected
+        let mut values = Vec::new();
+        assert!(descriptor.decode_input(&[0x07, 0x00], &mut values).is_err());
+    }
+
+    #[test]
+    fn absolute_pointer() {
+        let descriptor = ReportDescriptor::parse(&QEMU_TABLET).unwrap();
+        let mut values = Vec::new();
+
+        // Right button at X 16384 and Y 32767, the wheel still
+        let decoded = descriptor.decode_input(&[0b010, 0x00, 0x40, 0xFF, 0x7F, 0x00], &mut values).unwrap();
+        assert_eq!(usages_and_values(&values)[1..], [
+            (Usage::new(0x09, 2), 1),
+            (Usage::new(0x09, 3), 0),
+            (Usage::new(0x01, 0x30), 16384),
+            (Usage::new(0x01, 0x31), 32767),
+            (Usage::new(0x01, 0x38), 0),
+        ]);
+
+        // X and Y are positions across the whole range, unlike the wheel
+        let x = &decoded.fields[values[3].field];
+        assert!(!x.flags.is_relative());
+        assert!(decoded.fields[values[5].field].flags.is_relative());
+        assert!((x.normalized(16384) - 0.5).abs() < 1e-4);
+        assert_eq!(descriptor.application(x.collection), Some(Usage::new(0x01, 0x02)));
+    }
+}
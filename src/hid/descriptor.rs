@@ -0,0 +1,682 @@
+
+/// A usage page and usage id pair.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Usage {
+    pub page: u16,
+    pub id: u16,
+}
+
+impl Usage {
+    pub const fn new(page: u16, id: u16) -> Self {
+        Self { page, id }
+    }
+}
+
+/// A run of usages on one page, `minimum..=maximum`. A single Usage item is a range of one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UsageRange {
+    pub page: u16,
+    pub minimum: u16,
+    pub maximum: u16,
+}
+
+impl UsageRange {
+    pub fn count(&self) -> u32 {
+        (self.maximum as u32).saturating_sub(self.minimum as u32) + 1
+    }
+
+    pub fn contains(&self, usage: Usage) -> bool {
+        usage.page == self.page && (self.minimum..=self.maximum).contains(&usage.id)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollectionKind {
+    Physical,
+    Application,
+    Logical,
+    Report,
+    NamedArray,
+    UsageSwitch,
+    UsageModifier,
+    Other(u8),
+}
+
+impl From<u8> for CollectionKind {
+    fn from(value: u8) -> Self {
+        match value {
+            0x00 => Self::Physical,
+            0x01 => Self::Application,
+            0x02 => Self::Logical,
+            0x03 => Self::Report,
+            0x04 => Self::NamedArray,
+            0x05 => Self::UsageSwitch,
+            0x06 => Self::UsageModifier,
+            other => Self::Other(other),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Collection {
+    pub kind: CollectionKind,
+    pub usage: Usage,
+
+    /// Index of the enclosing collection in `ReportDescriptor::collections`
+    pub parent: Option<usize>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ReportKind {
+    Input,
+    Output,
+    Feature,
+}
+
+/// The data bits of an Input, Output or Feature item.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FieldFlags(pub u32);
+
+impl FieldFlags {
+    pub fn is_constant(&self) -> bool { self.0 & 0x001 != 0 }
+    pub fn is_variable(&self) -> bool { self.0 & 0x002 != 0 }
+    pub fn is_array(&self) -> bool { !self.is_variable() }
+    pub fn is_relative(&self) -> bool { self.0 & 0x004 != 0 }
+    pub fn wraps(&self) -> bool { self.0 & 0x008 != 0 }
+    pub fn is_nonlinear(&self) -> bool { self.0 & 0x010 != 0 }
+    pub fn has_no_preferred_state(&self) -> bool { self.0 & 0x020 != 0 }
+    pub fn has_null_state(&self) -> bool { self.0 & 0x040 != 0 }
+    pub fn is_volatile(&self) -> bool { self.0 & 0x080 != 0 }
+    pub fn is_buffered_bytes(&self) -> bool { self.0 & 0x100 != 0 }
+}
+
+/// One Input, Output or Feature main item and the global and local state it was declared with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    pub kind: ReportKind,
+    /// 0 when the descriptor doesn't use report IDs
+    pub report_id: u8,
+
+    /// Usages in declaration order. Variable fields hand them out one per element,
+    /// array fields index into them with their values.
+    pub usages: Vec<UsageRange>,
+
+    pub logical_minimum: i32,
+    pub logical_maximum: i32,
+    pub physical_minimum: i32,
+    pub physical_maximum: i32,
+    pub unit: u32,
+    pub unit_exponent: i8,
+
+    pub report_size: u32,
+    pub report_count: u32,
+    pub flags: FieldFlags,
+
+    /// Offset of the first element in bits, counted after the report ID byte
+    pub bit_offset: u32,
+
+    /// Index of the innermost enclosing collection in `ReportDescriptor::collections`
+    pub collection: Option<usize>,
+}
+
+impl Field {
+    /// The usage of element `index` of a variable field. The last usage repeats for any extra elements.
+    pub fn usage(&self, index: u32) -> Option<Usage> {
+        let mut remaining = index;
+        for range in &self.usages {
+            if remaining < range.count() {
+                return Some(Usage::new(range.page, range.minimum + remaining as u16));
+            }
+            remaining -= range.count();
+        }
+        self.usages.last().map(|range| Usage::new(range.page, range.maximum))
+    }
+
+    /// Total number of usages across all of the field's ranges.
+    pub fn usage_count(&self) -> u32 {
+        self.usages.iter().map(|range| range.count()).sum()
+    }
+
+    /// Whether any of the field's usages is `usage`.
+    pub fn has_usage(&self, usage: Usage) -> bool {
+        self.usages.iter().any(|range| range.contains(usage))
+    }
+
+    /// The physical range, which falls back to the logical range when the descriptor leaves both ends at 0.
+    pub fn physical_range(&self) -> (i32, i32) {
+        if self.physical_minimum == 0 && self.physical_maximum == 0 {
+            (self.logical_minimum, self.logical_maximum)
+        } else {
+            (self.physical_minimum, self.physical_maximum)
+        }
+    }
+
+    /// A value's position in the logical range, from 0.0 to 1.0.
+    pub fn normalized(&self, value: i32) -> f64 {
+        let span = self.logical_maximum as f64 - self.logical_minimum as f64;
+        if span <= 0.0 {
+            return 0.0;
+        }
+        ((value as f64 - self.logical_minimum as f64) / span).clamp(0.0, 1.0)
+    }
+
+    /// Convert a logical value to millimetres using the physical range and unit exponent.
+    /// `None` unless the field's unit is a plain centimetre or inch length.
+    pub fn millimetres(&self, value: i32) -> Option<f64> {
+        // System nibble: 1 is SI linear (centimetres), 3 is English linear (inches).
+        // The length exponent nibble has to be 1 and every other dimension 0.
+        let millimetres_per_unit = match self.unit {
+            0x11 => 10.0,
+            0x13 => 25.4,
+            _ => return None,
+        };
+
+        let (physical_minimum, physical_maximum) = self.physical_range();
+        let logical_span = self.logical_maximum as f64 - self.logical_minimum as f64;
+        if logical_span <= 0.0 {
+            return None;
+        }
+        let scale = (physical_maximum as f64 - physical_minimum as f64) / logical_span;
+
+        // Deltas only scale, positions also shift by the ranges' minimums
+        let physical = if self.flags.is_relative() {
+            value as f64 * scale
+        } else {
+            (value as f64 - self.logical_minimum as f64) * scale + physical_minimum as f64
+        };
+
+        Some(physical * 10_f64.powi(self.unit_exponent as i32) * millimetres_per_unit)
+    }
+}
+
+/// All the fields sharing a report kind and report ID, in the order they appear in the report.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+    pub kind: ReportKind,
+    /// 0 when the descriptor doesn't use report IDs
+    pub id: u8,
+    /// Length of the report in bits, not counting the report ID byte
+    pub bit_length: u32,
+    pub fields: Vec<Field>,
+}
+
+impl Report {
+    /// Length of the report in bytes as sent over the wire, including the report ID byte if there is one.
+    pub fn byte_length(&self) -> usize {
+        self.bit_length.div_ceil(8) as usize + if self.id != 0 { 1 } else { 0 }
+    }
+}
+
+/// A parsed HID report descriptor.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReportDescriptor {
+    /// Every collection in declaration order. `Collection::parent` links them into a tree.
+    pub collections: Vec<Collection>,
+    pub reports: Vec<Report>,
+}
+
+impl ReportDescriptor {
+    pub fn parse(bytes: &[u8]) -> Result<Self, crate::Error> {
+        Parser::default().parse(bytes)
+    }
+
+    pub fn report(&self, kind: ReportKind, id: u8) -> Option<&Report> {
+        self.reports.iter().find(|report| report.kind == kind && report.id == id)
+    }
+
+    /// Whether the reports are prefixed with a report ID byte.
+    pub fn uses_report_ids(&self) -> bool {
+        self.reports.iter().any(|report| report.id != 0)
+    }
+
+    /// The usages of the top-level collections, such as Generic Desktop Mouse or Keyboard.
+    pub fn application_usages(&self) -> Vec<Usage> {
+        self.collections.iter()
+            .filter(|collection| collection.parent.is_none())
+            .map(|collection| collection.usage)
+            .collect()
+    }
+
+    /// The usage of the application collection `collection` is in.
+    pub fn application(&self, collection: Option<usize>) -> Option<Usage> {
+        std::iter::successors(collection, |index| self.collections[*index].parent)
+            .last()
+            .map(|index| self.collections[index].usage)
+    }
+
+    /// Every field of the given kind, across all reports.
+    pub fn fields(&self, kind: ReportKind) -> impl Iterator<Item = &Field> {
+        self.reports.iter().filter(move |report| report.kind == kind).flat_map(|report| report.fields.iter())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct GlobalState {
+    usage_page: u16,
+    logical_minimum: i32,
+    logical_maximum: i32,
+    physical_minimum: i32,
+    physical_maximum: i32,
+    unit: u32,
+    unit_exponent: i8,
+    report_size: u32,
+    report_id: u8,
+    report_count: u32,
+
+    // Raw data of the min/max items, whose signedness depends on the minimum
+    logical_maximum_raw: (u32, usize),
+    physical_maximum_raw: (u32, usize),
+}
+
+/// A Usage item as written. Items shorter than 4 bytes take the usage page current at the main item.
+#[derive(Clone, Copy, Debug)]
+struct LocalUsage {
+    value: u32,
+    extended: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+struct LocalState {
+    usages: Vec<(LocalUsage, LocalUsage)>,
+    usage_minimum: Option<LocalUsage>,
+}
+
+#[derive(Default)]
+struct Parser {
+    global: GlobalState,
+    global_stack: Vec<GlobalState>,
+    local: LocalState,
+
+    collections: Vec<Collection>,
+    open_collections: Vec<usize>,
+    reports: Vec<Report>,
+}
+
+impl Parser {
+    fn parse(mut self, bytes: &[u8]) -> Result<ReportDescriptor, crate::Error> {
+        let mut position = 0;
+        while position < bytes.len() {
+            let prefix = bytes[position];
+
+            // Long items carry their own size and have no defined tags, so skip them
+            if prefix == 0xFE {
+                let size = *bytes.get(position + 1)
+                    .ok_or_else(|| invalid(position, "truncated long item"))? as usize;
+                position += 3 + size;
+                continue;
+            }
+
+            let size = match prefix & 0x03 {
+                3 => 4,
+                size => size as usize,
+            };
+            let data = bytes.get(position + 1..position + 1 + size)
+                .ok_or_else(|| invalid(position, "truncated item"))?;
+            let value = data.iter().rev().fold(0_u32, |value, byte| (value << 8) | *byte as u32);
+
+            match prefix & 0x0C {
+                0x00 => self.main_item(prefix & 0xFC, value, position)?,
+                0x04 => self.global_item(prefix & 0xFC, value, size, position)?,
+                0x08 => self.local_item(prefix & 0xFC, value, size),
+                _ => return Err(invalid(position, "reserved item type")),
+            }
+
+            position += 1 + size;
+        }
+
+        if !self.open_collections.is_empty() {
+            return Err(invalid(bytes.len(), "unclosed collection"));
+        }
+
+        Ok(ReportDescriptor {
+            collections: self.collections,
+            reports: self.reports,
+        })
+    }
+
+    fn main_item(&mut self, tag: u8, value: u32, position: usize) -> Result<(), crate::Error> {
+        match tag {
+            // Input, Output, Feature
+            0x80 | 0x90 | 0xB0 => {
+                let kind = match tag {
+                    0x80 => ReportKind::Input,
+                    0x90 => ReportKind::Output,
+                    _ => ReportKind::Feature,
+                };
+                self.add_field(kind, FieldFlags(value), position)?;
+            },
+            // Collection
+            0xA0 => {
+                let usage = self.resolved_usages().first()
+                    .map(|range| Usage::new(range.page, range.minimum))
+                    .unwrap_or_default();
+                self.collections.push(Collection {
+                    kind: CollectionKind::from(value as u8),
+                    usage,
+                    parent: self.open_collections.last().copied(),
+                });
+                self.open_collections.push(self.collections.len() - 1);
+            },
+            // End Collection
+            0xC0 => {
+                if self.open_collections.pop().is_none() {
+                    return Err(invalid(position, "End Collection without a Collection"));
+                }
+            },
+            _ => return Err(invalid(position, "unknown main item")),
+        }
+
+        // Local state only lasts until the next main item
+        self.local = LocalState::default();
+        Ok(())
+    }
+
+    fn global_item(&mut self, tag: u8, value: u32, size: usize, position: usize) -> Result<(), crate::Error> {
+        let global = &mut self.global;
+        match tag {
+            0x04 => global.usage_page = value as u16,
+            0x14 => {
+                global.logical_minimum = sign_extend(value, size);
+                global.logical_maximum = extend_maximum(global.logical_minimum, global.logical_maximum_raw);
+            },
+            0x24 => {
+                global.logical_maximum_raw = (value, size);
+                global.logical_maximum = extend_maximum(global.logical_minimum, global.logical_maximum_raw);
+            },
+            0x34 => {
+                global.physical_minimum = sign_extend(value, size);
+                global.physical_maximum = extend_maximum(global.physical_minimum, global.physical_maximum_raw);
+            },
+            0x44 => {
+                global.physical_maximum_raw = (value, size);
+                global.physical_maximum = extend_maximum(global.physical_minimum, global.physical_maximum_raw);
+            },
+            0x54 => {
+                // Almost every descriptor writes the exponent as a 4 bit two's complement nibble
+                global.unit_exponent = if value <= 0x0F {
+                    ((value as i8) << 4) >> 4
+                } else {
+                    sign_extend(value, size) as i8
+                };
+            },
+            0x64 => global.unit = value,
+            0x74 => {
+                // Field values are decoded into a u32
+                if value > 32 {
+                    return Err(invalid(position, "report size over 32 bits"));
+                }
+                global.report_size = value;
+            },
+            0x84 => {
+                if value == 0 || value > 0xFF {
+                    return Err(invalid(position, "report ID out of range"));
+                }
+                global.report_id = value as u8;
+            },
+            0x94 => global.report_count = value,
+            // Push
+            0xA4 => self.global_stack.push(*global),
+            // Pop
+            0xB4 => {
+                *global = self.global_stack.pop()
+                    .ok_or_else(|| invalid(position, "Pop without a Push"))?;
+            },
+            _ => return Err(invalid(position, "unknown global item")),
+        }
+        Ok(())
+    }
+
+    fn local_item(&mut self, tag: u8, value: u32, size: usize) {
+        let usage = LocalUsage { value, extended: size == 4 };
+        match tag {
+            // Usage
+            0x08 => self.local.usages.push((usage, usage)),
+            // Usage Minimum
+            0x18 => self.local.usage_minimum = Some(usage),
+            // Usage Maximum
+            0x28 => {
+                if let Some(minimum) = self.local.usage_minimum.take() {
+                    self.local.usages.push((minimum, usage));
+                }
+            },
+            // Designators, strings and delimiters don't affect the report layout
+            _ => {}
+        }
+    }
+
+    fn resolved_usages(&self) -> Vec<UsageRange> {
+        let resolve = |usage: LocalUsage| {
+            if usage.extended {
+                ((usage.value >> 16) as u16, usage.value as u16)
+            } else {
+                (self.global.usage_page, usage.value as u16)
+            }
+        };
+
+        self.local.usages.iter()
+            .map(|(minimum, maximum)| {
+                let (page, minimum) = resolve(*minimum);
+                let (_, maximum) = resolve(*maximum);
+                UsageRange { page, minimum, maximum: maximum.max(minimum) }
+            })
+            .collect()
+    }
+
+    fn add_field(&mut self, kind: ReportKind, flags: FieldFlags, position: usize) -> Result<(), crate::Error> {
+        let global = self.global;
+        let report_index = match self.reports.iter().position(|report| report.kind == kind && report.id == global.report_id) {
+            Some(index) => index,
+            None => {
+                self.reports.push(Report { kind, id: global.report_id, bit_length: 0, fields: Vec::new() });
+                self.reports.len() - 1
+            },
+        };
+
+        let field = Field {
+            kind,
+            report_id: global.report_id,
+            usages: self.resolved_usages(),
+            logical_minimum: global.logical_minimum,
+            logical_maximum: global.logical_maximum,
+            physical_minimum: global.physical_minimum,
+            physical_maximum: global.physical_maximum,
+            unit: global.unit,
+            unit_exponent: global.unit_exponent,
+            report_size: global.report_size,
+            report_count: global.report_count,
+            flags,
+            bit_offset: self.reports[report_index].bit_length,
+            collection: self.open_collections.last().copied(),
+        };
+
+        // Longer reports couldn't be read anyway, and encoding one would allocate for every bit of
+        // it. Elements without any bits still count, since setting reports walks through them.
+        let report = &mut self.reports[report_index];
+        report.bit_length = global.report_size.checked_mul(global.report_count)
+            .and_then(|bits| report.bit_length.checked_add(bits))
+            .filter(|&bits| bits <= MAX_REPORT_BITS && global.report_count <= MAX_REPORT_BITS)
+            .ok_or_else(|| invalid(position, "report too long"))?;
+        report.fields.push(field);
+        Ok(())
+    }
+}
+
+/// The longest report hidraw and IOKit hand over is 4096 bytes
+const MAX_REPORT_BITS: u32 = 4096 * 8;
+
+fn invalid(position: usize, message: &str) -> crate::Error {
+    crate::Error::InvalidReportDescriptor(format!("{} at byte {}", message, position))
+}
+
+fn sign_extend(value: u32, size: usize) -> i32 {
+    match size {
+        1 => value as u8 as i8 as i32,
+        2 => value as u16 as i16 as i32,
+        _ => value as i32,
+    }
+}
+
+/// Maximums are signed only when the minimum is negative; plenty of descriptors
+/// write 255 as `0x25 0xFF` next to a minimum of 0.
+fn extend_maximum(minimum: i32, (value, size): (u32, usize)) -> i32 {
+    if minimum < 0 {
+        sign_extend(value, size)
+    } else {
+        value as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CollectionKind, ReportDescriptor, ReportKind, Usage, UsageRange};
+    use crate::hid::fixtures::{BOOT_KEYBOARD, BOOT_MOUSE, REPORT_ID_MOUSE};
+
+    #[test]
+    fn boot_mouse() {
+        let descriptor = ReportDescriptor::parse(&BOOT_MOUSE).unwrap();
+
+        assert_eq!(descriptor.collections.len(), 2);
+        assert_eq!(descriptor.collections[0].kind, CollectionKind::Application);
+        assert_eq!(descriptor.collections[0].usage, Usage::new(0x01, 0x02));
+        assert_eq!(descriptor.collections[1].kind, CollectionKind::Physical);
+        assert_eq!(descriptor.collections[1].usage, Usage::new(0x01, 0x01));
+        assert_eq!(descriptor.collections[1].parent, Some(0));
+        assert_eq!(descriptor.application_usages(), vec![Usage::new(0x01, 0x02)]);
+
+        assert!(!descriptor.uses_report_ids());
+        let report = descriptor.report(ReportKind::Input, 0).unwrap();
+        assert_eq!(report.bit_length, 24);
+        assert_eq!(report.byte_length(), 3);
+        assert_eq!(report.fields.len(), 3);
+
+        let buttons = &report.fields[0];
+        assert_eq!(buttons.usages, vec![UsageRange { page: 0x09, minimum: 1, maximum: 3 }]);
+        assert_eq!((buttons.report_size, buttons.report_count, buttons.bit_offset), (1, 3, 0));
+        assert!(buttons.flags.is_variable() && !buttons.flags.is_relative());
+        assert_eq!(buttons.collection, Some(1));
+
+        let padding = &report.fields[1];
+        assert!(padding.flags.is_constant());
+        assert!(padding.usages.is_empty());
+        assert_eq!((padding.report_size, padding.bit_offset), (5, 3));
+
+        let axes = &report.fields[2];
+        assert_eq!(axes.usage(0), Some(Usage::new(0x01, 0x30)));
+        assert_eq!(axes.usage(1), Some(Usage::new(0x01, 0x31)));
+        assert_eq!((axes.logical_minimum, axes.logical_maximum), (-127, 127));
+        assert_eq!(axes.physical_range(), (-127, 127));
+        assert!(axes.flags.is_relative());
+        assert_eq!((axes.report_size, axes.report_count, axes.bit_offset), (8, 2, 8));
+    }
+
+    #[test]
+    fn boot_keyboard() {
+        let descriptor = ReportDescriptor::parse(&BOOT_KEYBOARD).unwrap();
+
+        let input = descriptor.report(ReportKind::Input, 0).unwrap();
+        assert_eq!(input.bit_length, 64);
+        let modifiers = &input.fields[0];
+        assert_eq!(modifiers.usages, vec![UsageRange { page: 0x07, minimum: 0xE0, maximum: 0xE7 }]);
+        assert!(modifiers.flags.is_variable());
+        let keys = &input.fields[2];
+        assert!(keys.flags.is_array());
+        assert_eq!((keys.report_size, keys.report_count, keys.bit_offset), (8, 6, 16));
+        assert_eq!((keys.logical_minimum, keys.logical_maximum), (0, 0x65));
+        assert_eq!(keys.usages, vec![UsageRange { page: 0x07, minimum: 0x00, maximum: 0x65 }]);
+
+        // The LED output report
+        let output = descriptor.report(ReportKind::Output, 0).unwrap();
+        assert_eq!(output.bit_length, 8);
+        assert_eq!(output.fields[0].usages, vec![UsageRange { page: 0x08, minimum: 1, maximum: 5 }]);
+    }
+
+    #[test]
+    fn report_ids_and_extended_usages() {
+        let descriptor = ReportDescriptor::parse(&REPORT_ID_MOUSE).unwrap();
+
+        assert!(descriptor.uses_report_ids());
+        assert_eq!(descriptor.application_usages(), vec![Usage::new(0x01, 0x02), Usage::new(0xFF00, 0x01)]);
+
+        let report = descriptor.report(ReportKind::Input, 2).unwrap();
+        assert_eq!(report.bit_length, 16 + 24 + 8 + 8);
+        assert_eq!(report.byte_length(), 8);
+
+        let axes = &report.fields[1];
+        assert_eq!((axes.logical_minimum, axes.logical_maximum), (-2047, 2047));
+        assert_eq!((axes.report_size, axes.bit_offset), (12, 16));
+
+        // AC Pan uses a two byte usage on the Consumer page
+        let pan = &report.fields[3];
+        assert_eq!(pan.usage(0), Some(Usage::new(0x0C, 0x0238)));
+        assert_eq!(pan.bit_offset, 48);
+    }
+
+    #[test]
+    fn unsigned_maximum_and_push_pop() {
+        let descriptor = ReportDescriptor::parse(&[
+            0x05, 0x01, 0x09, 0x04, 0xA1, 0x01,
+            0x15, 0x00, 0x25, 0xFF,             // Logical Maximum (255), written as one byte
+            0x35, 0x00, 0x46, 0x10, 0x27,       // Physical Maximum (10000)
+            0x55, 0x0E, 0x65, 0x13,             // Unit Exponent (-2), Unit (inch, linear)
+            0xA4,                               // Push
+            0x75, 0x08, 0x95, 0x01, 0x09, 0x30, 0x81, 0x02,
+            0xB4,                               // Pop
+            0x09, 0x31, 0x81, 0x02,
+            0xC0,
+        ]).unwrap();
+
+        let fields: Vec<_> = descriptor.fields(ReportKind::Input).collect();
+        assert_eq!((fields[0].logical_minimum, fields[0].logical_maximum), (0, 255));
+        assert_eq!(fields[0].physical_range(), (0, 10000));
+        assert_eq!((fields[0].unit, fields[0].unit_exponent), (0x13, -2));
+
+        // The pop restored the size and count from before they were set
+        assert_eq!((fields[1].report_size, fields[1].report_count), (0, 0));
+    }
+
+    #[test]
+    fn millimetres() {
+        let descriptor = ReportDescriptor::parse(&[
+            0x05, 0x0D, 0x09, 0x04, 0xA1, 0x01, 0x05, 0x01,
+            0x15, 0x00, 0x26, 0xE8, 0x03,       // Logical Maximum (1000)
+            0x35, 0x00, 0x45, 0x04,             // Physical Maximum (4)
+            0x65, 0x13, 0x55, 0x00,             // Inches
+            0x75, 0x10, 0x95, 0x01, 0x09, 0x30, 0x81, 0x02,
+            0x65, 0x00,                         // No unit
+            0x09, 0x31, 0x81, 0x02,
+            0xC0,
+        ]).unwrap();
+
+        let fields: Vec<_> = descriptor.fields(ReportKind::Input).collect();
+        assert_eq!(fields[0].millimetres(0), Some(0.0));
+        assert_eq!(fields[0].millimetres(250), Some(25.4));
+        assert_eq!(fields[1].millimetres(250), None);
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(ReportDescriptor::parse(&[0x05]).is_err());
+        assert!(ReportDescriptor::parse(&[0xC0]).is_err());
+        assert!(ReportDescriptor::parse(&[0xA1, 0x01]).is_err());
+        assert!(ReportDescriptor::parse(&[0xB4]).is_err());
+        assert!(ReportDescriptor::parse(&[0x85, 0x00]).is_err());
+        assert!(ReportDescriptor::parse(&[0x75, 0x48]).is_err());
+
+        // Report Size 32 and Report Count 0x7FFFFFFF, twice
+        let too_long = [0x75, 0x20, 0x97, 0xFF, 0xFF, 0xFF, 0x7F, 0x81, 0x02, 0x81, 0x02];
+        assert!(ReportDescriptor::parse(&too_long).is_err());
+    }
+
+    #[test]
+    fn report_length_limit() {
+        // 4096 bytes is as long as a report gets
+        let longest = ReportDescriptor::parse(&[0x75, 0x08, 0x96, 0x00, 0x10, 0x81, 0x02]).unwrap();
+        assert_eq!(longest.reports[0].byte_length(), 4096);
+        assert!(ReportDescriptor::parse(&[0x75, 0x08, 0x96, 0x00, 0x10, 0x81, 0x02, 0x95, 0x01, 0x81, 0x02]).is_err());
+
+        // Report Size 32 and Report Count 0x07FFFFFF doesn't overflow, but would be 512 MiB
+        assert!(ReportDescriptor::parse(&[0x75, 0x20, 0x97, 0xFF, 0xFF, 0xFF, 0x07, 0x81, 0x02]).is_err());
+        // Elements without bits still have to be walked through
+        assert!(ReportDescriptor::parse(&[0x75, 0x00, 0x97, 0xFF, 0xFF, 0xFF, 0x07, 0xB1, 0x02]).is_err());
+    }
+}
@@ -0,0 +1,120 @@
+use std::iter;
+
+use crate::{hid::decoder::insert_bits, Field, Report, ReportDescriptor, ReportKind, Usage};
+
+impl ReportDescriptor {
+    /// Feature reports with every variable element of `usage` set to `value(field)`, report ID byte
+    /// first, 0 when the device doesn't use them.
+    ///
+    /// `current` is asked to fill in each report first, e.g. with a Get Feature request, so the
+    /// other controls in it keep their values. Reports it can't fill in start out zeroed.
+    pub fn feature_reports_setting(&self, usage: Usage, value: impl Fn(&Field) -> i32, mut current: impl FnMut(&mut [u8]) -> bool) -> Vec<Vec<u8>> {
+        let mut reports = Vec::new();
+        for report in self.reports.iter().filter(|report| report.kind == ReportKind::Feature) {
+            let mut data: Option<Vec<u8>> = None;
+            for field in &report.fields {
+                if field.flags.is_constant() || !field.flags.is_variable() {
+                    continue;
+                }
+
+                for element in 0..field.report_count {
+                    if field.usage(element) != Some(usage) {
+                        continue;
+                    }
+
+                    let data = data.get_or_insert_with(|| {
+                        let mut data = vec![0; report.bit_length.div_ceil(8) as usize + 1];
+                        data[0] = report.id;
+                        if !current(&mut data) {
+                            data[1..].fill(0);
+                        }
+                        data[0] = report.id;
+                        data
+                    });
+                    insert_bits(&mut data[1..], field.bit_offset + element * field.report_size, field.report_size, value(field) as u32);
+                }
+            }
+            reports.extend(data);
+        }
+        reports
+    }
+
+    /// The output report of the collection `collection`, such as a Physical Interface Device Set
+    /// Effect Report, report ID byte first, 0 when the device doesn't use them.
+    ///
+    /// Variable elements are set to `value(usage, field)`, clamped to their logical range, and
+    /// array fields select the first of their usages it gives a value for. Everything else is zero.
+    pub fn output_report(&self, collection: Usage, value: impl Fn(Usage, &Field) -> Option<i32>) -> Option<Vec<u8>> {
+        let report = self.reports.iter()
+            .filter(|report| report.kind == ReportKind::Output)
+            .find(|report| report.fields.iter().any(|field| self.in_collection(field, collection)))?;
+
+        Some(encode_report(report, value))
+    }
+
+    /// Whether `field` sits in a collection of `usage`, at any depth.
+    fn in_collection(&self, field: &Field, usage: Usage) -> bool {
+        iter::successors(field.collection, |index| self.collections[*index].parent)
+            .any(|index| self.collections[index].usage == usage)
+    }
+}
+
+/// `report` with every variable element set to `value(usage, field)`, clamped to its logical range,
+/// and every array field selecting the first of its usages `value` gives a value for.
+pub(crate) fn encode_report(report: &Report, value: impl Fn(Usage, &Field) -> Option<i32>) -> Vec<u8> {
+    let mut data = vec![0; report.bit_length.div_ceil(8) as usize + 1];
+    data[0] = report.id;
+    for field in report.fields.iter().filter(|field| !field.flags.is_constant()) {
+        if field.flags.is_variable() {
+            for element in 0..field.report_count {
+                let Some(value) = field.usage(element).and_then(|usage| value(usage, field)) else {
+                    continue;
+                };
+                let value = value.clamp(field.logical_minimum, field.logical_maximum);
+                insert_bits(&mut data[1..], field.bit_offset + element * field.report_size, field.report_size, value as u32);
+            }
+        } else if let Some(index) = (0..field.usage_count()).find(|index| field.usage(*index).is_some_and(|usage| value(usage, field).is_some())) {
+            insert_bits(&mut data[1..], field.bit_offset, field.report_size, (field.logical_minimum + index as i32) as u32);
+        }
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{hid::fixtures::{PID_JOYSTICK, TOUCHPAD}, ReportDescriptor, Usage};
+
+    #[test]
+    fn feature_reports() {
+        let descriptor = ReportDescriptor::parse(&TOUCHPAD).unwrap();
+
+        // Input Mode 3 switches a Precision Touchpad to reporting contacts
+        let reports = descriptor.feature_reports_setting(Usage::new(0x0D, 0x52), |_| 3, |data| {
+            assert_eq!(data[0], 0x03);
+            false
+        });
+        assert_eq!(reports, vec![vec![0x03, 0x03]]);
+
+        assert!(descriptor.feature_reports_setting(Usage::new(0x01, 0x48), |_| 1, |_| false).is_empty());
+    }
+
+    #[test]
+    fn output_reports() {
+        let descriptor = ReportDescriptor::parse(&PID_JOYSTICK).unwrap();
+
+        // Block index 3, the second effect type, and a duration beyond the logical maximum
+        let report = descriptor.output_report(Usage::new(0x0F, 0x21), |usage, _| match usage.id {
+            0x22 => Some(3),
+            0x30 => Some(1),
+            0x50 => Some(100_000),
+            _ => None,
+        });
+        assert_eq!(report, Some(vec![0x02, 0x03, 0x02, 0xFF, 0x7F, 0x00]));
+
+        // Effect Operation sits in its own logical collection inside the report's
+        let report = descriptor.output_report(Usage::new(0x0F, 0x78), |usage, field| (usage.id == 0x7B).then_some(field.logical_maximum));
+        assert_eq!(report, Some(vec![0x04, 0x00, 0x03, 0x00]));
+
+        assert_eq!(descriptor.output_report(Usage::new(0x0F, 0x5A), |_, _| Some(1)), None);
+    }
+}
@@ -0,0 +1,97 @@
+//! Captured report descriptors shared by the HID tests.
+
+/// The boot protocol mouse descriptor from the HID specification, appendix E.10
+pub const BOOT_MOUSE: [u8; 50] = [
+    0x05, 0x01, 0x09, 0x02, 0xA1, 0x01, 0x09, 0x01, 0xA1, 0x00, 0x05, 0x09, 0x19, 0x01, 0x29, 0x03,
+    0x15, 0x00, 0x25, 0x01, 0x95, 0x03, 0x75, 0x01, 0x81, 0x02, 0x95, 0x01, 0x75, 0x05, 0x81, 0x01,
+    0x05, 0x01, 0x09, 0x30, 0x09, 0x31, 0x15, 0x81, 0x25, 0x7F, 0x75, 0x08, 0x95, 0x02, 0x81, 0x06,
+    0xC0, 0xC0,
+];
+
+/// The boot protocol keyboard descriptor from the HID specification, appendix E.6
+pub const BOOT_KEYBOARD: [u8; 63] = [
+    0x05, 0x01, 0x09, 0x06, 0xA1, 0x01, 0x05, 0x07, 0x19, 0xE0, 0x29, 0xE7, 0x15, 0x00, 0x25, 0x01,
+    0x75, 0x01, 0x95, 0x08, 0x81, 0x02, 0x95, 0x01, 0x75, 0x08, 0x81, 0x01, 0x95, 0x05, 0x75, 0x01,
+    0x05, 0x08, 0x19, 0x01, 0x29, 0x05, 0x91, 0x02, 0x95, 0x01, 0x75, 0x03, 0x91, 0x01, 0x95, 0x06,
+    0x75, 0x08, 0x15, 0x00, 0x25, 0x65, 0x05, 0x07, 0x19, 0x00, 0x29, 0x65, 0x81, 0x00, 0xC0,
+];
+
+/// A wireless mouse with report IDs: 16 buttons, 12 bit X/Y, a wheel and AC Pan
+pub const REPORT_ID_MOUSE: [u8; 79] = [
+    0x05, 0x01, 0x09, 0x02, 0xA1, 0x01, 0x85, 0x02, 0x09, 0x01, 0xA1, 0x00, 0x05, 0x09, 0x19, 0x01,
+    0x29, 0x10, 0x15, 0x00, 0x25, 0x01, 0x95, 0x10, 0x75, 0x01, 0x81, 0x02, 0x05, 0x01, 0x16, 0x01,
+    0xF8, 0x26, 0xFF, 0x07, 0x75, 0x0C, 0x95, 0x02, 0x09, 0x30, 0x09, 0x31, 0x81, 0x06, 0x15, 0x81,
+    0x25, 0x7F, 0x75, 0x08, 0x95, 0x01, 0x09, 0x38, 0x81, 0x06, 0x05, 0x0C, 0x0A, 0x38, 0x02, 0x95,
+    0x01, 0x81, 0x06, 0xC0, 0xC0, 0x06, 0x00, 0xFF, 0x09, 0x01, 0xA1, 0x01, 0x85, 0x10, 0xC0,
+];
+
+/// A mouse whose wheel and AC Pan each sit in a logical collection with their own Resolution
+/// Multiplier, 8 for the wheel and 4 for pan, packed into a single feature report
+pub const HIGH_RESOLUTION_MOUSE: [u8; 129] = [
+    0x05, 0x01, 0x09, 0x02, 0xA1, 0x01, 0x09, 0x01, 0xA1, 0x00, 0x05, 0x09, 0x19, 0x01, 0x29, 0x02,
+    0x15, 0x00, 0x25, 0x01, 0x75, 0x01, 0x95, 0x02, 0x81, 0x02, 0x95, 0x01, 0x75, 0x06, 0x81, 0x03,
+    0x05, 0x01, 0x09, 0x30, 0x09, 0x31, 0x15, 0x81, 0x25, 0x7F, 0x75, 0x08, 0x95, 0x02, 0x81, 0x06,
+    0xA1, 0x02, 0x09, 0x48, 0x15, 0x00, 0x25, 0x01, 0x35, 0x01, 0x45, 0x08, 0x75, 0x02, 0x95, 0x01,
+    0xB1, 0x02, 0x35, 0x00, 0x45, 0x00, 0x09, 0x38, 0x15, 0x81, 0x25, 0x7F, 0x75, 0x08, 0x95, 0x01,
+    0x81, 0x06, 0xC0, 0xA1, 0x02, 0x09, 0x48, 0x15, 0x00, 0x25, 0x01, 0x35, 0x01, 0x45, 0x04, 0x75,
+    0x02, 0x95, 0x01, 0xB1, 0x02, 0x35, 0x00, 0x45, 0x00, 0x05, 0x0C, 0x0A, 0x38, 0x02, 0x15, 0x81,
+    0x25, 0x7F, 0x75, 0x08, 0x95, 0x01, 0x81, 0x06, 0xC0, 0x75, 0x04, 0x95, 0x01, 0xB1, 0x03, 0xC0,
+    0xC0,
+];
+
+/// The absolute pointer QEMU emulates as its usb-tablet device: three buttons, X and Y from 0 to
+/// 32767 and a relative wheel
+pub const QEMU_TABLET: [u8; 74] = [
+    0x05, 0x01, 0x09, 0x02, 0xA1, 0x01, 0x09, 0x01, 0xA1, 0x00, 0x05, 0x09, 0x19, 0x01, 0x29, 0x03,
+    0x15, 0x00, 0x25, 0x01, 0x95, 0x03, 0x75, 0x01, 0x81, 0x02, 0x95, 0x01, 0x75, 0x05, 0x81, 0x01,
+    0x05, 0x01, 0x09, 0x30, 0x09, 0x31, 0x15, 0x00, 0x26, 0xFF, 0x7F, 0x35, 0x00, 0x46, 0xFF, 0x7F,
+    0x75, 0x10, 0x95, 0x02, 0x81, 0x02, 0x05, 0x01, 0x09, 0x38, 0x15, 0x81, 0x25, 0x7F, 0x35, 0x00,
+    0x45, 0x00, 0x75, 0x08, 0x95, 0x01, 0x81, 0x06, 0xC0, 0xC0,
+];
+
+/// A Precision Touchpad with two contacts per report, 100 by 50 millimetres, with tip pressure, a
+/// contact count and a button in input report 1, and the Input Mode in feature report 3
+pub const TOUCHPAD: [u8; 225] = [
+    0x05, 0x0D, 0x09, 0x05, 0xA1, 0x01, 0x85, 0x01, 0x09, 0x22, 0xA1, 0x02, 0x15, 0x00, 0x25, 0x01,
+    0x75, 0x01, 0x95, 0x01, 0x09, 0x47, 0x81, 0x02, 0x09, 0x42, 0x81, 0x02, 0x95, 0x06, 0x81, 0x03,
+    0x25, 0x0F, 0x75, 0x08, 0x95, 0x01, 0x09, 0x51, 0x81, 0x02, 0x05, 0x01, 0x26, 0xE8, 0x03, 0x75,
+    0x10, 0x55, 0x0E, 0x65, 0x11, 0x35, 0x00, 0x46, 0xE8, 0x03, 0x09, 0x30, 0x81, 0x02, 0x26, 0xF4,
+    0x01, 0x46, 0xF4, 0x01, 0x09, 0x31, 0x81, 0x02, 0x05, 0x0D, 0x65, 0x00, 0x55, 0x00, 0x45, 0x00,
+    0x26, 0xFF, 0x00, 0x75, 0x08, 0x09, 0x30, 0x81, 0x02, 0xC0, 0x09, 0x22, 0xA1, 0x02, 0x15, 0x00,
+    0x25, 0x01, 0x75, 0x01, 0x95, 0x01, 0x09, 0x47, 0x81, 0x02, 0x09, 0x42, 0x81, 0x02, 0x95, 0x06,
+    0x81, 0x03, 0x25, 0x0F, 0x75, 0x08, 0x95, 0x01, 0x09, 0x51, 0x81, 0x02, 0x05, 0x01, 0x26, 0xE8,
+    0x03, 0x75, 0x10, 0x55, 0x0E, 0x65, 0x11, 0x35, 0x00, 0x46, 0xE8, 0x03, 0x09, 0x30, 0x81, 0x02,
+    0x26, 0xF4, 0x01, 0x46, 0xF4, 0x01, 0x09, 0x31, 0x81, 0x02, 0x05, 0x0D, 0x65, 0x00, 0x55, 0x00,
+    0x45, 0x00, 0x26, 0xFF, 0x00, 0x75, 0x08, 0x09, 0x30, 0x81, 0x02, 0xC0, 0x09, 0x54, 0x25, 0x7F,
+    0x95, 0x01, 0x75, 0x08, 0x81, 0x02, 0x05, 0x09, 0x09, 0x01, 0x25, 0x01, 0x75, 0x01, 0x95, 0x01,
+    0x81, 0x02, 0x95, 0x07, 0x81, 0x03, 0xC0, 0x05, 0x0D, 0x09, 0x0E, 0xA1, 0x01, 0x85, 0x03, 0x09,
+    0x22, 0xA1, 0x02, 0x09, 0x52, 0x15, 0x00, 0x25, 0x0A, 0x75, 0x08, 0x95, 0x01, 0xB1, 0x02, 0xC0,
+    0xC0,
+];
+
+/// A game pad with 15 buttons, a hat switch, two sticks on X/Y and Z/Rz, and analog triggers on
+/// the Simulation page's Brake and Accelerator, all in a single input report without an ID
+pub const GAMEPAD: [u8; 79] = [
+    0x05, 0x01, 0x09, 0x05, 0xA1, 0x01, 0x05, 0x09, 0x19, 0x01, 0x29, 0x0F, 0x15, 0x00, 0x25, 0x01,
+    0x75, 0x01, 0x95, 0x0F, 0x81, 0x02, 0x95, 0x01, 0x81, 0x03, 0x05, 0x01, 0x09, 0x39, 0x25, 0x07,
+    0x35, 0x00, 0x46, 0x3B, 0x01, 0x65, 0x14, 0x75, 0x04, 0x95, 0x01, 0x81, 0x42, 0x65, 0x00, 0x45,
+    0x00, 0x81, 0x03, 0x09, 0x30, 0x09, 0x31, 0x09, 0x32, 0x09, 0x35, 0x26, 0xFF, 0x00, 0x75, 0x08,
+    0x95, 0x04, 0x81, 0x02, 0x05, 0x02, 0x09, 0xC5, 0x09, 0xC4, 0x95, 0x02, 0x81, 0x02, 0xC0,
+];
+
+/// A joystick with Physical Interface Device output reports for a periodic effect: Set Effect (2),
+/// Set Periodic (3) and Effect Operation (4)
+pub const PID_JOYSTICK: [u8; 189] = [
+    0x05, 0x01, 0x09, 0x04, 0xA1, 0x01, 0x85, 0x01, 0x05, 0x09, 0x19, 0x01, 0x29, 0x08, 0x15, 0x00,
+    0x25, 0x01, 0x75, 0x01, 0x95, 0x08, 0x81, 0x02, 0x05, 0x0F, 0x09, 0x21, 0xA1, 0x02, 0x85, 0x02,
+    0x09, 0x22, 0x15, 0x01, 0x25, 0x28, 0x75, 0x08, 0x95, 0x01, 0x91, 0x02, 0x09, 0x25, 0xA1, 0x02,
+    0x09, 0x26, 0x09, 0x30, 0x15, 0x01, 0x25, 0x02, 0x75, 0x08, 0x95, 0x01, 0x91, 0x00, 0xC0, 0x09,
+    0x50, 0x15, 0x00, 0x26, 0xFF, 0x7F, 0x75, 0x10, 0x95, 0x01, 0x91, 0x02, 0x09, 0x52, 0x15, 0x00,
+    0x26, 0xFF, 0x00, 0x75, 0x08, 0x95, 0x01, 0x91, 0x02, 0xC0, 0x09, 0x6E, 0xA1, 0x02, 0x85, 0x03,
+    0x09, 0x22, 0x15, 0x01, 0x25, 0x28, 0x75, 0x08, 0x95, 0x01, 0x91, 0x02, 0x09, 0x70, 0x15, 0x00,
+    0x26, 0x10, 0x27, 0x75, 0x10, 0x95, 0x01, 0x91, 0x02, 0x09, 0x72, 0x15, 0x00, 0x26, 0xFF, 0x7F,
+    0x75, 0x10, 0x95, 0x01, 0x91, 0x02, 0xC0, 0x09, 0x77, 0xA1, 0x02, 0x85, 0x04, 0x09, 0x22, 0x15,
+    0x01, 0x25, 0x28, 0x75, 0x08, 0x95, 0x01, 0x91, 0x02, 0x09, 0x78, 0xA1, 0x02, 0x09, 0x79, 0x09,
+    0x7A, 0x09, 0x7B, 0x15, 0x01, 0x25, 0x03, 0x75, 0x08, 0x95, 0x01, 0x91, 0x00, 0xC0, 0x09, 0x7C,
+    0x15, 0x00, 0x26, 0xFF, 0x00, 0x75, 0x08, 0x95, 0x01, 0x91, 0x02, 0xC0, 0xC0,
+];
@@ -0,0 +1,130 @@
+use crate::Field;
+
+/// Where a hat switch points, in eight directions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum HatDirection {
+    #[default]
+    Centered,
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+impl HatDirection {
+    const CLOCKWISE: [HatDirection; 8] = [
+        HatDirection::Up,
+        HatDirection::UpRight,
+        HatDirection::Right,
+        HatDirection::DownRight,
+        HatDirection::Down,
+        HatDirection::DownLeft,
+        HatDirection::Left,
+        HatDirection::UpLeft,
+    ];
+
+    /// The direction `position` eighths of a turn clockwise from up.
+    pub fn from_position(position: u8) -> HatDirection {
+        Self::CLOCKWISE[position as usize % 8]
+    }
+
+    /// The direction of an SDL hat mask: up 1, right 2, down 4 and left 8. Opposite bits cancel out.
+    pub fn from_mask(mask: u8) -> HatDirection {
+        let vertical = (mask & 0b0100 != 0) as i8 - (mask & 0b0001 != 0) as i8;
+        let horizontal = (mask & 0b0010 != 0) as i8 - (mask & 0b1000 != 0) as i8;
+        match (horizontal, vertical) {
+            (0, -1) => HatDirection::Up,
+            (1, -1) => HatDirection::UpRight,
+            (1, 0) => HatDirection::Right,
+            (1, 1) => HatDirection::DownRight,
+            (0, 1) => HatDirection::Down,
+            (-1, 1) => HatDirection::DownLeft,
+            (-1, 0) => HatDirection::Left,
+            (-1, -1) => HatDirection::UpLeft,
+            _ => HatDirection::Centered,
+        }
+    }
+
+    /// The SDL hat mask of the direction.
+    pub fn mask(&self) -> u8 {
+        (self.up() as u8) | (self.right() as u8) << 1 | (self.down() as u8) << 2 | (self.left() as u8) << 3
+    }
+
+    pub fn up(&self) -> bool {
+        matches!(self, HatDirection::UpLeft | HatDirection::Up | HatDirection::UpRight)
+    }
+
+    pub fn right(&self) -> bool {
+        matches!(self, HatDirection::UpRight | HatDirection::Right | HatDirection::DownRight)
+    }
+
+    pub fn down(&self) -> bool {
+        matches!(self, HatDirection::DownRight | HatDirection::Down | HatDirection::DownLeft)
+    }
+
+    pub fn left(&self) -> bool {
+        matches!(self, HatDirection::DownLeft | HatDirection::Left | HatDirection::UpLeft)
+    }
+}
+
+impl Field {
+    /// The direction of a Hat Switch value.
+    ///
+    /// The logical range counts the positions clockwise from up: eight for hats with diagonals,
+    /// four for those without. Values outside it are the centered null state when the field has
+    /// one, and meaningless otherwise, which gives `None`.
+    pub fn hat_direction(&self, value: i32) -> Option<HatDirection> {
+        let (minimum, maximum, value) = (self.logical_minimum as i64, self.logical_maximum as i64, value as i64);
+        if value < minimum || value > maximum {
+            return self.flags.has_null_state().then_some(HatDirection::Centered);
+        }
+
+        // Scale like the kernel does, so the positions of a four way hat land on up, right, down and left
+        let position = (value - minimum) * 8 / (maximum - minimum + 1);
+        Some(HatDirection::from_position(position as u8))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HatDirection;
+    use crate::{hid::fixtures::GAMEPAD, FieldFlags, ReportDescriptor, Usage};
+
+    #[test]
+    fn directions() {
+        for position in 0..8 {
+            let direction = HatDirection::from_position(position);
+            assert_eq!(HatDirection::from_mask(direction.mask()), direction);
+        }
+        assert_eq!(HatDirection::UpRight.mask(), 0b0011);
+        assert!(HatDirection::DownLeft.down() && HatDirection::DownLeft.left() && !HatDirection::DownLeft.up());
+        assert_eq!(HatDirection::from_mask(0b0101), HatDirection::Centered);
+        assert_eq!(HatDirection::from_mask(0b0111), HatDirection::Right);
+    }
+
+    #[test]
+    fn hat_values() {
+        let descriptor = ReportDescriptor::parse(&GAMEPAD).unwrap();
+        let mut field = descriptor.reports[0].fields.iter().find(|field| field.has_usage(Usage::new(0x01, 0x39))).unwrap().clone();
+
+        // Clockwise from up, with the null state centered
+        assert!(field.flags.has_null_state());
+        assert_eq!((0..=8).map(|value| field.hat_direction(value).unwrap().mask()).collect::<Vec<_>>(), vec![1, 3, 2, 6, 4, 12, 8, 9, 0]);
+
+        // Four way hats skip the diagonals, wherever their range starts
+        (field.logical_minimum, field.logical_maximum) = (1, 4);
+        assert_eq!((1..=4).map(|value| field.hat_direction(value).unwrap()).collect::<Vec<_>>(), vec![
+            HatDirection::Up, HatDirection::Right, HatDirection::Down, HatDirection::Left,
+        ]);
+        assert_eq!(field.hat_direction(0), Some(HatDirection::Centered));
+
+        // Without a null state, values outside the range say nothing
+        field.flags = FieldFlags(0x02);
+        assert_eq!(field.hat_direction(0), None);
+        assert_eq!(field.hat_direction(4), Some(HatDirection::Left));
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{hid::encoder::encode_report, ReportDescriptor, ReportKind};
+
+/// The lock and status LEDs of a keyboard.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct LedState {
+    pub num_lock: bool,
+    pub caps_lock: bool,
+    pub scroll_lock: bool,
+    pub compose: bool,
+    pub kana: bool,
+}
+
+impl LedState {
+    /// Whether the LED of an LED page usage is lit, or `None` for LEDs it doesn't cover.
+    pub fn get(&self, usage: u16) -> Option<bool> {
+        match usage {
+            0x01 => Some(self.num_lock),
+            0x02 => Some(self.caps_lock),
+            0x03 => Some(self.scroll_lock),
+            0x04 => Some(self.compose),
+            0x05 => Some(self.kana),
+            _ => None,
+        }
+    }
+
+    /// Light or clear the LED of an LED page usage, ignoring LEDs it doesn't cover.
+    pub fn set(&mut self, usage: u16, on: bool) {
+        let led = match usage {
+            0x01 => &mut self.num_lock,
+            0x02 => &mut self.caps_lock,
+            0x03 => &mut self.scroll_lock,
+            0x04 => &mut self.compose,
+            0x05 => &mut self.kana,
+            _ => return,
+        };
+        *led = on;
+    }
+}
+
+impl ReportDescriptor {
+    /// The output report that lights `leds`, report ID byte first, 0 when the device doesn't use
+    /// them. LEDs `LedState` doesn't cover are turned off. `None` when the device has no LEDs.
+    pub fn led_report(&self, leds: &LedState) -> Option<Vec<u8>> {
+        let report = self.reports.iter()
+            .filter(|report| report.kind == ReportKind::Output)
+            .find(|report| report.fields.iter().any(|field| field.usages.iter().any(|range| range.page == 0x08)))?;
+
+        Some(encode_report(report, |usage, _| (usage.page == 0x08).then(|| leds.get(usage.id).unwrap_or(false) as i32)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::LedState;
+    use crate::{hid::fixtures::{BOOT_KEYBOARD, BOOT_MOUSE}, Backend, Context, DeviceInfo, Event, Pembejeo, ReportDescriptor};
+
+    /// Keeps the output reports it is asked to send.
+    struct OutputBackend(Arc<Mutex<Vec<Vec<u8>>>>);
+
+    impl Backend for OutputBackend {
+        fn start(&mut self, _context: Context) -> Result<(), crate::Error> {
+            Ok(())
+        }
+
+        fn shutdown(&mut self) {}
+
+        fn set_output_report(&mut self, _device_id: &str, report: &[u8]) -> Result<(), crate::Error> {
+            self.0.lock().unwrap().push(report.to_vec());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn led_state() {
+        let mut leds = LedState::default();
+        leds.set(0x02, true);
+        leds.set(0x4B, true);
+        assert_eq!(leds, LedState { caps_lock: true, ..Default::default() });
+        assert_eq!(leds.get(0x02), Some(true));
+        assert_eq!(leds.get(0x01), Some(false));
+        assert_eq!(leds.get(0x4B), None);
+    }
+
+    #[test]
+    fn led_reports() {
+        // Five LEDs and three bits of padding, without a report ID
+        let descriptor = ReportDescriptor::parse(&BOOT_KEYBOARD).unwrap();
+        let leds = LedState { caps_lock: true, scroll_lock: true, ..Default::default() };
+        assert_eq!(descriptor.led_report(&leds), Some(vec![0x00, 0b0000_0110]));
+        assert_eq!(descriptor.led_report(&LedState::default()), Some(vec![0x00, 0x00]));
+
+        assert_eq!(ReportDescriptor::parse(&BOOT_MOUSE).unwrap().led_report(&leds), None);
+    }
+
+    #[test]
+    fn set_leds() {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let pembejeo = Pembejeo::with_backend(Box::new(OutputBackend(reports.clone()))).unwrap();
+        let keyboard = pembejeo.add_device(DeviceInfo {
+            id: "keyboard".to_string(),
+            vendor_id: 0x1234,
+            product_id: 0x5678,
+            report_descriptor: BOOT_KEYBOARD.to_vec(),
+            ..Default::default()
+        });
+
+        let leds = LedState { num_lock: true, ..Default::default() };
+        pembejeo.set_leds("keyboard", leds).unwrap();
+        assert_eq!(*reports.lock().unwrap(), vec![vec![0x00, 0x01]]);
+
+        let mut event = Event::default();
+        assert!(pembejeo.poll(&mut event) && matches!(event, Event::DeviceAdded(_)));
+        assert!(pembejeo.poll(&mut event));
+        let Event::LedChanged(led_event) = event else {
+            panic!("expected the LEDs, got {:?}", event);
+        };
+        assert_eq!((led_event.device, led_event.leds), (keyboard, leds));
+    }
+}
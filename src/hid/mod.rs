@@ -0,0 +1,15 @@
+mod descriptor;
+mod decoder;
+mod encoder;
+mod hat;
+mod led;
+mod multiplier;
+
+#[cfg(test)]
+pub(crate) mod fixtures;
+
+pub use descriptor::*;
+pub use decoder::*;
+pub use hat::*;
+pub use led::*;
+pub use multiplier::*;
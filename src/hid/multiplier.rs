@@ -0,0 +1,114 @@
+use std::iter;
+
+use crate::{CollectionKind, Field, ReportDescriptor, ReportKind, Usage};
+
+/// Generic Desktop Resolution Multiplier
+const RESOLUTION_MULTIPLIER: Usage = Usage::new(0x01, 0x48);
+
+/// A Resolution Multiplier control in a feature report.
+///
+/// While it is at its highest value, the wheels in its scope report `multiplier` counts per detent
+/// instead of one.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ResolutionMultiplier {
+    pub report_id: u8,
+    /// Index of the field in the feature report's `Report::fields`
+    pub field: usize,
+    pub element: u32,
+
+    /// The collection whose wheels it scales: its logical collection, else its physical one, else its application
+    pub scope: Option<usize>,
+    pub multiplier: f64,
+}
+
+impl ReportDescriptor {
+    /// Every Resolution Multiplier control the device declares.
+    pub fn resolution_multipliers(&self) -> Vec<ResolutionMultiplier> {
+        let mut multipliers = Vec::new();
+        for report in self.reports.iter().filter(|report| report.kind == ReportKind::Feature) {
+            for (index, field) in report.fields.iter().enumerate() {
+                if field.flags.is_constant() || !field.flags.is_variable() {
+                    continue;
+                }
+
+                for element in 0..field.report_count {
+                    if field.usage(element) != Some(RESOLUTION_MULTIPLIER) {
+                        continue;
+                    }
+
+                    // The physical value at the logical maximum, like the kernel, which ignores nonsense
+                    let (_, physical_maximum) = field.physical_range();
+                    let multiplier = if (1..=255).contains(&physical_maximum) { physical_maximum as f64 } else { 1.0 };
+
+                    multipliers.push(ResolutionMultiplier {
+                        report_id: report.id,
+                        field: index,
+                        element,
+                        scope: self.multiplier_scope(field.collection),
+                        multiplier,
+                    });
+                }
+            }
+        }
+        multipliers
+    }
+
+    /// Feature reports setting every Resolution Multiplier to its highest value, or its lowest when
+    /// `enabled` is false. See `feature_reports_setting` for `current`.
+    pub fn resolution_multiplier_reports(&self, enabled: bool, current: impl FnMut(&mut [u8]) -> bool) -> Vec<Vec<u8>> {
+        let value = |field: &Field| if enabled { field.logical_maximum } else { field.logical_minimum };
+        self.feature_reports_setting(RESOLUTION_MULTIPLIER, value, current)
+    }
+
+    /// Whether `collection` is `ancestor` or nested inside it. `None` stands for the whole descriptor.
+    pub fn is_within(&self, collection: Option<usize>, ancestor: Option<usize>) -> bool {
+        match ancestor {
+            Some(ancestor) => iter::successors(collection, |index| self.collections[*index].parent).any(|index| index == ancestor),
+            None => true,
+        }
+    }
+
+    fn multiplier_scope(&self, collection: Option<usize>) -> Option<usize> {
+        let ancestors = || iter::successors(collection, |index| self.collections[*index].parent);
+        ancestors().find(|index| self.collections[*index].kind == CollectionKind::Logical)
+            .or_else(|| ancestors().find(|index| self.collections[*index].kind == CollectionKind::Physical))
+            .or_else(|| ancestors().last())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{hid::fixtures::{BOOT_MOUSE, HIGH_RESOLUTION_MOUSE}, ReportDescriptor, ReportKind, Usage};
+
+    #[test]
+    fn multipliers_and_scopes() {
+        let descriptor = ReportDescriptor::parse(&HIGH_RESOLUTION_MOUSE).unwrap();
+        let multipliers = descriptor.resolution_multipliers();
+        assert_eq!(multipliers.iter().map(|multiplier| multiplier.multiplier).collect::<Vec<_>>(), vec![8.0, 4.0]);
+
+        // Each multiplier only covers the wheel sharing its logical collection
+        let input = descriptor.report(ReportKind::Input, 0).unwrap();
+        let wheel = input.fields.iter().find(|field| field.has_usage(Usage::new(0x01, 0x38))).unwrap();
+        let pan = input.fields.iter().find(|field| field.has_usage(Usage::new(0x0C, 0x0238))).unwrap();
+        assert!(descriptor.is_within(wheel.collection, multipliers[0].scope));
+        assert!(!descriptor.is_within(wheel.collection, multipliers[1].scope));
+        assert!(descriptor.is_within(pan.collection, multipliers[1].scope));
+
+        assert!(ReportDescriptor::parse(&BOOT_MOUSE).unwrap().resolution_multipliers().is_empty());
+    }
+
+    #[test]
+    fn multiplier_reports() {
+        let descriptor = ReportDescriptor::parse(&HIGH_RESOLUTION_MOUSE).unwrap();
+
+        // Both 2-bit multipliers live in one report
+        assert_eq!(descriptor.resolution_multiplier_reports(true, |_| false), vec![vec![0x00, 0b0101]]);
+
+        // Other bits of the current report are kept
+        let reports = descriptor.resolution_multiplier_reports(false, |data| {
+            data[1] = 0xF5;
+            true
+        });
+        assert_eq!(reports, vec![vec![0x00, 0xF0]]);
+    }
+}
@@ -0,0 +1,479 @@
+use crate::{gamepad::GamepadInterpreter, DeviceHandle, touch::TouchInterpreter, Event, Field, FieldValue, KeyCode, KeyEvent, MouseButton, MouseButtonEvent, MouseMotionEvent, Pembejeo, PointerPosition, Report, ReportDescriptor, ResolutionMultiplier, ScaledMotion, ScrollEvent, Timestamp, Usage};
+
+/// Turns decoded HID input reports into events for one device.
+#[derive(Debug, Default)]
+pub(crate) struct ReportInterpreter {
+    /// Reused for every report so decoding doesn't allocate
+    values: Vec<FieldValue>,
+
+    /// Keyboard usages currently held down
+    keys: Vec<u16>,
+    /// Button usages currently held down
+    buttons: Vec<u16>,
+    /// Usages held down according to the report being handled
+    pressed: Vec<u16>,
+
+    /// Resolution multipliers that are turned on, empty while every wheel count is a detent
+    multipliers: Vec<ResolutionMultiplier>,
+    /// Vertical and horizontal scrolling that doesn't add up to a whole detent yet, in 120ths
+    scroll_remainder: [i32; 2],
+    /// What rounding wheel counts to 120ths left over, for multipliers that don't divide 120
+    v120_remainder: [f64; 2],
+
+    touch: TouchInterpreter,
+    gamepad: GamepadInterpreter,
+}
+
+impl ReportInterpreter {
+    pub(crate) fn handle_report(&mut self, pembejeo: &Pembejeo, device: DeviceHandle, timestamp: Timestamp, descriptor: &ReportDescriptor, report: &[u8]) {
+        let Ok(decoded) = descriptor.decode_input(report, &mut self.values) else {
+            return;
+        };
+
+        // Everything a report says about motion goes into a single event
+        let mut mouse_motion_event = MouseMotionEvent::default();
+        let mut scaled: Option<ScaledMotion> = None;
+        let mut scroll_event = ScrollEvent::default();
+
+        for value in &self.values {
+            let field = &decoded.fields[value.field];
+            if !field.flags.is_relative() {
+                // Joysticks and touchpads have absolute axes as well, but they aren't pointers
+                if is_pointer(descriptor.application(field.collection)) {
+                    update_position(&mut mouse_motion_event.position, field, value);
+                }
+                continue;
+            }
+
+            match (value.usage.page, value.usage.id) {
+                // Generic Desktop X, Y and Wheel
+                (0x01, 0x30) => {
+                    mouse_motion_event.x = mouse_motion_event.x.saturating_add(value.value);
+                    if let Some(millimetres) = field.millimetres(value.value) {
+                        scaled.get_or_insert_with(ScaledMotion::default).x += millimetres;
+                    }
+                },
+                (0x01, 0x31) => {
+                    mouse_motion_event.y = mouse_motion_event.y.saturating_add(value.value);
+                    if let Some(millimetres) = field.millimetres(value.value) {
+                        scaled.get_or_insert_with(ScaledMotion::default).y += millimetres;
+                    }
+                },
+                (0x01, 0x38) => {
+                    let v120 = v120(&self.multipliers, &mut self.v120_remainder[0], descriptor, field, value.value);
+                    scroll_event.vertical_v120 = scroll_event.vertical_v120.saturating_add(v120);
+                },
+                // Consumer AC Pan
+                (0x0C, 0x0238) => {
+                    let v120 = v120(&self.multipliers, &mut self.v120_remainder[1], descriptor, field, value.value);
+                    scroll_event.horizontal_v120 = scroll_event.horizontal_v120.saturating_add(v120);
+                },
+                _ => {}
+            }
+        }
+        mouse_motion_event.scaled = scaled;
+
+        if mouse_motion_event.has_motion() {
+            mouse_motion_event.device = device;
+            mouse_motion_event.timestamp = timestamp;
+            pembejeo.push_event(Event::MouseMotion(mouse_motion_event));
+        }
+
+        if scroll_event.has_scroll() {
+            scroll_event.vertical = whole_detents(&mut self.scroll_remainder[0], scroll_event.vertical_v120);
+            scroll_event.horizontal = whole_detents(&mut self.scroll_remainder[1], scroll_event.horizontal_v120);
+            scroll_event.device = device;
+            scroll_event.timestamp = timestamp;
+            pembejeo.push_event(Event::Scroll(scroll_event));
+        }
+
+        // Keys and buttons only produce events when their state changes
+        let rolled_over = self.values.iter().any(|value| value.usage.page == 0x07 && (0x01..=0x03).contains(&value.usage.id));
+        if !rolled_over {
+            update_held(&self.values, decoded, 0x07, &mut self.keys, &mut self.pressed, |usage, pressed| {
+                let key_event = KeyEvent {
+                    device,
+                    timestamp,
+                    usage,
+                    key: KeyCode::from_usage(usage),
+                };
+                pembejeo.push_event(if pressed { Event::KeyDown(key_event) } else { Event::KeyUp(key_event) });
+            });
+        }
+
+        // Game pads and joysticks use the Button page too
+        if !self.gamepad.handle_report(pembejeo, device, timestamp, descriptor, decoded, &self.values) {
+            update_held(&self.values, decoded, 0x09, &mut self.buttons, &mut self.pressed, |usage, pressed| {
+                pembejeo.push_event(Event::MouseButton(MouseButtonEvent {
+                    device,
+                    timestamp,
+                    button: MouseButton::from_usage(usage),
+                    pressed,
+                }));
+            });
+        }
+
+        self.touch.handle_report(pembejeo, device, timestamp, descriptor, decoded, &self.values);
+    }
+}
+
+impl ReportInterpreter {
+    /// Turn the descriptor's Resolution Multipliers on or off with the backend's feature report requests,
+    /// then scale wheel counts to match. `get` fills in a feature report and returns whether it could.
+    pub(crate) fn set_high_resolution_scroll(
+        &mut self,
+        device_id: &str,
+        descriptor: &ReportDescriptor,
+        enabled: bool,
+        get: impl FnMut(&mut [u8]) -> bool,
+        mut set: impl FnMut(&[u8]) -> Result<(), crate::Error>,
+    ) -> Result<(), crate::Error> {
+        let multipliers = descriptor.resolution_multipliers();
+        if multipliers.is_empty() {
+            return Err(crate::Error::NotSupported(format!("{} has no resolution multiplier", device_id)));
+        }
+
+        for report in descriptor.resolution_multiplier_reports(enabled, get) {
+            set(&report)?;
+        }
+
+        self.set_resolution_multipliers(if enabled { multipliers } else { Vec::new() });
+        Ok(())
+    }
+
+    /// Scale wheel counts by the multipliers that were turned on, leaving the others at a detent per count.
+    pub(crate) fn set_resolution_multipliers(&mut self, multipliers: Vec<ResolutionMultiplier>) {
+        self.multipliers = multipliers;
+        self.scroll_remainder = [0, 0];
+        self.v120_remainder = [0.0, 0.0];
+    }
+}
+
+/// Wheel counts in 120ths of a detent, carrying the fraction rounding leaves in `remainder` over
+/// to the next report so every detent still adds up to 120.
+fn v120(multipliers: &[ResolutionMultiplier], remainder: &mut f64, descriptor: &ReportDescriptor, field: &Field, value: i32) -> i32 {
+    match multipliers.iter().find(|multiplier| descriptor.is_within(field.collection, multiplier.scope)) {
+        Some(multiplier) => {
+            let v120 = value as f64 * 120.0 / multiplier.multiplier + *remainder;
+            let rounded = v120.round();
+            *remainder = v120 - rounded;
+            rounded as i32
+        },
+        None => value.saturating_mul(120),
+    }
+}
+
+/// Generic Desktop Pointer and Mouse, and the Digitizer and Pen applications of tablets.
+fn is_pointer(application: Option<Usage>) -> bool {
+    matches!(application.map(|usage| (usage.page, usage.id)), Some((0x01, 0x01 | 0x02) | (0x0D, 0x01 | 0x02)))
+}
+
+/// Set the coordinate an absolute Generic Desktop X or Y value gives.
+fn update_position(position: &mut Option<PointerPosition>, field: &Field, value: &FieldValue) {
+    let (normalized, millimetres) = (field.normalized(value.value), field.millimetres(value.value));
+    match (value.usage.page, value.usage.id) {
+        (0x01, 0x30) => {
+            let position = position.get_or_insert_with(PointerPosition::default);
+            position.x = normalized;
+            if let Some(millimetres) = millimetres {
+                position.millimetres.get_or_insert_with(ScaledMotion::default).x = millimetres;
+            }
+        },
+        (0x01, 0x31) => {
+            let position = position.get_or_insert_with(PointerPosition::default);
+            position.y = normalized;
+            if let Some(millimetres) = millimetres {
+                position.millimetres.get_or_insert_with(ScaledMotion::default).y = millimetres;
+            }
+        },
+        _ => {}
+    }
+}
+
+/// Add `v120` to the partial detent in `remainder` and take out the whole detents.
+fn whole_detents(remainder: &mut i32, v120: i32) -> i32 {
+    // Turning the wheel the other way starts over
+    if (*remainder > 0 && v120 < 0) || (*remainder < 0 && v120 > 0) {
+        *remainder = 0;
+    }
+
+    *remainder = remainder.saturating_add(v120);
+    let detents = *remainder / 120;
+    *remainder -= detents * 120;
+    detents
+}
+
+/// Collect the usages of `page` that are down in this report into `pressed`, report every
+/// difference from `held` through `changed`, releases first, and make the result the new `held`.
+///
+/// ErrorRollOver, POSTFail and ErrorUndefined on the keyboard page fill every slot when the
+/// report can't say which keys are down, so callers skip those reports to keep the previous state.
+pub(crate) fn update_held(values: &[FieldValue], report: &Report, page: u16, held: &mut Vec<u16>, pressed: &mut Vec<u16>, mut changed: impl FnMut(u16, bool)) {
+    pressed.clear();
+    for value in values {
+        if value.usage.page == page && value.value != 0 && !pressed.contains(&value.usage.id) {
+            pressed.push(value.usage.id);
+        }
+    }
+
+    for &usage in held.iter() {
+        if pressed.contains(&usage) {
+            continue;
+        }
+
+        // Usages this report has no field for are reported elsewhere, so they stay held
+        if !report.fields.iter().any(|field| field.has_usage(Usage::new(page, usage))) {
+            pressed.push(usage);
+            continue;
+        }
+
+        changed(usage, false);
+    }
+
+    for &usage in pressed.iter() {
+        if !held.contains(&usage) {
+            changed(usage, true);
+        }
+    }
+
+    std::mem::swap(held, pressed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ReportInterpreter;
+    use crate::{hid::fixtures::{BOOT_KEYBOARD, BOOT_MOUSE, HIGH_RESOLUTION_MOUSE, QEMU_TABLET, REPORT_ID_MOUSE}, DeviceHandle, Event, KeyCode, KeyEvent, MouseButton, MouseButtonEvent, MouseMotionEvent, NullBackend, Pembejeo, ReportDescriptor, ScrollEvent, Timestamp};
+
+    fn drain(pembejeo: &Pembejeo) -> Vec<Event> {
+        let mut events = Vec::new();
+        let mut event = Event::default();
+        while pembejeo.poll(&mut event) {
+            events.push(event.clone());
+        }
+        events
+    }
+
+    #[test]
+    fn one_motion_event_per_report() {
+        let pembejeo = Pembejeo::with_backend(Box::new(NullBackend)).unwrap();
+        let descriptor = ReportDescriptor::parse(&BOOT_MOUSE).unwrap();
+        let mut interpreter = ReportInterpreter::default();
+
+        // Diagonal motion arrives as one event
+        interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::from_nanos(1_000), &descriptor, &[0x00, 0x04, 0xFE]);
+        // Button-only reports don't produce motion
+        interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::from_nanos(2_000), &descriptor, &[0x01, 0x00, 0x00]);
+
+        assert_eq!(drain(&pembejeo), vec![
+            Event::MouseMotion(MouseMotionEvent {
+                device: DeviceHandle(1),
+                timestamp: Timestamp::from_nanos(1_000),
+                x: 4,
+                y: -2,
+                ..Default::default()
+            }),
+            Event::MouseButton(MouseButtonEvent {
+                device: DeviceHandle(1),
+                timestamp: Timestamp::from_nanos(2_000),
+                button: MouseButton::Left,
+                pressed: true,
+            }),
+        ]);
+    }
+
+    #[test]
+    fn wheel_and_pan() {
+        let pembejeo = Pembejeo::with_backend(Box::new(NullBackend)).unwrap();
+        let descriptor = ReportDescriptor::parse(&REPORT_ID_MOUSE).unwrap();
+        let mut interpreter = ReportInterpreter::default();
+
+        // Report 2: no buttons, no X/Y, wheel +1, pan -1
+        interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, &[0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0xFF]);
+
+        assert_eq!(drain(&pembejeo), vec![Event::Scroll(ScrollEvent {
+            device: DeviceHandle(1),
+            timestamp: Timestamp::default(),
+            vertical: 1,
+            horizontal: -1,
+            vertical_v120: 120,
+            horizontal_v120: -120,
+        })]);
+    }
+
+    #[test]
+    fn high_resolution_scroll() {
+        let pembejeo = Pembejeo::with_backend(Box::new(NullBackend)).unwrap();
+        let descriptor = ReportDescriptor::parse(&HIGH_RESOLUTION_MOUSE).unwrap();
+        let mut interpreter = ReportInterpreter::default();
+        interpreter.set_resolution_multipliers(descriptor.resolution_multipliers());
+
+        let scroll = |vertical, horizontal, vertical_v120, horizontal_v120| Event::Scroll(ScrollEvent {
+            device: DeviceHandle(1),
+            timestamp: Timestamp::default(),
+            vertical,
+            horizontal,
+            vertical_v120,
+            horizontal_v120,
+        });
+
+        // Buttons, X, Y, wheel and pan. The wheel counts 8 per detent, pan 4.
+        interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, &[0, 0, 0, 4, 0]);
+        interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, &[0, 0, 0, 5, 1]);
+        // Reversing drops the partial detent
+        interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, &[0, 0, 0, 0xFF, 0]);
+
+        assert_eq!(drain(&pembejeo), vec![
+            scroll(0, 0, 60, 0),
+            scroll(1, 0, 75, 30),
+            scroll(0, 0, -15, 0),
+        ]);
+    }
+
+    #[test]
+    fn uneven_resolution_multiplier() {
+        let pembejeo = Pembejeo::with_backend(Box::new(NullBackend)).unwrap();
+        let descriptor = ReportDescriptor::parse(&HIGH_RESOLUTION_MOUSE).unwrap();
+        let mut interpreter = ReportInterpreter::default();
+
+        // 16 counts per detent are 7.5 120ths each, which rounding alone would turn into 8
+        let mut multipliers = descriptor.resolution_multipliers();
+        multipliers.iter_mut().for_each(|multiplier| multiplier.multiplier = 16.0);
+        interpreter.set_resolution_multipliers(multipliers);
+        for _ in 0..16 {
+            interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, &[0, 0, 0, 1, 0]);
+        }
+
+        let scroll: Vec<_> = drain(&pembejeo).into_iter().map(|event| match event {
+            Event::Scroll(scroll_event) => (scroll_event.vertical, scroll_event.vertical_v120),
+            event => panic!("expected scrolling, got {:?}", event),
+        }).collect();
+        assert_eq!(scroll.iter().map(|(_, v120)| v120).sum::<i32>(), 120);
+        assert_eq!(scroll.iter().map(|(detents, _)| detents).sum::<i32>(), 1);
+        assert_eq!(scroll.last(), Some(&(1, 7)));
+    }
+
+    #[test]
+    fn wide_and_scaled_motion() {
+        let pembejeo = Pembejeo::with_backend(Box::new(NullBackend)).unwrap();
+        let mut interpreter = ReportInterpreter::default();
+
+        // 16 bit relative X/Y where each count is 0.01 cm
+        let descriptor = ReportDescriptor::parse(&[
+            0x05, 0x01, 0x09, 0x02, 0xA1, 0x01, 0x09, 0x01, 0xA1, 0x00,
+            0x09, 0x30, 0x09, 0x31,
+            0x16, 0x01, 0x80, 0x26, 0xFF, 0x7F,     // Logical -32767..32767
+            0x36, 0x01, 0x80, 0x46, 0xFF, 0x7F,     // Physical -32767..32767
+            0x65, 0x11, 0x55, 0x0E,                 // Centimetres, exponent -2
+            0x75, 0x10, 0x95, 0x02, 0x81, 0x06,
+            0xC0, 0xC0,
+        ]).unwrap();
+
+        // X 20000 and Y -30000 no longer fit an i16 once accumulated, but do in one report
+        let x = 20000_i16.to_le_bytes();
+        let y = (-30000_i16).to_le_bytes();
+        interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, &[x[0], x[1], y[0], y[1]]);
+
+        let events = drain(&pembejeo);
+        let Event::MouseMotion(mouse_motion_event) = &events[0] else {
+            panic!("expected motion, got {:?}", events);
+        };
+        assert_eq!((mouse_motion_event.x, mouse_motion_event.y), (20000, -30000));
+        let scaled = mouse_motion_event.scaled.unwrap();
+        assert!((scaled.x - 2000.0).abs() < 1e-9);
+        assert!((scaled.y + 3000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn absolute_pointers() {
+        let pembejeo = Pembejeo::with_backend(Box::new(NullBackend)).unwrap();
+        let descriptor = ReportDescriptor::parse(&QEMU_TABLET).unwrap();
+        let mut interpreter = ReportInterpreter::default();
+
+        // A quarter across and all the way down, which has no physical size
+        interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, &[0, 0x00, 0x20, 0xFF, 0x7F, 0]);
+        let events = drain(&pembejeo);
+        let [Event::MouseMotion(mouse_motion_event)] = &events[..] else {
+            panic!("expected motion, got {:?}", events);
+        };
+        assert_eq!((mouse_motion_event.x, mouse_motion_event.y), (0, 0));
+        let position = mouse_motion_event.position.unwrap();
+        assert!((position.x - 0.25).abs() < 1e-4 && position.y == 1.0);
+        assert_eq!(position.millimetres, None);
+
+        // A pen on a 100 by 50 mm tablet, counting 0.01 mm
+        let descriptor = ReportDescriptor::parse(&[
+            0x05, 0x0D, 0x09, 0x02, 0xA1, 0x01, 0x09, 0x20, 0xA1, 0x00,
+            0x09, 0x42, 0x15, 0x00, 0x25, 0x01, 0x75, 0x01, 0x95, 0x01, 0x81, 0x02,
+            0x75, 0x07, 0x81, 0x03,
+            0x05, 0x01, 0x65, 0x11, 0x55, 0x0D, 0x75, 0x10, 0x95, 0x01, 0x35, 0x00,
+            0x09, 0x30, 0x26, 0x10, 0x27, 0x46, 0x10, 0x27, 0x81, 0x02,      // X 0..10000
+            0x09, 0x31, 0x26, 0x88, 0x13, 0x46, 0x88, 0x13, 0x81, 0x02,      // Y 0..5000
+            0xC0, 0xC0,
+        ]).unwrap();
+        let (x, y) = (2500_u16.to_le_bytes(), 5000_u16.to_le_bytes());
+        interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, &[1, x[0], x[1], y[0], y[1]]);
+        let events = drain(&pembejeo);
+        let [Event::MouseMotion(mouse_motion_event)] = &events[..] else {
+            panic!("expected motion, got {:?}", events);
+        };
+        let millimetres = mouse_motion_event.position.unwrap().millimetres.unwrap();
+        assert!((millimetres.x - 25.0).abs() < 1e-9);
+        assert!((millimetres.y - 50.0).abs() < 1e-9);
+
+        // Joysticks' absolute axes aren't a pointer
+        let descriptor = ReportDescriptor::parse(&[
+            0x05, 0x01, 0x09, 0x04, 0xA1, 0x01, 0x09, 0x30, 0x09, 0x31,
+            0x15, 0x00, 0x26, 0xFF, 0x00, 0x75, 0x08, 0x95, 0x02, 0x81, 0x02, 0xC0,
+        ]).unwrap();
+        interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, &[0, 255]);
+        assert!(!drain(&pembejeo).iter().any(|event| matches!(event, Event::MouseMotion(_))));
+    }
+
+    #[test]
+    fn key_presses_and_releases() {
+        let pembejeo = Pembejeo::with_backend(Box::new(NullBackend)).unwrap();
+        let descriptor = ReportDescriptor::parse(&BOOT_KEYBOARD).unwrap();
+        let mut interpreter = ReportInterpreter::default();
+
+        let key = |usage, key| KeyEvent { device: DeviceHandle(1), timestamp: Timestamp::default(), usage, key };
+
+        // Left shift, then A while shift is held
+        interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, &[0x02, 0, 0, 0, 0, 0, 0, 0]);
+        interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, &[0x02, 0, 0x04, 0, 0, 0, 0, 0]);
+        // Too many keys: the state is kept as is
+        interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, &[0x02, 0, 0x01, 0x01, 0x01, 0x01, 0x01, 0x01]);
+        // A moves to another slot, which is not a new press
+        interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, &[0x02, 0, 0, 0x04, 0, 0, 0, 0]);
+        // Everything released
+        interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, &[0, 0, 0, 0, 0, 0, 0, 0]);
+
+        assert_eq!(drain(&pembejeo), vec![
+            Event::KeyDown(key(0xE1, KeyCode::LeftShift)),
+            Event::KeyDown(key(0x04, KeyCode::A)),
+            Event::KeyUp(key(0xE1, KeyCode::LeftShift)),
+            Event::KeyUp(key(0x04, KeyCode::A)),
+        ]);
+    }
+
+    #[test]
+    fn button_presses_and_releases() {
+        let pembejeo = Pembejeo::with_backend(Box::new(NullBackend)).unwrap();
+        let descriptor = ReportDescriptor::parse(&REPORT_ID_MOUSE).unwrap();
+        let mut interpreter = ReportInterpreter::default();
+
+        let button = |button, pressed| Event::MouseButton(MouseButtonEvent { device: DeviceHandle(1), timestamp: Timestamp::default(), button, pressed });
+
+        // Right, then right and button 4, then button 16 alone
+        interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, &[0x02, 0b0010, 0, 0, 0, 0, 0, 0]);
+        interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, &[0x02, 0b1010, 0, 0, 0, 0, 0, 0]);
+        interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, &[0x02, 0, 0x80, 0, 0, 0, 0, 0]);
+
+        assert_eq!(drain(&pembejeo), vec![
+            button(MouseButton::Right, true),
+            button(MouseButton::Back, true),
+            button(MouseButton::Right, false),
+            button(MouseButton::Back, false),
+            button(MouseButton::Extra(16), true),
+        ]);
+    }
+}
@@ -0,0 +1,102 @@
+/// Generates `KeyCode` and its conversions from a single table of HID usages.
+macro_rules! key_codes {
+    ($($name:ident = $usage:literal,)*) => {
+        /// A key, named after its US layout legend and identified by its Keyboard/Keypad page (0x07) usage.
+        ///
+        /// Keys are physical positions, not characters: `KeyCode::Q` is the key next to Tab on
+        /// every layout, even where it prints an A.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum KeyCode {
+            $($name,)*
+            /// A usage without a name here, e.g. vendor or reserved usages
+            Other(u16),
+        }
+
+        impl KeyCode {
+            /// The key for a Keyboard/Keypad page usage.
+            pub fn from_usage(usage: u16) -> KeyCode {
+                match usage {
+                    $($usage => KeyCode::$name,)*
+                    usage => KeyCode::Other(usage),
+                }
+            }
+
+            /// The key's Keyboard/Keypad page usage.
+            pub fn usage(&self) -> u16 {
+                match self {
+                    $(KeyCode::$name => $usage,)*
+                    KeyCode::Other(usage) => *usage,
+                }
+            }
+        }
+    };
+}
+
+key_codes! {
+    A = 0x04, B = 0x05, C = 0x06, D = 0x07, E = 0x08, F = 0x09, G = 0x0A, H = 0x0B,
+    I = 0x0C, J = 0x0D, K = 0x0E, L = 0x0F, M = 0x10, N = 0x11, O = 0x12, P = 0x13,
+    Q = 0x14, R = 0x15, S = 0x16, T = 0x17, U = 0x18, V = 0x19, W = 0x1A, X = 0x1B,
+    Y = 0x1C, Z = 0x1D,
+
+    Digit1 = 0x1E, Digit2 = 0x1F, Digit3 = 0x20, Digit4 = 0x21, Digit5 = 0x22,
+    Digit6 = 0x23, Digit7 = 0x24, Digit8 = 0x25, Digit9 = 0x26, Digit0 = 0x27,
+
+    Enter = 0x28, Escape = 0x29, Backspace = 0x2A, Tab = 0x2B, Space = 0x2C,
+    Minus = 0x2D, Equal = 0x2E, LeftBracket = 0x2F, RightBracket = 0x30, Backslash = 0x31,
+    NonUsHash = 0x32, Semicolon = 0x33, Apostrophe = 0x34, Grave = 0x35, Comma = 0x36,
+    Period = 0x37, Slash = 0x38, CapsLock = 0x39,
+
+    F1 = 0x3A, F2 = 0x3B, F3 = 0x3C, F4 = 0x3D, F5 = 0x3E, F6 = 0x3F,
+    F7 = 0x40, F8 = 0x41, F9 = 0x42, F10 = 0x43, F11 = 0x44, F12 = 0x45,
+
+    PrintScreen = 0x46, ScrollLock = 0x47, Pause = 0x48, Insert = 0x49, Home = 0x4A,
+    PageUp = 0x4B, Delete = 0x4C, End = 0x4D, PageDown = 0x4E,
+    Right = 0x4F, Left = 0x50, Down = 0x51, Up = 0x52,
+
+    NumLock = 0x53, KeypadDivide = 0x54, KeypadMultiply = 0x55, KeypadSubtract = 0x56,
+    KeypadAdd = 0x57, KeypadEnter = 0x58, Keypad1 = 0x59, Keypad2 = 0x5A, Keypad3 = 0x5B,
+    Keypad4 = 0x5C, Keypad5 = 0x5D, Keypad6 = 0x5E, Keypad7 = 0x5F, Keypad8 = 0x60,
+    Keypad9 = 0x61, Keypad0 = 0x62, KeypadDecimal = 0x63,
+
+    NonUsBackslash = 0x64, Application = 0x65, Power = 0x66, KeypadEqual = 0x67,
+
+    F13 = 0x68, F14 = 0x69, F15 = 0x6A, F16 = 0x6B, F17 = 0x6C, F18 = 0x6D,
+    F19 = 0x6E, F20 = 0x6F, F21 = 0x70, F22 = 0x71, F23 = 0x72, F24 = 0x73,
+
+    Execute = 0x74, Help = 0x75, Menu = 0x76, Select = 0x77, Stop = 0x78, Again = 0x79,
+    Undo = 0x7A, Cut = 0x7B, Copy = 0x7C, Paste = 0x7D, Find = 0x7E,
+    Mute = 0x7F, VolumeUp = 0x80, VolumeDown = 0x81,
+
+    KeypadComma = 0x85,
+    International1 = 0x87, International2 = 0x88, International3 = 0x89,
+    International4 = 0x8A, International5 = 0x8B,
+    Lang1 = 0x90, Lang2 = 0x91,
+
+    LeftControl = 0xE0, LeftShift = 0xE1, LeftAlt = 0xE2, LeftGui = 0xE3,
+    RightControl = 0xE4, RightShift = 0xE5, RightAlt = 0xE6, RightGui = 0xE7,
+}
+
+impl KeyCode {
+    /// Control, Shift, Alt and GUI on either side.
+    pub fn is_modifier(&self) -> bool {
+        (0xE0..=0xE7).contains(&self.usage())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KeyCode;
+
+    #[test]
+    fn usages_round_trip() {
+        for usage in 0..=0xFF {
+            assert_eq!(KeyCode::from_usage(usage).usage(), usage);
+        }
+        assert_eq!(KeyCode::from_usage(0x04), KeyCode::A);
+        assert_eq!(KeyCode::from_usage(0x2C), KeyCode::Space);
+        assert_eq!(KeyCode::from_usage(0xE5), KeyCode::RightShift);
+        assert_eq!(KeyCode::from_usage(0xA5), KeyCode::Other(0xA5));
+        assert!(KeyCode::LeftGui.is_modifier());
+        assert!(!KeyCode::CapsLock.is_modifier());
+    }
+}
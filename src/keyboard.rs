@@ -1,11 +0,0 @@
-#[derive(Clone, Debug)]
-pub struct Keyboard {
-    pub id: String,
-    pub vender_id: u16,
-    pub product_id: u16,
-
-    pub product: String,
-    pub manufacturer: String
-}
-
-
@@ -1,32 +1,65 @@
 mod pembejeo;
+mod backend;
+mod device;
+mod filter;
 mod mouse;
-mod keyboard;
+mod key_code;
+mod gamepad;
+mod gamepad_mapping;
+mod rumble;
+mod hid;
+mod interpreter;
+mod touch;
+mod gesture;
 mod event;
+mod event_queue;
+mod timestamp;
 mod error;
 
 #[cfg(target_os = "macos")]
 mod apple;
+#[cfg(target_os = "linux")]
+mod linux;
 
 
 pub use pembejeo::*;
+pub use backend::*;
+pub use device::*;
+pub use filter::*;
 pub use mouse::*;
-pub use keyboard::*;
+pub use key_code::*;
+pub use gamepad::*;
+pub use gamepad_mapping::*;
+pub use rumble::Rumble;
+pub use hid::*;
 pub use event::*;
+pub use event_queue::OverflowPolicy;
+pub use timestamp::*;
+pub use gesture::*;
 pub use error::*;
 
+#[cfg(target_os = "macos")]
+pub use apple::IOHIDBackend;
+#[cfg(target_os = "linux")]
+pub use linux::{EvdevBackend, HidrawBackend};
+
 #[cfg(test)]
 mod tests {
-    use crate::Event;
-
     #[test]
+    #[cfg(target_os = "macos")]
     fn hello_world() {
+        use std::time::{Duration, Instant};
+
+        use crate::Event;
+
         println!("Hello, World!");
         let pembejeo = crate::Pembejeo::new().unwrap();
 
         loop {
             println!("Waiting for input!");
+            let next_frame = Instant::now() + Duration::from_millis(16);
             let mut event = Event::default();
-            while pembejeo.wait(&mut event) {
+            while pembejeo.wait_until(&mut event, next_frame) {
                 println!("Event: {:?}", event);
             }
 
@@ -35,6 +68,16 @@ mod tests {
     }
 
     #[test]
+    fn null_backend() {
+        let pembejeo = crate::Pembejeo::with_backend(Box::new(crate::NullBackend)).unwrap();
+
+        let mut event = crate::Event::default();
+        assert!(!pembejeo.poll(&mut event));
+        assert_eq!(event, crate::Event::Empty);
+    }
+
+    #[test]
+    #[cfg(target_os = "macos")]
     fn test_sizes() {
         use core_foundation::string::{CFString, CFStringRef};
         let normal_size = std::mem::size_of::<CFString>();
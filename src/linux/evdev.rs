@@ -0,0 +1,1073 @@
+
+use std::{collections::HashMap, ffi::CStr, fs::{self, File, OpenOptions}, io, mem, os::{fd::{AsRawFd, OwnedFd}, unix::fs::OpenOptionsExt}, path::{Path, PathBuf}, sync::{Arc, Mutex}, thread::{self, JoinHandle}};
+
+use libc::{c_int, c_void, ff_effect, ff_rumble_effect, input_absinfo, input_event, input_id, pollfd, POLLERR, POLLHUP, POLLIN};
+
+use crate::{device::unique_id, linux::{create_pipe, drain, linux_id, read_string, wake, watch_directory, keymap::hid_usage, input::{bitmask_len, eviocgabs, eviocgbit, eviocgled, eviocgname, eviocgphys, eviocguniq, test_bit, ABS_BRAKE, ABS_GAS, ABS_HAT0X, ABS_HAT3Y, ABS_MAX, ABS_RX, ABS_RY, ABS_RZ, ABS_X, ABS_Y, ABS_Z, BTN_BACK, BTN_DEAD, BTN_DPAD_DOWN, BTN_DPAD_LEFT, BTN_DPAD_RIGHT, BTN_DPAD_UP, BTN_EXTRA, BTN_FORWARD, BTN_GAMEPAD, BTN_JOYSTICK, BTN_LEFT, BTN_MIDDLE, BTN_RIGHT, BTN_SIDE, BTN_TASK, BTN_THUMBR, EVIOCGID, EVIOCSCLOCKID, EVIOCSFF, EV_ABS, EV_FF, EV_KEY, EV_LED, EV_MAX, FF_MAX, FF_RUMBLE, LED_KANA, LED_MAX, LED_NUML, EV_REL, KEY_A, KEY_MAX, KEY_SPACE, KEY_Z, REL_HWHEEL, REL_HWHEEL_HI_RES, REL_MAX, REL_WHEEL, REL_WHEEL_HI_RES, REL_X, REL_Y, EV_SYN, SYN_DROPPED, SYN_REPORT}}, gamepad::{centered, push_hat}, Backend, Capabilities, Context, DeviceHandle, DeviceInfo, Event, GamepadAxis, GamepadAxisEvent, GamepadButton, GamepadButtonEvent, HatDirection, JoystickInput, KeyCode, KeyEvent, LedEvent, LedState, MouseButton, MouseButtonEvent, MouseMotionEvent, Rumble, ScrollEvent, SdlGuid, Timestamp, Transport, Usage};
+
+const INPUT_DIRECTORY: &str = "/dev/input";
+
+/// The Linux backend, reading `struct input_event` records from `/dev/input/event*`.
+pub struct EvdevBackend {
+    input_thread: Option<JoinHandle<()>>,
+    wake_fd: Option<OwnedFd>,
+    /// Wakes the input thread up to match the devices against the filters again
+    rescan_fd: Option<OwnedFd>,
+
+    /// Open devices by id, shared with the input thread
+    outputs: Arc<Mutex<HashMap<String, EvdevOutput>>>,
+}
+
+impl EvdevBackend {
+    pub fn new() -> Result<Self, crate::Error> {
+        Ok(EvdevBackend {
+            input_thread: None,
+            wake_fd: None,
+            rescan_fd: None,
+            outputs: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+}
+
+impl Backend for EvdevBackend {
+    fn start(&mut self, context: Context) -> Result<(), crate::Error> {
+        // A pipe to wake the input thread up on shutdown
+        let (wake_read, wake_write) = create_pipe()
+            .map_err(|err| crate::Error::FailedCreatingPembejeo(format!("Failed to create the wake pipe: {}", err)))?;
+        let (rescan_read, rescan_write) = create_pipe()
+            .map_err(|err| crate::Error::FailedCreatingPembejeo(format!("Failed to create the rescan pipe: {}", err)))?;
+
+        // Watch the input directory for hotplugged devices
+        let inotify = watch_directory(c"/dev/input")
+            .map_err(|err| crate::Error::FailedCreatingPembejeo(format!("Failed to watch /dev/input: {}", err)))?;
+
+        let outputs = self.outputs.clone();
+        self.wake_fd = Some(wake_write);
+        self.rescan_fd = Some(rescan_write);
+        self.input_thread = Some(thread::spawn(move || {
+            run_input_loop(context, &outputs, wake_read, rescan_read, inotify);
+        }));
+
+        Ok(())
+    }
+
+    fn shutdown(&mut self) {
+        if let Some(wake_fd) = self.wake_fd.take() {
+            wake(&wake_fd);
+        }
+
+        if let Some(thread) = self.input_thread.take() {
+            thread.join().unwrap();
+        }
+        self.rescan_fd = None;
+    }
+
+    fn apply_filters(&mut self) -> Result<(), crate::Error> {
+        if let Some(rescan_fd) = &self.rescan_fd {
+            wake(rescan_fd);
+        }
+        Ok(())
+    }
+
+    fn rumble(&mut self, device_id: &str, rumble: &Rumble) -> Result<(), crate::Error> {
+        let mut outputs = self.outputs.lock().unwrap();
+        let Some(output) = outputs.get_mut(device_id) else {
+            return Err(crate::Error::DeviceNotFound(device_id.to_string()));
+        };
+        if !output.rumble {
+            return Err(crate::Error::NotSupported(format!("{} has no rumble motors", device_id)));
+        }
+
+        // Stopping needs nothing uploaded
+        if rumble.is_off() {
+            return match output.effect {
+                Some(effect) => write_event(&output.file, EV_FF, effect as u16, 0).map_err(|err| crate::Error::FailedPlayingEffect(err.to_string())),
+                None => Ok(()),
+            };
+        }
+
+        let mut effect: ff_effect = unsafe { mem::zeroed() };
+        effect.type_ = FF_RUMBLE;
+        // -1 asks the kernel for a new effect, after that it is updated in place
+        effect.id = output.effect.unwrap_or(-1);
+        // 0 plays until stopped, which `Pembejeo` does for rumbles too long to fit
+        effect.replay.length = u16::try_from(rumble.duration.as_millis()).unwrap_or(0);
+        unsafe {
+            let magnitudes = &mut *(effect.u.as_mut_ptr() as *mut ff_rumble_effect);
+            magnitudes.strong_magnitude = Rumble::scale(rumble.strong, u16::MAX as i32) as u16;
+            magnitudes.weak_magnitude = Rumble::scale(rumble.weak, u16::MAX as i32) as u16;
+            if libc::ioctl(output.file.as_raw_fd(), EVIOCSFF as _, &mut effect as *mut ff_effect) < 0 {
+                return Err(crate::Error::FailedPlayingEffect(format!("EVIOCSFF failed: {}", io::Error::last_os_error())));
+            }
+        }
+        output.effect = Some(effect.id);
+        write_event(&output.file, EV_FF, effect.id as u16, 1).map_err(|err| crate::Error::FailedPlayingEffect(err.to_string()))
+    }
+
+    fn set_leds(&mut self, device_id: &str, leds: LedState) -> Result<(), crate::Error> {
+        let outputs = self.outputs.lock().unwrap();
+        let Some(output) = outputs.get(device_id) else {
+            return Err(crate::Error::DeviceNotFound(device_id.to_string()));
+        };
+        if !output.leds {
+            return Err(crate::Error::NotSupported(format!("{} has no LEDs", device_id)));
+        }
+
+        // The kernel echoes the LEDs that changed back to every reader, which reports them
+        let write = || {
+            for code in LED_NUML..=LED_KANA {
+                write_event(&output.file, EV_LED, code, leds.get(code + 1).unwrap_or(false) as i32)?;
+            }
+            write_event(&output.file, EV_SYN, SYN_REPORT, 0)
+        };
+        write().map_err(|err| crate::Error::FailedSettingReport(format!("Writing the LEDs failed: {}", err)))
+    }
+}
+
+/// Write an event to a device, e.g. to play a force feedback effect.
+fn write_event(file: &File, type_: u16, code: u16, value: i32) -> io::Result<()> {
+    let mut event: input_event = unsafe { mem::zeroed() };
+    event.type_ = type_;
+    event.code = code;
+    event.value = value;
+
+    let res = unsafe { libc::write(file.as_raw_fd(), &event as *const input_event as *const c_void, mem::size_of::<input_event>()) };
+    if res < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+struct EvdevDevice {
+    id: String,
+    path: PathBuf,
+    /// What the device was announced with, to match it against the filters again
+    info: DeviceInfo,
+    file: Arc<File>,
+
+    /// Motion and scrolling collected since the last SYN_REPORT
+    motion: MouseMotionEvent,
+    scroll: ScrollEvent,
+    /// The kernel enabled high resolution scrolling and reports `REL_WHEEL_HI_RES` in 120ths of a detent
+    high_resolution_scroll: bool,
+
+    /// The `EV_KEY` codes of a gamepad in the order SDL mappings number them
+    buttons: Vec<u16>,
+    /// The LEDs as the kernel last set them, `LED_<name>` being LED page usage - 1
+    leds: LedState,
+    /// An `EV_LED` event changed them since the last SYN_REPORT
+    leds_changed: bool,
+
+    /// The `ABS_` code, minimum and maximum of every gamepad axis other than hats, in SDL's order
+    axes: Vec<(u16, i32, i32)>,
+    hats: Vec<EvdevHat>,
+}
+
+/// What the backend needs of a device to write to it.
+struct EvdevOutput {
+    file: Arc<File>,
+    /// The device plays `FF_RUMBLE` effects
+    rumble: bool,
+    /// The device has `EV_LED` LEDs
+    leds: bool,
+    /// The id the kernel gave the rumble effect when it was first uploaded
+    effect: Option<i16>,
+}
+
+struct EvdevHat {
+    /// The `ABS_HAT<n>X` code, `ABS_HAT<n>Y` follows it
+    code: u16,
+    /// The last X and Y value
+    values: [i32; 2],
+    /// The direction last reported, through the mapping or in the built-in layout
+    direction: HatDirection,
+}
+
+fn run_input_loop(context: Context, outputs: &Mutex<HashMap<String, EvdevOutput>>, wake: OwnedFd, rescan: OwnedFd, inotify: OwnedFd) {
+    let mut devices: Vec<EvdevDevice> = Vec::new();
+    scan_devices(&context, outputs, &mut devices);
+
+    loop {
+        // The wake and rescan pipes and inotify come first, then one entry per device
+        let mut poll_fds: Vec<pollfd> = [wake.as_raw_fd(), rescan.as_raw_fd(), inotify.as_raw_fd()].into_iter()
+            .chain(devices.iter().map(|device| device.file.as_raw_fd()))
+            .map(|fd| pollfd { fd, events: POLLIN, revents: 0 })
+            .collect();
+
+        let res = unsafe { libc::poll(poll_fds.as_mut_ptr(), poll_fds.len() as libc::nfds_t, -1) };
+        if res < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            context.push_event(Event::BackendFailed { message: format!("Polling input devices failed: {}", err) });
+            return;
+        }
+
+        // Shutting down
+        if poll_fds[0].revents != 0 {
+            return;
+        }
+
+        // Collect the devices that went away while reading, newest first so the indices stay valid
+        let mut removed = Vec::new();
+        for (index, poll_fd) in poll_fds[3..].iter().enumerate() {
+            let failed = poll_fd.revents & POLLIN != 0 && read_events(&context, &mut devices[index]).is_err();
+            if failed || poll_fd.revents & (POLLHUP | POLLERR) != 0 {
+                removed.push(index);
+            }
+        }
+        for index in removed.into_iter().rev() {
+            let device = devices.remove(index);
+            outputs.lock().unwrap().remove(&device.id);
+            context.remove_device(&device.id);
+        }
+
+        // The filters changed, so drop the devices they no longer match and look for new ones
+        if poll_fds[1].revents != 0 {
+            drain(&rescan);
+            let (kept, removed) = devices.into_iter().partition(|device| context.matches_device(&device.info));
+            devices = kept;
+            for device in removed {
+                outputs.lock().unwrap().remove(&device.id);
+                context.remove_device(&device.id);
+            }
+            scan_devices(&context, outputs, &mut devices);
+        }
+
+        // A device node was created or had its permissions changed
+        if poll_fds[2].revents != 0 {
+            drain(&inotify);
+            scan_devices(&context, outputs, &mut devices);
+        }
+    }
+}
+
+fn scan_devices(context: &Context, outputs: &Mutex<HashMap<String, EvdevOutput>>, devices: &mut Vec<EvdevDevice>) {
+    let Ok(entries) = fs::read_dir(INPUT_DIRECTORY) else {
+        return;
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with("event")))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        if devices.iter().any(|device| device.path == path) {
+            continue;
+        }
+
+        if let Some(device) = open_device(context, outputs, &path) {
+            devices.push(device);
+        }
+    }
+}
+
+fn open_device(context: &Context, outputs: &Mutex<HashMap<String, EvdevOutput>>, path: &Path) -> Option<EvdevDevice> {
+    // Force feedback needs write access, so fall back to read-only for input
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NONBLOCK | libc::O_CLOEXEC)
+        .open(path)
+        .or_else(|_| OpenOptions::new().read(true).custom_flags(libc::O_NONBLOCK | libc::O_CLOEXEC).open(path))
+        .ok()?;
+    let fd = file.as_raw_fd();
+
+    // Events are stamped with the wall clock unless asked otherwise, which can jump
+    let clock: c_int = libc::CLOCK_MONOTONIC;
+    unsafe { libc::ioctl(fd, EVIOCSCLOCKID as _, &clock as *const c_int) };
+
+    // Get the device's name and ids
+    let mut name_buffer = [0_u8; 256];
+    let mut input_id: input_id = unsafe { mem::zeroed() };
+    unsafe {
+        if libc::ioctl(fd, eviocgname(name_buffer.len()) as _, name_buffer.as_mut_ptr()) < 0 {
+            return None;
+        }
+        if libc::ioctl(fd, EVIOCGID as _, &mut input_id as *mut input_id) < 0 {
+            return None;
+        }
+    }
+    let product = CStr::from_bytes_until_nul(&name_buffer)
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    // Get the device's capabilities
+    let mut ev_bits = [0_u8; bitmask_len(EV_MAX)];
+    let mut rel_bits = [0_u8; bitmask_len(REL_MAX)];
+    let mut key_bits = [0_u8; bitmask_len(KEY_MAX)];
+    let mut abs_bits = [0_u8; bitmask_len(ABS_MAX)];
+    let mut ff_bits = [0_u8; bitmask_len(FF_MAX)];
+    let mut led_bits = [0_u8; bitmask_len(LED_MAX)];
+    let mut led_state = [0_u8; bitmask_len(LED_MAX)];
+    unsafe {
+        if libc::ioctl(fd, eviocgbit(0, ev_bits.len()) as _, ev_bits.as_mut_ptr()) < 0 {
+            return None;
+        }
+        if test_bit(&ev_bits, EV_REL) {
+            libc::ioctl(fd, eviocgbit(EV_REL, rel_bits.len()) as _, rel_bits.as_mut_ptr());
+        }
+        if test_bit(&ev_bits, EV_KEY) {
+            libc::ioctl(fd, eviocgbit(EV_KEY, key_bits.len()) as _, key_bits.as_mut_ptr());
+        }
+        if test_bit(&ev_bits, EV_ABS) {
+            libc::ioctl(fd, eviocgbit(EV_ABS, abs_bits.len()) as _, abs_bits.as_mut_ptr());
+        }
+        if test_bit(&ev_bits, EV_FF) {
+            libc::ioctl(fd, eviocgbit(EV_FF, ff_bits.len()) as _, ff_bits.as_mut_ptr());
+        }
+        if test_bit(&ev_bits, EV_LED) {
+            libc::ioctl(fd, eviocgbit(EV_LED, led_bits.len()) as _, led_bits.as_mut_ptr());
+            libc::ioctl(fd, eviocgled(led_state.len()) as _, led_state.as_mut_ptr());
+        }
+    }
+
+    let capabilities = Capabilities {
+        pointer: test_bit(&rel_bits, REL_X) && test_bit(&rel_bits, REL_Y) && test_bit(&key_bits, BTN_LEFT),
+        scroll: test_bit(&rel_bits, REL_WHEEL) || test_bit(&rel_bits, REL_HWHEEL),
+        keys: [KEY_A, KEY_Z, KEY_SPACE].iter().all(|key| test_bit(&key_bits, *key)),
+        touch: false,
+        gamepad: test_bit(&key_bits, BTN_GAMEPAD) || test_bit(&key_bits, BTN_JOYSTICK),
+        rumble: test_bit(&ff_bits, FF_RUMBLE),
+        leds: (LED_NUML..=LED_KANA).any(|code| test_bit(&led_bits, code)),
+    };
+    if !capabilities.pointer && !capabilities.keys && !capabilities.gamepad {
+        return None;
+    }
+
+    // evdev has no collections, so stand in the usages of the matching ones
+    let mut usages = Vec::new();
+    if capabilities.pointer {
+        usages.push(Usage::new(0x01, 0x02));
+    }
+    if capabilities.keys {
+        usages.push(Usage::new(0x01, 0x06));
+    }
+    if capabilities.gamepad {
+        usages.push(if test_bit(&key_bits, BTN_GAMEPAD) { Usage::new(0x01, 0x05) } else { Usage::new(0x01, 0x04) });
+    }
+
+    let location = read_string(fd, eviocgphys);
+    let serial_number = read_string(fd, eviocguniq);
+    let id = linux_id("evdev", input_id.vendor, input_id.product, &serial_number, &location, &product);
+    let id = unique_id(id, |id| context.devices.lock().unwrap().contains_key(id));
+    let guid = capabilities.gamepad.then(|| SdlGuid::new(input_id.bustype, input_id.vendor, input_id.product, input_id.version, &product));
+    let info = DeviceInfo {
+        id: id.clone(),
+        handle: DeviceHandle::default(),
+        vendor_id: input_id.vendor,
+        product_id: input_id.product,
+        version: input_id.version,
+        transport: Transport::from_bus_type(input_id.bustype),
+        location,
+        usages,
+        capabilities,
+        product,
+        manufacturer: String::new(),
+        serial_number,
+        guid,
+        report_descriptor: Vec::new(),
+    };
+    if !context.matches_device(&info) {
+        return None;
+    }
+
+    let high_resolution_scroll = test_bit(&rel_bits, REL_WHEEL_HI_RES) || test_bit(&rel_bits, REL_HWHEEL_HI_RES);
+
+    // Number the inputs like SDL does, joystick buttons first. Axes are scaled by their range, which only the kernel knows.
+    let (mut buttons, mut axes, mut hats) = (Vec::new(), Vec::new(), Vec::new());
+    if capabilities.gamepad {
+        buttons.extend((BTN_JOYSTICK..KEY_MAX).chain(0..BTN_JOYSTICK).filter(|code| test_bit(&key_bits, *code)));
+        for code in (0..ABS_MAX).filter(|code| test_bit(&abs_bits, *code)) {
+            if (ABS_HAT0X..=ABS_HAT3Y).contains(&code) {
+                // A hat counts once, for its X axis
+                let x = code & !1;
+                if hats.last().is_none_or(|hat: &EvdevHat| hat.code != x) {
+                    hats.push(EvdevHat { code: x, values: [0, 0], direction: HatDirection::Centered });
+                }
+                continue;
+            }
+
+            let mut absinfo: input_absinfo = unsafe { mem::zeroed() };
+            if unsafe { libc::ioctl(fd, eviocgabs(code) as _, &mut absinfo as *mut input_absinfo) } >= 0 {
+                axes.push((code, absinfo.minimum, absinfo.maximum));
+            }
+        }
+    }
+
+    let mut leds = LedState::default();
+    for code in LED_NUML..=LED_KANA {
+        leds.set(code + 1, test_bit(&led_state, code));
+    }
+
+    let file = Arc::new(file);
+    outputs.lock().unwrap().insert(id.clone(), EvdevOutput {
+        file: file.clone(),
+        rumble: capabilities.rumble,
+        leds: capabilities.leds,
+        effect: None,
+    });
+    let mut info = info;
+    info.handle = context.add_device(info.clone());
+    let motion = MouseMotionEvent { device: info.handle, ..Default::default() };
+    let scroll = ScrollEvent { device: info.handle, ..Default::default() };
+
+    Some(EvdevDevice { id, path: path.to_path_buf(), info, file, motion, scroll, high_resolution_scroll, leds, leds_changed: false, buttons, axes, hats })
+}
+
+/// Read every pending event from the device. An error means the device is gone.
+fn read_events(context: &Context, device: &mut EvdevDevice) -> io::Result<()> {
+    let mut buffer: [input_event; 64] = unsafe { mem::zeroed() };
+
+    loop {
+        let res = unsafe { libc::read(device.file.as_raw_fd(), buffer.as_mut_ptr() as *mut c_void, mem::size_of_val(&buffer)) };
+        if res < 0 {
+            let err = io::Error::last_os_error();
+            return match err.kind() {
+                io::ErrorKind::WouldBlock => Ok(()),
+                io::ErrorKind::Interrupted => continue,
+                _ => Err(err),
+            };
+        }
+
+        let count = res as usize / mem::size_of::<input_event>();
+        if count == 0 {
+            return Ok(());
+        }
+
+        for event in &buffer[..count] {
+            handle_input_event(context, device, event);
+        }
+    }
+}
+
+fn handle_input_event(context: &Context, device: &mut EvdevDevice, event: &input_event) {
+    let timestamp = event_time(event);
+    if device.info.capabilities.gamepad && map_joystick_event(context, device, event, timestamp) {
+        return;
+    }
+
+    let motion = &mut device.motion;
+    let scroll = &mut device.scroll;
+    match (event.type_, event.code) {
+        // Motion accumulates until the frame ends
+        (EV_REL, REL_X) => motion.x = motion.x.saturating_add(event.value),
+        (EV_REL, REL_Y) => motion.y = motion.y.saturating_add(event.value),
+        (EV_REL, REL_WHEEL) => scroll.vertical = scroll.vertical.saturating_add(event.value),
+        (EV_REL, REL_HWHEEL) => scroll.horizontal = scroll.horizontal.saturating_add(event.value),
+        (EV_REL, REL_WHEEL_HI_RES) => scroll.vertical_v120 = scroll.vertical_v120.saturating_add(event.value),
+        (EV_REL, REL_HWHEEL_HI_RES) => scroll.horizontal_v120 = scroll.horizontal_v120.saturating_add(event.value),
+
+        (EV_SYN, SYN_REPORT) => {
+            if motion.has_motion() {
+                motion.timestamp = timestamp;
+                context.push_event(Event::MouseMotion(motion.clone()));
+            }
+            motion.reset();
+
+            // Without high resolution events the kernel only reports whole detents
+            if !device.high_resolution_scroll {
+                scroll.vertical_v120 = scroll.vertical.saturating_mul(120);
+                scroll.horizontal_v120 = scroll.horizontal.saturating_mul(120);
+            }
+            if scroll.has_scroll() {
+                scroll.timestamp = timestamp;
+                context.push_event(Event::Scroll(scroll.clone()));
+            }
+            scroll.reset();
+
+            if mem::take(&mut device.leds_changed) {
+                context.push_event(Event::LedChanged(LedEvent {
+                    device: device.info.handle,
+                    timestamp,
+                    leds: device.leds,
+                }));
+            }
+
+            // A hat's X and Y both change in one frame when it turns between diagonals
+            for (index, hat) in device.hats.iter_mut().enumerate() {
+                let direction = hat_direction(hat.values);
+                if direction != hat.direction {
+                    push_hat(context, device.info.handle, timestamp, index as u16, mem::replace(&mut hat.direction, direction), direction);
+                }
+            }
+        },
+        // The kernel's buffer overran, so the partial frame is meaningless
+        (EV_SYN, SYN_DROPPED) => {
+            motion.reset();
+            scroll.reset();
+        },
+
+        // Whoever changed the LEDs, the kernel tells every reader
+        (EV_LED, LED_NUML..=LED_KANA) => {
+            let on = event.value != 0;
+            if device.leds.get(event.code + 1) != Some(on) {
+                device.leds.set(event.code + 1, on);
+                device.leds_changed = true;
+            }
+        },
+
+        // Buttons and keys are reported as they come, autorepeat (2) is left to the application
+        (EV_KEY, BTN_LEFT..=BTN_TASK) => {
+            let pressed = match event.value {
+                0 => false,
+                1 => true,
+                _ => return,
+            };
+            context.push_event(Event::MouseButton(MouseButtonEvent {
+                device: device.info.handle,
+                timestamp,
+                button: mouse_button(event.code),
+                pressed,
+            }));
+        },
+        (EV_KEY, BTN_JOYSTICK..=BTN_THUMBR | BTN_DPAD_UP..=BTN_DPAD_RIGHT) => {
+            let pressed = match event.value {
+                0 => false,
+                1 => true,
+                _ => return,
+            };
+            context.push_event(Event::GamepadButton(GamepadButtonEvent {
+                device: device.info.handle,
+                timestamp,
+                button: gamepad_button(event.code),
+                pressed,
+            }));
+        },
+        (EV_KEY, code) => {
+            let Some(usage) = hid_usage(code) else {
+                return;
+            };
+            let key_event = KeyEvent {
+                device: device.info.handle,
+                timestamp,
+                usage,
+                key: KeyCode::from_usage(usage),
+            };
+            match event.value {
+                1 => context.push_event(Event::KeyDown(key_event)),
+                0 => context.push_event(Event::KeyUp(key_event)),
+                _ => {}
+            }
+        },
+
+        // The kernel only reports axes that moved
+        (EV_ABS, code) => {
+            let Some(axis) = gamepad_axis(code) else {
+                return;
+            };
+            let Some(&(_, minimum, maximum)) = device.axes.iter().find(|known| known.0 == code) else {
+                return;
+            };
+            context.push_event(Event::GamepadAxis(GamepadAxisEvent {
+                device: device.info.handle,
+                timestamp,
+                axis,
+                value: axis.normalize(event.value, minimum, maximum),
+            }));
+        },
+        _ => {}
+    }
+}
+
+/// Report a gamepad event through the device's SDL mapping. Returns false when the device has
+/// none, or the event isn't a joystick input, so it is handled in the built-in layout.
+fn map_joystick_event(context: &Context, device: &mut EvdevDevice, event: &input_event, timestamp: Timestamp) -> bool {
+    let input = match (event.type_, event.code) {
+        (EV_KEY, code) => {
+            let Some(index) = device.buttons.iter().position(|button| *button == code) else {
+                return false;
+            };
+            let pressed = match event.value {
+                0 => false,
+                1 => true,
+                _ => return false,
+            };
+            JoystickInput::Button { index: index as u16, pressed }
+        },
+        (EV_ABS, ABS_HAT0X..=ABS_HAT3Y) => {
+            let Some(index) = device.hats.iter().position(|hat| hat.code == event.code & !1) else {
+                return false;
+            };
+            let hat = &mut device.hats[index];
+            hat.values[(event.code & 1) as usize] = event.value;
+
+            // The built-in layout waits for the end of the frame, unless the mapping takes it
+            let direction = hat_direction(hat.values);
+            if !context.map_joystick_input(device.info.handle, JoystickInput::Hat { index: index as u16, mask: direction.mask() }, timestamp) {
+                return false;
+            }
+            hat.direction = direction;
+            return true;
+        },
+        (EV_ABS, code) => {
+            let Some(index) = device.axes.iter().position(|axis| axis.0 == code) else {
+                return false;
+            };
+            let (_, minimum, maximum) = device.axes[index];
+            JoystickInput::Axis { index: index as u16, value: centered(event.value, minimum, maximum) }
+        },
+        _ => return false,
+    };
+
+    context.map_joystick_input(device.info.handle, input, timestamp)
+}
+
+/// When the kernel read the event from the device, on the clock `EVIOCSCLOCKID` selected.
+fn event_time(event: &input_event) -> Timestamp {
+    Timestamp::from_nanos(event.time.tv_sec as u64 * 1_000_000_000 + event.time.tv_usec as u64 * 1_000)
+}
+
+/// The direction of a hat's X and Y values, which go from -1 up or left to 1 down or right.
+fn hat_direction([x, y]: [i32; 2]) -> HatDirection {
+    let horizontal = match x.signum() {
+        -1 => 0b1000,
+        1 => 0b0010,
+        _ => 0,
+    };
+    let vertical = match y.signum() {
+        -1 => 0b0001,
+        1 => 0b0100,
+        _ => 0,
+    };
+    HatDirection::from_mask(horizontal | vertical)
+}
+
+/// The button for a `BTN_LEFT..=BTN_TASK` code. The kernel assigns HID buttons 4 and 5 to
+/// `BTN_SIDE` and `BTN_EXTRA`, while `BTN_BACK` and `BTN_FORWARD` come from drivers that say so.
+fn mouse_button(code: u16) -> MouseButton {
+    match code {
+        BTN_LEFT => MouseButton::Left,
+        BTN_RIGHT => MouseButton::Right,
+        BTN_MIDDLE => MouseButton::Middle,
+        BTN_SIDE | BTN_BACK => MouseButton::Back,
+        BTN_EXTRA | BTN_FORWARD => MouseButton::Forward,
+        code => MouseButton::Extra(code - BTN_LEFT + 1),
+    }
+}
+
+/// The button for a joystick, gamepad or d-pad code. `BTN_GAMEPAD` onwards follow the order the
+/// kernel gives HID game pad buttons, and joystick buttons have no layout.
+fn gamepad_button(code: u16) -> GamepadButton {
+    match code {
+        BTN_DPAD_UP => GamepadButton::DPadUp,
+        BTN_DPAD_DOWN => GamepadButton::DPadDown,
+        BTN_DPAD_LEFT => GamepadButton::DPadLeft,
+        BTN_DPAD_RIGHT => GamepadButton::DPadRight,
+        BTN_JOYSTICK..=BTN_DEAD => GamepadButton::Other(code - BTN_JOYSTICK + 1),
+        code => GamepadButton::from_usage(code - BTN_GAMEPAD + 1),
+    }
+}
+
+/// The axis for an `ABS_` code. Gamepad drivers put the right stick on `ABS_RX` and `ABS_RY` and
+/// analog triggers on `ABS_Z` and `ABS_RZ`, or on `ABS_BRAKE` and `ABS_GAS` when they simulate pedals.
+fn gamepad_axis(code: u16) -> Option<GamepadAxis> {
+    match code {
+        ABS_X => Some(GamepadAxis::LeftX),
+        ABS_Y => Some(GamepadAxis::LeftY),
+        ABS_RX => Some(GamepadAxis::RightX),
+        ABS_RY => Some(GamepadAxis::RightY),
+        ABS_Z | ABS_BRAKE => Some(GamepadAxis::LeftTrigger),
+        ABS_RZ | ABS_GAS => Some(GamepadAxis::RightTrigger),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::{File, OpenOptions}, io::Write, mem, os::{fd::AsRawFd, unix::fs::OpenOptionsExt}, slice, thread, time::{Duration, Instant}};
+
+    use libc::{input_event, uinput_abs_setup, uinput_setup};
+
+    use super::EvdevBackend;
+    use crate::{linux::input::{ABS_HAT0X, ABS_HAT0Y, ABS_RZ, ABS_X, ABS_Y, BTN_DPAD_UP, BTN_EAST, BTN_LEFT, BTN_NORTH, BTN_RIGHT, BTN_SIDE, BTN_SOUTH, BTN_START, BTN_WEST, EV_ABS, EV_KEY, EV_REL, EV_SYN, KEY_A, KEY_SPACE, KEY_Z, REL_WHEEL, REL_WHEEL_HI_RES, REL_X, REL_Y, SYN_REPORT, UI_ABS_SETUP, UI_DEV_CREATE, UI_DEV_DESTROY, UI_DEV_SETUP, UI_SET_ABSBIT, UI_SET_EVBIT, UI_SET_KEYBIT, UI_SET_RELBIT}, Event, GamepadAxis, GamepadButton, HatDirection, KeyCode, MouseButton, Pembejeo, Timestamp};
+
+    /// An input device created through `/dev/uinput`.
+    pub(crate) struct VirtualDevice {
+        file: File,
+    }
+
+    impl VirtualDevice {
+        /// A mouse with a left button and X/Y motion.
+        pub(crate) fn mouse(name: &str) -> Option<Self> {
+            VirtualDevice::new(name, &[BTN_LEFT, BTN_RIGHT, BTN_SIDE], &[REL_X, REL_Y])
+        }
+
+        /// A gamepad with face buttons, Start, a d-pad, a left stick from -512 to 511 and a right trigger up to 255.
+        pub(crate) fn gamepad(name: &str) -> Option<Self> {
+            let keys = [BTN_SOUTH, BTN_EAST, BTN_NORTH, BTN_WEST, BTN_START, BTN_DPAD_UP];
+            VirtualDevice::with_absolute_axes(name, &keys, &[], &[(ABS_X, -512, 511), (ABS_Y, -512, 511), (ABS_RZ, 0, 255), (ABS_HAT0X, -1, 1), (ABS_HAT0Y, -1, 1)])
+        }
+
+        /// `None` when uinput isn't available, e.g. in containers or without permissions.
+        pub(crate) fn new(name: &str, keys: &[u16], relative_axes: &[u16]) -> Option<Self> {
+            VirtualDevice::with_absolute_axes(name, keys, relative_axes, &[])
+        }
+
+        /// Like `new`, also with absolute axes of `(code, minimum, maximum)`.
+        pub(crate) fn with_absolute_axes(name: &str, keys: &[u16], relative_axes: &[u16], absolute_axes: &[(u16, i32, i32)]) -> Option<Self> {
+            let file = OpenOptions::new().write(true).custom_flags(libc::O_NONBLOCK).open("/dev/uinput").ok()?;
+            let fd = file.as_raw_fd();
+
+            let mut setup: uinput_setup = unsafe { mem::zeroed() };
+            setup.id.bustype = 0x06; // BUS_VIRTUAL
+            setup.id.vendor = 0x1234;
+            setup.id.product = 0x5678;
+            for (dst, src) in setup.name.iter_mut().zip(name.bytes()) {
+                *dst = src as libc::c_char;
+            }
+
+            unsafe {
+                libc::ioctl(fd, UI_SET_EVBIT as _, EV_KEY as libc::c_int);
+                for key in keys {
+                    libc::ioctl(fd, UI_SET_KEYBIT as _, *key as libc::c_int);
+                }
+                libc::ioctl(fd, UI_SET_EVBIT as _, EV_REL as libc::c_int);
+                for axis in relative_axes {
+                    libc::ioctl(fd, UI_SET_RELBIT as _, *axis as libc::c_int);
+                }
+                if !absolute_axes.is_empty() {
+                    libc::ioctl(fd, UI_SET_EVBIT as _, EV_ABS as libc::c_int);
+                }
+                for &(code, minimum, maximum) in absolute_axes {
+                    let mut abs_setup: uinput_abs_setup = mem::zeroed();
+                    abs_setup.code = code;
+                    abs_setup.absinfo.minimum = minimum;
+                    abs_setup.absinfo.maximum = maximum;
+                    libc::ioctl(fd, UI_SET_ABSBIT as _, code as libc::c_int);
+                    libc::ioctl(fd, UI_ABS_SETUP as _, &abs_setup as *const uinput_abs_setup);
+                }
+                if libc::ioctl(fd, UI_DEV_SETUP as _, &setup as *const uinput_setup) < 0 {
+                    return None;
+                }
+                if libc::ioctl(fd, UI_DEV_CREATE as _) < 0 {
+                    return None;
+                }
+            }
+
+            Some(VirtualDevice { file })
+        }
+
+        pub(crate) fn emit(&mut self, type_: u16, code: u16, value: i32) {
+            let mut event: input_event = unsafe { mem::zeroed() };
+            event.type_ = type_;
+            event.code = code;
+            event.value = value;
+            let bytes = unsafe { slice::from_raw_parts(&event as *const input_event as *const u8, mem::size_of::<input_event>()) };
+            self.file.write_all(bytes).unwrap();
+        }
+    }
+
+    impl Drop for VirtualDevice {
+        fn drop(&mut self) {
+            unsafe { libc::ioctl(self.file.as_raw_fd(), UI_DEV_DESTROY as _) };
+        }
+    }
+
+    #[test]
+    #[ignore = "needs write access to /dev/uinput"]
+    fn uinput_mouse_motion() {
+        let name = "pembejeo evdev test mouse";
+        let mut mouse = VirtualDevice::mouse(name).expect("uinput is unavailable");
+
+        let pembejeo = Pembejeo::with_backend(Box::new(EvdevBackend::new().unwrap())).unwrap();
+
+        // Wait for the backend to pick the device up
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let device = loop {
+            if let Some(found) = pembejeo.mice().into_iter().find(|found| found.product == name) {
+                assert_eq!(found.vendor_id, 0x1234);
+                assert_eq!(found.product_id, 0x5678);
+                break found.handle;
+            }
+            assert!(Instant::now() < deadline, "the virtual mouse was never discovered");
+            thread::sleep(Duration::from_millis(10));
+        };
+
+        let sent = Timestamp::now();
+        mouse.emit(EV_REL, REL_X, 5);
+        mouse.emit(EV_REL, REL_Y, -3);
+        mouse.emit(EV_SYN, SYN_REPORT, 0);
+
+        let mut motion = Vec::new();
+        let mut event = Event::default();
+        while motion.is_empty() && Instant::now() < deadline {
+            if pembejeo.poll(&mut event) {
+                if let Event::MouseMotion(mouse_motion_event) = &event {
+                    assert_eq!(mouse_motion_event.device, device);
+                    // Stamped by the kernel on the monotonic clock, not the wall clock
+                    assert!(mouse_motion_event.timestamp >= sent && mouse_motion_event.timestamp <= Timestamp::now());
+                    motion.push((mouse_motion_event.x, mouse_motion_event.y));
+                }
+            } else {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        // Both axes of the frame arrive in one event
+        assert_eq!(motion, vec![(5, -3)]);
+    }
+
+    #[test]
+    #[ignore = "needs write access to /dev/uinput"]
+    fn uinput_mouse_buttons() {
+        let name = "pembejeo evdev test buttons";
+        let mut mouse = VirtualDevice::mouse(name).expect("uinput is unavailable");
+
+        let pembejeo = Pembejeo::with_backend(Box::new(EvdevBackend::new().unwrap())).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !pembejeo.mice().iter().any(|found| found.product == name) {
+            assert!(Instant::now() < deadline, "the virtual mouse was never discovered");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        mouse.emit(EV_KEY, BTN_RIGHT, 1);
+        mouse.emit(EV_SYN, SYN_REPORT, 0);
+        mouse.emit(EV_KEY, BTN_SIDE, 1);
+        mouse.emit(EV_KEY, BTN_RIGHT, 0);
+        mouse.emit(EV_SYN, SYN_REPORT, 0);
+
+        let mut buttons = Vec::new();
+        let mut event = Event::default();
+        while buttons.len() < 3 && Instant::now() < deadline {
+            if pembejeo.poll(&mut event) {
+                if let Event::MouseButton(mouse_button_event) = &event {
+                    buttons.push((mouse_button_event.button, mouse_button_event.pressed));
+                }
+            } else {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        assert_eq!(buttons, vec![(MouseButton::Right, true), (MouseButton::Back, true), (MouseButton::Right, false)]);
+    }
+
+    #[test]
+    #[ignore = "needs write access to /dev/uinput"]
+    fn uinput_hotplug() {
+        let name = "pembejeo evdev test hotplug";
+        let pembejeo = Pembejeo::with_backend(Box::new(EvdevBackend::new().unwrap())).unwrap();
+        let mut mouse = VirtualDevice::mouse(name).expect("uinput is unavailable");
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut event = Event::default();
+        let device = loop {
+            assert!(Instant::now() < deadline, "the virtual mouse was never announced");
+            if !pembejeo.poll(&mut event) {
+                thread::sleep(Duration::from_millis(1));
+                continue;
+            }
+            if let Event::DeviceAdded(device_event) = &event {
+                let info = &device_event.info;
+                if info.product == name {
+                    assert!(info.is_mouse() && !info.is_keyboard());
+                    assert_eq!((info.vendor_id, info.product_id), (0x1234, 0x5678));
+                    break info.handle;
+                }
+            }
+        };
+
+        // Input sent before the device goes away comes out before its removal
+        mouse.emit(EV_REL, REL_X, 1);
+        mouse.emit(EV_SYN, SYN_REPORT, 0);
+        drop(mouse);
+
+        let mut events = Vec::new();
+        while Instant::now() < deadline {
+            if !pembejeo.poll(&mut event) {
+                thread::sleep(Duration::from_millis(1));
+                continue;
+            }
+            match &event {
+                Event::MouseMotion(motion) if motion.device == device => events.push("motion"),
+                Event::DeviceRemoved(removed) if removed.device == device => {
+                    events.push("removed");
+                    break;
+                },
+                _ => {},
+            }
+        }
+
+        assert_eq!(events, vec!["motion", "removed"]);
+        assert_eq!(pembejeo.device(device), None);
+    }
+
+    #[test]
+    #[ignore = "needs write access to /dev/uinput"]
+    fn uinput_scroll() {
+        let name = "pembejeo evdev test wheel";
+        let mut mouse = VirtualDevice::new(name, &[BTN_LEFT], &[REL_X, REL_Y, REL_WHEEL, REL_WHEEL_HI_RES]).expect("uinput is unavailable");
+
+        let pembejeo = Pembejeo::with_backend(Box::new(EvdevBackend::new().unwrap())).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !pembejeo.mice().iter().any(|found| found.product == name) {
+            assert!(Instant::now() < deadline, "the virtual mouse was never discovered");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        // Half a detent, then the rest of it along with the whole detent the kernel would report
+        mouse.emit(EV_REL, REL_WHEEL_HI_RES, 60);
+        mouse.emit(EV_SYN, SYN_REPORT, 0);
+        mouse.emit(EV_REL, REL_WHEEL_HI_RES, 60);
+        mouse.emit(EV_REL, REL_WHEEL, 1);
+        mouse.emit(EV_SYN, SYN_REPORT, 0);
+
+        let mut scroll = Vec::new();
+        let mut event = Event::default();
+        while scroll.len() < 2 && Instant::now() < deadline {
+            if pembejeo.poll(&mut event) {
+                if let Event::Scroll(scroll_event) = &event {
+                    scroll.push((scroll_event.vertical, scroll_event.vertical_v120));
+                }
+            } else {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        assert_eq!(scroll, vec![(0, 60), (1, 60)]);
+    }
+
+    #[test]
+    #[ignore = "needs write access to /dev/uinput"]
+    fn uinput_keyboard_keys() {
+        let name = "pembejeo evdev test keyboard";
+        let mut keyboard = VirtualDevice::new(name, &[KEY_A, KEY_Z, KEY_SPACE], &[]).expect("uinput is unavailable");
+
+        let pembejeo = Pembejeo::with_backend(Box::new(EvdevBackend::new().unwrap())).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let device = loop {
+            if let Some(found) = pembejeo.keyboards().into_iter().find(|found| found.product == name) {
+                break found.handle;
+            }
+            assert!(Instant::now() < deadline, "the virtual keyboard was never discovered");
+            thread::sleep(Duration::from_millis(10));
+        };
+
+        keyboard.emit(EV_KEY, KEY_A, 1);
+        keyboard.emit(EV_SYN, SYN_REPORT, 0);
+        // Autorepeat doesn't produce events
+        keyboard.emit(EV_KEY, KEY_A, 2);
+        keyboard.emit(EV_SYN, SYN_REPORT, 0);
+        keyboard.emit(EV_KEY, KEY_A, 0);
+        keyboard.emit(EV_SYN, SYN_REPORT, 0);
+
+        let mut keys = Vec::new();
+        let mut event = Event::default();
+        while keys.len() < 2 && Instant::now() < deadline {
+            if pembejeo.poll(&mut event) {
+                match &event {
+                    Event::KeyDown(key_event) if key_event.device == device => keys.push((true, key_event.usage, key_event.key)),
+                    Event::KeyUp(key_event) if key_event.device == device => keys.push((false, key_event.usage, key_event.key)),
+                    _ => {}
+                }
+            } else {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        assert_eq!(keys, vec![(true, 0x04, KeyCode::A), (false, 0x04, KeyCode::A)]);
+    }
+
+    #[test]
+    #[ignore = "needs write access to /dev/uinput"]
+    fn uinput_gamepad() {
+        let name = "pembejeo evdev test gamepad";
+        let mut gamepad = VirtualDevice::gamepad(name).expect("uinput is unavailable");
+
+        let pembejeo = Pembejeo::with_backend(Box::new(EvdevBackend::new().unwrap())).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !pembejeo.devices.lock().unwrap().values().any(|found| found.is_gamepad() && found.product == name) {
+            assert!(Instant::now() < deadline, "the virtual gamepad was never discovered");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        gamepad.emit(EV_KEY, BTN_SOUTH, 1);
+        gamepad.emit(EV_ABS, ABS_X, 511);
+        gamepad.emit(EV_ABS, ABS_RZ, 255);
+        gamepad.emit(EV_SYN, SYN_REPORT, 0);
+        gamepad.emit(EV_KEY, BTN_DPAD_UP, 1);
+        gamepad.emit(EV_KEY, BTN_SOUTH, 0);
+        gamepad.emit(EV_SYN, SYN_REPORT, 0);
+        gamepad.emit(EV_ABS, ABS_HAT0X, 1);
+        gamepad.emit(EV_ABS, ABS_HAT0Y, 1);
+        gamepad.emit(EV_SYN, SYN_REPORT, 0);
+
+        let mut buttons = Vec::new();
+        let mut axes = Vec::new();
+        let mut hats = Vec::new();
+        let mut event = Event::default();
+        while hats.is_empty() && Instant::now() < deadline {
+            if pembejeo.poll(&mut event) {
+                match &event {
+                    Event::GamepadButton(button_event) => buttons.push((button_event.button, button_event.pressed)),
+                    Event::GamepadAxis(axis_event) => axes.push((axis_event.axis, axis_event.value)),
+                    Event::GamepadHat(hat_event) => hats.push((hat_event.hat, hat_event.direction)),
+                    _ => {}
+                }
+            } else {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        assert_eq!(buttons, vec![
+            (GamepadButton::South, true), (GamepadButton::DPadUp, true), (GamepadButton::South, false),
+            (GamepadButton::DPadRight, true), (GamepadButton::DPadDown, true),
+        ]);
+        // ABS_RZ is an analog trigger on gamepads
+        assert_eq!(axes, vec![(GamepadAxis::LeftX, 1.0), (GamepadAxis::RightTrigger, 1.0)]);
+        // Both axes of the hat make one move
+        assert_eq!(hats, vec![(0, HatDirection::DownRight)]);
+    }
+
+    #[test]
+    #[ignore = "needs write access to /dev/uinput"]
+    fn uinput_gamepad_mapping() {
+        let name = "pembejeo evdev test mapped gamepad";
+        let mut gamepad = VirtualDevice::gamepad(name).expect("uinput is unavailable");
+
+        let pembejeo = Pembejeo::with_backend(Box::new(EvdevBackend::new().unwrap())).unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let guid = loop {
+            let devices = pembejeo.devices.lock().unwrap();
+            if let Some(guid) = devices.values().find(|found| found.product == name).and_then(|found| found.guid) {
+                assert_eq!((guid.bus(), guid.vendor_id(), guid.product_id()), (0x06, 0x1234, 0x5678));
+                break guid;
+            }
+            drop(devices);
+            assert!(Instant::now() < deadline, "the virtual gamepad was never discovered");
+            thread::sleep(Duration::from_millis(10));
+        };
+
+        // Swap the face buttons and the stick axes. BTN_SOUTH is button 0 and ABS_X axis 0.
+        pembejeo.add_gamepad_mappings(&format!("{},Swapped,a:b1,b:b0,leftx:a1,lefty:a0,", guid)).unwrap();
+
+        gamepad.emit(EV_KEY, BTN_SOUTH, 1);
+        gamepad.emit(EV_ABS, ABS_X, -512);
+        gamepad.emit(EV_SYN, SYN_REPORT, 0);
+
+        let mut inputs = Vec::new();
+        let mut event = Event::default();
+        while inputs.len() < 2 && Instant::now() < deadline {
+            if pembejeo.poll(&mut event) {
+                match &event {
+                    Event::GamepadButton(button_event) => inputs.push(format!("{:?} {}", button_event.button, button_event.pressed)),
+                    Event::GamepadAxis(axis_event) => inputs.push(format!("{:?} {:.1}", axis_event.axis, axis_event.value)),
+                    _ => {}
+                }
+            } else {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+
+        assert_eq!(inputs, vec!["East true", "LeftY -1.0"]);
+    }
+}
@@ -0,0 +1,493 @@
+
+use std::{collections::HashMap, ffi::CStr, fs::{self, File, OpenOptions}, io, mem, os::{fd::{AsRawFd, OwnedFd}, unix::fs::OpenOptionsExt}, path::{Path, PathBuf}, sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex}, thread::{self, JoinHandle}};
+
+use libc::{c_int, c_void, pollfd, POLLERR, POLLHUP, POLLIN};
+
+use crate::{device::unique_id, linux::{create_pipe, drain, linux_id, input::{hidiocgfeature, hidiocgrawname, hidiocgrawphys, hidiocgrawuniq, hidiocsfeature, hidraw_devinfo, hidraw_report_descriptor, HIDIOCGRAWINFO, HIDIOCGRDESC, HIDIOCGRDESCSIZE}, read_string, wake, watch_directory}, interpreter::ReportInterpreter, Backend, Capabilities, Context, DeviceHandle, DeviceInfo, Event, HidReport, HidReportEvent, ReportDescriptor, Timestamp, Transport};
+
+const DEVICE_DIRECTORY: &str = "/dev";
+
+/// The largest input report hidraw hands out in a single read.
+const MAX_REPORT_SIZE: usize = 4096;
+
+/// The Linux raw HID backend, reading reports and report descriptors from `/dev/hidraw*`.
+/// It reaches devices evdev never sees, such as vendor-defined collections.
+pub struct HidrawBackend {
+    input_thread: Option<JoinHandle<()>>,
+    wake_fd: Option<OwnedFd>,
+    /// Wakes the input thread up to match the devices against the filters again
+    rescan_fd: Option<OwnedFd>,
+
+    /// Open devices by id, shared with the input thread
+    devices: Arc<Mutex<HashMap<String, Arc<HidrawDevice>>>>,
+}
+
+impl HidrawBackend {
+    pub fn new() -> Result<Self, crate::Error> {
+        Ok(HidrawBackend {
+            input_thread: None,
+            wake_fd: None,
+            rescan_fd: None,
+            devices: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    fn device(&self, device_id: &str) -> Result<Arc<HidrawDevice>, crate::Error> {
+        let devices = self.devices.lock().unwrap();
+        devices.get(device_id).cloned().ok_or_else(|| crate::Error::DeviceNotFound(device_id.to_string()))
+    }
+}
+
+impl Backend for HidrawBackend {
+    fn start(&mut self, context: Context) -> Result<(), crate::Error> {
+        // A pipe to wake the input thread up on shutdown
+        let (wake_read, wake_write) = create_pipe()
+            .map_err(|err| crate::Error::FailedCreatingPembejeo(format!("Failed to create the wake pipe: {}", err)))?;
+        let (rescan_read, rescan_write) = create_pipe()
+            .map_err(|err| crate::Error::FailedCreatingPembejeo(format!("Failed to create the rescan pipe: {}", err)))?;
+
+        // Watch for hotplugged devices
+        let inotify = watch_directory(c"/dev")
+            .map_err(|err| crate::Error::FailedCreatingPembejeo(format!("Failed to watch /dev: {}", err)))?;
+
+        let devices = self.devices.clone();
+        self.wake_fd = Some(wake_write);
+        self.rescan_fd = Some(rescan_write);
+        self.input_thread = Some(thread::spawn(move || {
+            run_input_loop(context, &devices, wake_read, rescan_read, inotify);
+        }));
+
+        Ok(())
+    }
+
+    fn shutdown(&mut self) {
+        if let Some(wake_fd) = self.wake_fd.take() {
+            wake(&wake_fd);
+        }
+
+        if let Some(thread) = self.input_thread.take() {
+            thread.join().unwrap();
+        }
+        self.rescan_fd = None;
+    }
+
+    fn apply_filters(&mut self) -> Result<(), crate::Error> {
+        if let Some(rescan_fd) = &self.rescan_fd {
+            wake(rescan_fd);
+        }
+        Ok(())
+    }
+
+    fn set_feature_report(&mut self, device_id: &str, report: &[u8]) -> Result<(), crate::Error> {
+        set_feature_report(&self.device(device_id)?.file, report)
+    }
+
+    fn set_output_report(&mut self, device_id: &str, report: &[u8]) -> Result<(), crate::Error> {
+        // hidraw sends writes as output reports, report ID byte first
+        let res = unsafe { libc::write(self.device(device_id)?.file.as_raw_fd(), report.as_ptr() as *const c_void, report.len()) };
+        if res < 0 {
+            return Err(crate::Error::FailedSettingReport(format!("Writing the output report failed: {}", io::Error::last_os_error())));
+        }
+        Ok(())
+    }
+
+    fn get_feature_report(&mut self, device_id: &str, report: &mut [u8]) -> Result<usize, crate::Error> {
+        get_feature_report(&self.device(device_id)?.file, report)
+    }
+
+    fn set_high_resolution_scroll(&mut self, device_id: &str, enabled: bool) -> Result<(), crate::Error> {
+        let device = self.device(device_id)?;
+        let Some(descriptor) = &device.descriptor else {
+            return Err(crate::Error::NotSupported(format!("{} is driven by the kernel, which manages its resolution multiplier", device_id)));
+        };
+
+        let mut interpreter = device.interpreter.lock().unwrap();
+        interpreter.set_high_resolution_scroll(
+            device_id,
+            descriptor,
+            enabled,
+            |report| get_feature_report(&device.file, report).is_ok(),
+            |report| set_feature_report(&device.file, report),
+        )
+    }
+}
+
+fn set_feature_report(file: &File, report: &[u8]) -> Result<(), crate::Error> {
+    // The ioctl takes a mutable buffer even when sending
+    let mut buffer = report.to_vec();
+    let res = unsafe { libc::ioctl(file.as_raw_fd(), hidiocsfeature(buffer.len()) as _, buffer.as_mut_ptr()) };
+    if res < 0 {
+        return Err(crate::Error::FailedSettingReport(format!("HIDIOCSFEATURE failed: {}", io::Error::last_os_error())));
+    }
+    Ok(())
+}
+
+fn get_feature_report(file: &File, report: &mut [u8]) -> Result<usize, crate::Error> {
+    let res = unsafe { libc::ioctl(file.as_raw_fd(), hidiocgfeature(report.len()) as _, report.as_mut_ptr()) };
+    if res < 0 {
+        return Err(crate::Error::FailedGettingReport(format!("HIDIOCGFEATURE failed: {}", io::Error::last_os_error())));
+    }
+    Ok(res as usize)
+}
+
+/// An open device, shared between the backend and its input thread.
+struct HidrawDevice {
+    file: File,
+    path: PathBuf,
+    /// What the device was announced with, to match it against the filters again
+    info: DeviceInfo,
+
+    /// Whether a kernel input driver claimed the device, so evdev reports it as well
+    has_input_driver: bool,
+    /// Set when no kernel input driver claimed the device.
+    /// Those devices never reach evdev, so their reports are interpreted here instead.
+    descriptor: Option<ReportDescriptor>,
+    interpreter: Mutex<ReportInterpreter>,
+    /// Report the raw reports as `Event::HidReport`, when a filter asks for them
+    hid_reports: AtomicBool,
+}
+
+fn run_input_loop(context: Context, devices: &Mutex<HashMap<String, Arc<HidrawDevice>>>, wake: OwnedFd, rescan: OwnedFd, inotify: OwnedFd) {
+    let mut open: Vec<(String, Arc<HidrawDevice>)> = Vec::new();
+    scan_devices(&context, devices, &mut open);
+
+    loop {
+        // The wake and rescan pipes and inotify come first, then one entry per device
+        let mut poll_fds: Vec<pollfd> = [wake.as_raw_fd(), rescan.as_raw_fd(), inotify.as_raw_fd()].into_iter()
+            .chain(open.iter().map(|(_, device)| device.file.as_raw_fd()))
+            .map(|fd| pollfd { fd, events: POLLIN, revents: 0 })
+            .collect();
+
+        let res = unsafe { libc::poll(poll_fds.as_mut_ptr(), poll_fds.len() as libc::nfds_t, -1) };
+        if res < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            context.push_event(Event::BackendFailed { message: format!("Polling hidraw devices failed: {}", err) });
+            return;
+        }
+
+        // Shutting down
+        if poll_fds[0].revents != 0 {
+            return;
+        }
+
+        // Collect the devices that went away while reading, newest first so the indices stay valid
+        let mut removed = Vec::new();
+        for (index, poll_fd) in poll_fds[3..].iter().enumerate() {
+            let (_, device) = &open[index];
+            let failed = poll_fd.revents & POLLIN != 0 && read_reports(&context, device).is_err();
+            if failed || poll_fd.revents & (POLLHUP | POLLERR) != 0 {
+                removed.push(index);
+            }
+        }
+        for index in removed.into_iter().rev() {
+            let (id, _) = open.remove(index);
+            remove_device(&context, devices, id);
+        }
+
+        // The filters changed, so drop the devices they no longer match and look for new ones
+        if poll_fds[1].revents != 0 {
+            drain(&rescan);
+            let (kept, removed): (Vec<_>, Vec<_>) = open.into_iter().partition(|(_, device)| is_wanted(&context, &device.info, device.has_input_driver));
+            open = kept;
+            for (_, device) in &open {
+                device.hid_reports.store(context.wants_hid_reports(&device.info), Ordering::Relaxed);
+            }
+            for (id, _) in removed {
+                remove_device(&context, devices, id);
+            }
+            scan_devices(&context, devices, &mut open);
+        }
+
+        // A device node was created or had its permissions changed
+        if poll_fds[2].revents != 0 {
+            drain(&inotify);
+            scan_devices(&context, devices, &mut open);
+        }
+    }
+}
+
+fn scan_devices(context: &Context, devices: &Mutex<HashMap<String, Arc<HidrawDevice>>>, open: &mut Vec<(String, Arc<HidrawDevice>)>) {
+    let Ok(entries) = fs::read_dir(DEVICE_DIRECTORY) else {
+        return;
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with("hidraw")))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        if open.iter().any(|(_, device)| device.path == path) {
+            continue;
+        }
+
+        if let Some((mut info, file)) = open_device(context, &path) {
+            // Devices with an input driver report their input through evdev, this id only has the raw reports
+            let has_input_driver = has_input_driver(&path);
+            if has_input_driver {
+                info.capabilities = Capabilities { rumble: info.capabilities.rumble, leds: info.capabilities.leds, ..Default::default() };
+            }
+            if !is_wanted(context, &info, has_input_driver) {
+                continue;
+            }
+
+            let descriptor = if has_input_driver { None } else { info.parse_report_descriptor().ok() };
+            let id = info.id.clone();
+            info.handle = context.assign_handle(&id);
+            let hid_reports = AtomicBool::new(context.wants_hid_reports(&info));
+            let device = Arc::new(HidrawDevice { file, path, info: info.clone(), has_input_driver, descriptor, interpreter: Mutex::new(ReportInterpreter::default()), hid_reports });
+
+            devices.lock().unwrap().insert(id.clone(), device.clone());
+            context.add_device(info);
+            open.push((id, device));
+        }
+    }
+}
+
+fn remove_device(context: &Context, devices: &Mutex<HashMap<String, Arc<HidrawDevice>>>, id: String) {
+    devices.lock().unwrap().remove(&id);
+    context.remove_device(&id);
+}
+
+/// Whether a device should be announced. Evdev already announces the ones with an input driver,
+/// so their hidraw nodes only are when the application asks for their raw reports.
+fn is_wanted(context: &Context, info: &DeviceInfo, has_input_driver: bool) -> bool {
+    context.matches_device(info) && (!has_input_driver || context.wants_hid_reports(info))
+}
+
+/// Whether the kernel bound an input driver to the device, making it show up in evdev as well.
+fn has_input_driver(path: &Path) -> bool {
+    let Some(name) = path.file_name() else {
+        return false;
+    };
+    let input_directory = Path::new("/sys/class/hidraw").join(name).join("device/input");
+    fs::read_dir(input_directory).is_ok_and(|mut entries| entries.next().is_some())
+}
+
+fn open_device(context: &Context, path: &Path) -> Option<(DeviceInfo, File)> {
+    // Feature and output reports need write access, so fall back to read-only for input
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NONBLOCK | libc::O_CLOEXEC)
+        .open(path)
+        .or_else(|_| OpenOptions::new().read(true).custom_flags(libc::O_NONBLOCK | libc::O_CLOEXEC).open(path))
+        .ok()?;
+    let fd = file.as_raw_fd();
+
+    // Get the device's name and ids
+    let mut name_buffer = [0_u8; 256];
+    let mut devinfo: hidraw_devinfo = unsafe { mem::zeroed() };
+    unsafe {
+        if libc::ioctl(fd, hidiocgrawname(name_buffer.len()) as _, name_buffer.as_mut_ptr()) < 0 {
+            return None;
+        }
+        if libc::ioctl(fd, HIDIOCGRAWINFO as _, &mut devinfo as *mut hidraw_devinfo) < 0 {
+            return None;
+        }
+    }
+    let product = CStr::from_bytes_until_nul(&name_buffer)
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    // Get the device's report descriptor
+    let report_descriptor = unsafe {
+        let mut size: c_int = 0;
+        if libc::ioctl(fd, HIDIOCGRDESCSIZE as _, &mut size as *mut c_int) < 0 {
+            return None;
+        }
+
+        let mut descriptor: Box<hidraw_report_descriptor> = Box::new(mem::zeroed());
+        descriptor.size = size as u32;
+        if libc::ioctl(fd, HIDIOCGRDESC as _, descriptor.as_mut() as *mut hidraw_report_descriptor) < 0 {
+            return None;
+        }
+        descriptor.value[..descriptor.size as usize].to_vec()
+    };
+
+    let location = read_string(fd, hidiocgrawphys);
+    let serial_number = read_string(fd, hidiocgrawuniq);
+    let id = linux_id("hidraw", devinfo.vendor as u16, devinfo.product as u16, &serial_number, &location, &product);
+
+    let mut info = DeviceInfo {
+        id: unique_id(id, |id| context.devices.lock().unwrap().contains_key(id)),
+        handle: DeviceHandle::default(),
+        vendor_id: devinfo.vendor as u16,
+        product_id: devinfo.product as u16,
+        // hidraw doesn't hand out the release number
+        version: 0,
+        transport: Transport::from_bus_type(devinfo.bustype as u16),
+        location,
+        usages: Vec::new(),
+        capabilities: Capabilities::default(),
+        product,
+        manufacturer: String::new(),
+        serial_number,
+        // evdev reports hidraw gamepads and maps them
+        guid: None,
+        report_descriptor,
+    };
+    info.usages = info.parse_report_descriptor().map(|descriptor| descriptor.application_usages()).unwrap_or_default();
+    info.capabilities = info.report_capabilities();
+
+    Some((info, file))
+}
+
+/// Read every pending report from the device. An error means the device is gone.
+fn read_reports(context: &Context, device: &HidrawDevice) -> io::Result<()> {
+    let mut buffer = [0_u8; MAX_REPORT_SIZE];
+
+    loop {
+        // Each read returns exactly one report
+        let res = unsafe { libc::read(device.file.as_raw_fd(), buffer.as_mut_ptr() as *mut c_void, buffer.len()) };
+        if res < 0 {
+            let err = io::Error::last_os_error();
+            return match err.kind() {
+                io::ErrorKind::WouldBlock => Ok(()),
+                io::ErrorKind::Interrupted => continue,
+                _ => Err(err),
+            };
+        }
+        if res == 0 {
+            return Ok(());
+        }
+
+        // hidraw doesn't say when the report came in, reading it is as close as it gets
+        let timestamp = Timestamp::now();
+        let report = &buffer[..res as usize];
+        if let Some(descriptor) = &device.descriptor {
+            device.interpreter.lock().unwrap().handle_report(context, device.info.handle, timestamp, descriptor, report);
+        }
+        if !device.hid_reports.load(Ordering::Relaxed) {
+            continue;
+        }
+
+        let hid_report_event = HidReportEvent {
+            device: device.info.handle,
+            timestamp,
+            report: HidReport::new(report),
+        };
+        context.push_event(Event::HidReport(hid_report_event));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs::{File, OpenOptions}, io::Write, thread, time::{Duration, Instant}};
+
+    use super::HidrawBackend;
+    use crate::{DeviceFilter, Event, Pembejeo};
+
+    const UHID_DESTROY: u32 = 1;
+    const UHID_CREATE2: u32 = 11;
+    const UHID_INPUT2: u32 = 12;
+
+    /// Size of the packed `struct uhid_event`
+    const UHID_EVENT_SIZE: usize = 4 + 4372;
+
+    /// A HID device created through `/dev/uhid`.
+    pub(crate) struct VirtualHidDevice {
+        file: File,
+    }
+
+    impl VirtualHidDevice {
+        /// `None` when uhid isn't available, e.g. in containers or without permissions.
+        pub(crate) fn new(name: &str, report_descriptor: &[u8]) -> Option<Self> {
+            let mut file = OpenOptions::new().read(true).write(true).open("/dev/uhid").ok()?;
+
+            // struct uhid_create2_req
+            let mut event = vec![0_u8; UHID_EVENT_SIZE];
+            event[0..4].copy_from_slice(&UHID_CREATE2.to_ne_bytes());
+            let request = &mut event[4..];
+            request[..name.len()].copy_from_slice(name.as_bytes());
+            request[256..258].copy_from_slice(&(report_descriptor.len() as u16).to_ne_bytes());
+            request[258..260].copy_from_slice(&0x06_u16.to_ne_bytes()); // BUS_VIRTUAL
+            request[260..264].copy_from_slice(&0x1234_u32.to_ne_bytes());
+            request[264..268].copy_from_slice(&0x5678_u32.to_ne_bytes());
+            request[276..276 + report_descriptor.len()].copy_from_slice(report_descriptor);
+            file.write_all(&event).ok()?;
+
+            Some(VirtualHidDevice { file })
+        }
+
+        pub(crate) fn send_input(&mut self, report: &[u8]) {
+            // struct uhid_input2_req
+            let mut event = vec![0_u8; UHID_EVENT_SIZE];
+            event[0..4].copy_from_slice(&UHID_INPUT2.to_ne_bytes());
+            event[4..6].copy_from_slice(&(report.len() as u16).to_ne_bytes());
+            event[6..6 + report.len()].copy_from_slice(report);
+            self.file.write_all(&event).unwrap();
+        }
+    }
+
+    impl Drop for VirtualHidDevice {
+        fn drop(&mut self) {
+            let mut event = vec![0_u8; UHID_EVENT_SIZE];
+            event[0..4].copy_from_slice(&UHID_DESTROY.to_ne_bytes());
+            let _ = self.file.write_all(&event);
+        }
+    }
+
+    /// A vendor-defined collection with a single 4 byte input report
+    const VENDOR_DESCRIPTOR: [u8; 21] = [
+        0x06, 0x00, 0xFF, // Usage Page (Vendor Defined 0xFF00)
+        0x09, 0x01,       // Usage (0x01)
+        0xA1, 0x01,       // Collection (Application)
+        0x09, 0x02,       //   Usage (0x02)
+        0x15, 0x00,       //   Logical Minimum (0)
+        0x26, 0xFF, 0x00, //   Logical Maximum (255)
+        0x75, 0x08,       //   Report Size (8)
+        0x95, 0x04,       //   Report Count (4)
+        0x81, 0x02,       //   Input (Data, Variable, Absolute)
+        0xC0,             // End Collection
+    ];
+
+    #[test]
+    #[ignore = "needs write access to /dev/uhid"]
+    fn uhid_reports() {
+        let name = "pembejeo hidraw test device";
+        let mut device = VirtualHidDevice::new(name, &VENDOR_DESCRIPTOR).expect("uhid is unavailable");
+
+        let pembejeo = Pembejeo::builder()
+            .backend(Box::new(HidrawBackend::new().unwrap()))
+            .filter(DeviceFilter { hid_reports: true, ..DeviceFilter::name(name) })
+            .build()
+            .unwrap();
+
+        // Wait for the backend to pick the device up
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let id = loop {
+            let devices = pembejeo.devices.lock().unwrap();
+            if let Some(found) = devices.values().find(|found| found.product == name) {
+                assert_eq!(found.vendor_id, 0x1234);
+                assert_eq!(found.product_id, 0x5678);
+                assert_eq!(found.report_descriptor, VENDOR_DESCRIPTOR);
+                break found.id.clone();
+            }
+            drop(devices);
+            assert!(Instant::now() < deadline, "the virtual device was never discovered");
+            thread::sleep(Duration::from_millis(10));
+        };
+
+        device.send_input(&[0xDE, 0xAD, 0xBE, 0xEF]);
+
+        let mut event = Event::default();
+        loop {
+            assert!(Instant::now() < deadline, "the report never arrived");
+            if !pembejeo.poll(&mut event) {
+                thread::sleep(Duration::from_millis(1));
+                continue;
+            }
+            if let Event::HidReport(hid_report_event) = &event {
+                assert_eq!(Some(hid_report_event.device), pembejeo.device_handle(&id));
+                assert_eq!(*hid_report_event.report, [0xDE, 0xAD, 0xBE, 0xEF]);
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! Bindings for `linux/input.h`, `linux/input-event-codes.h`, `linux/uinput.h`
+//! and `linux/hidraw.h` that the libc crate doesn't provide.
+#![allow(dead_code)]
+
+use libc::{c_int, ff_effect, input_absinfo, input_id, uinput_abs_setup, uinput_setup};
+
+use crate::linux::ioctl::{io, ioc, ior, iow, IOC_READ, IOC_WRITE};
+
+// Event types
+pub const EV_SYN: u16 = 0x00;
+pub const EV_KEY: u16 = 0x01;
+pub const EV_REL: u16 = 0x02;
+pub const EV_ABS: u16 = 0x03;
+pub const EV_LED: u16 = 0x11;
+pub const EV_FF: u16 = 0x15;
+pub const EV_MAX: u16 = 0x1f;
+
+// Synchronization events
+pub const SYN_REPORT: u16 = 0;
+pub const SYN_DROPPED: u16 = 3;
+
+// Relative axes
+pub const REL_X: u16 = 0x00;
+pub const REL_Y: u16 = 0x01;
+pub const REL_HWHEEL: u16 = 0x06;
+pub const REL_WHEEL: u16 = 0x08;
+pub const REL_WHEEL_HI_RES: u16 = 0x0b;
+pub const REL_HWHEEL_HI_RES: u16 = 0x0c;
+pub const REL_MAX: u16 = 0x0f;
+
+// Absolute axes
+pub const ABS_X: u16 = 0x00;
+pub const ABS_Y: u16 = 0x01;
+pub const ABS_Z: u16 = 0x02;
+pub const ABS_RX: u16 = 0x03;
+pub const ABS_RY: u16 = 0x04;
+pub const ABS_RZ: u16 = 0x05;
+pub const ABS_GAS: u16 = 0x09;
+pub const ABS_BRAKE: u16 = 0x0a;
+pub const ABS_HAT0X: u16 = 0x10;
+pub const ABS_HAT0Y: u16 = 0x11;
+pub const ABS_HAT3Y: u16 = 0x17;
+pub const ABS_MAX: u16 = 0x3f;
+
+// Keys and buttons
+pub const KEY_A: u16 = 30;
+pub const KEY_Z: u16 = 44;
+pub const KEY_SPACE: u16 = 57;
+pub const BTN_LEFT: u16 = 0x110;
+pub const BTN_RIGHT: u16 = 0x111;
+pub const BTN_MIDDLE: u16 = 0x112;
+pub const BTN_SIDE: u16 = 0x113;
+pub const BTN_EXTRA: u16 = 0x114;
+pub const BTN_FORWARD: u16 = 0x115;
+pub const BTN_BACK: u16 = 0x116;
+pub const BTN_TASK: u16 = 0x117;
+pub const BTN_JOYSTICK: u16 = 0x120;
+pub const BTN_DEAD: u16 = 0x12f;
+pub const BTN_GAMEPAD: u16 = 0x130;
+pub const BTN_SOUTH: u16 = 0x130;
+pub const BTN_EAST: u16 = 0x131;
+pub const BTN_NORTH: u16 = 0x133;
+pub const BTN_WEST: u16 = 0x134;
+pub const BTN_START: u16 = 0x13b;
+pub const BTN_THUMBR: u16 = 0x13e;
+pub const BTN_DPAD_UP: u16 = 0x220;
+pub const BTN_DPAD_DOWN: u16 = 0x221;
+pub const BTN_DPAD_LEFT: u16 = 0x222;
+pub const BTN_DPAD_RIGHT: u16 = 0x223;
+pub const KEY_MAX: u16 = 0x2ff;
+
+// LEDs
+pub const LED_NUML: u16 = 0x00;
+pub const LED_KANA: u16 = 0x04;
+pub const LED_MAX: u16 = 0x0f;
+
+// Force feedback effects
+pub const FF_RUMBLE: u16 = 0x50;
+pub const FF_MAX: u16 = 0x7f;
+
+pub const EVIOCGID: u32 = ior::<input_id>(b'E', 0x02);
+
+pub const fn eviocgname(len: usize) -> u32 {
+    ioc(IOC_READ, b'E', 0x06, len)
+}
+
+pub const fn eviocgphys(len: usize) -> u32 {
+    ioc(IOC_READ, b'E', 0x07, len)
+}
+
+pub const fn eviocguniq(len: usize) -> u32 {
+    ioc(IOC_READ, b'E', 0x08, len)
+}
+
+pub const fn eviocgbit(ev: u16, len: usize) -> u32 {
+    ioc(IOC_READ, b'E', 0x20 + ev as u8, len)
+}
+
+pub const fn eviocgabs(abs: u16) -> u32 {
+    ior::<input_absinfo>(b'E', 0x40 + abs as u8)
+}
+
+pub const fn eviocgled(len: usize) -> u32 {
+    ioc(IOC_READ, b'E', 0x19, len)
+}
+
+pub const EVIOCSCLOCKID: u32 = iow::<c_int>(b'E', 0xa0);
+pub const EVIOCSFF: u32 = iow::<ff_effect>(b'E', 0x80);
+pub const EVIOCRMFF: u32 = iow::<c_int>(b'E', 0x81);
+
+pub const UI_DEV_CREATE: u32 = io(b'U', 1);
+pub const UI_DEV_DESTROY: u32 = io(b'U', 2);
+pub const UI_DEV_SETUP: u32 = iow::<uinput_setup>(b'U', 3);
+pub const UI_SET_EVBIT: u32 = iow::<c_int>(b'U', 100);
+pub const UI_SET_KEYBIT: u32 = iow::<c_int>(b'U', 101);
+pub const UI_SET_RELBIT: u32 = iow::<c_int>(b'U', 102);
+pub const UI_SET_ABSBIT: u32 = iow::<c_int>(b'U', 103);
+pub const UI_ABS_SETUP: u32 = iow::<uinput_abs_setup>(b'U', 4);
+
+pub const HID_MAX_DESCRIPTOR_SIZE: usize = 4096;
+
+#[repr(C)]
+pub struct hidraw_report_descriptor {
+    pub size: u32,
+    pub value: [u8; HID_MAX_DESCRIPTOR_SIZE],
+}
+
+#[repr(C)]
+pub struct hidraw_devinfo {
+    pub bustype: u32,
+    pub vendor: i16,
+    pub product: i16,
+}
+
+pub const HIDIOCGRDESCSIZE: u32 = ior::<c_int>(b'H', 0x01);
+pub const HIDIOCGRDESC: u32 = ior::<hidraw_report_descriptor>(b'H', 0x02);
+pub const HIDIOCGRAWINFO: u32 = ior::<hidraw_devinfo>(b'H', 0x03);
+
+pub const fn hidiocgrawname(len: usize) -> u32 {
+    ioc(IOC_READ, b'H', 0x04, len)
+}
+
+pub const fn hidiocgrawphys(len: usize) -> u32 {
+    ioc(IOC_READ, b'H', 0x05, len)
+}
+
+pub const fn hidiocgrawuniq(len: usize) -> u32 {
+    ioc(IOC_READ, b'H', 0x08, len)
+}
+
+pub const fn hidiocsfeature(len: usize) -> u32 {
+    ioc(IOC_WRITE | IOC_READ, b'H', 0x06, len)
+}
+
+pub const fn hidiocgfeature(len: usize) -> u32 {
+    ioc(IOC_WRITE | IOC_READ, b'H', 0x07, len)
+}
+
+/// Number of bytes in a bitmask covering codes `0..=max`, as passed to `EVIOCGBIT`.
+pub const fn bitmask_len(max: u16) -> usize {
+    max as usize / 8 + 1
+}
+
+/// Test a bit in a bitmask filled by `EVIOCGBIT`.
+pub fn test_bit(bits: &[u8], bit: u16) -> bool {
+    bits.get(bit as usize / 8).is_some_and(|byte| byte & (1 << (bit % 8)) != 0)
+}
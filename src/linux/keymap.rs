@@ -0,0 +1,55 @@
+//! Translation between evdev key codes and Keyboard/Keypad page (0x07) usages.
+
+/// The kernel's `hid_keyboard` table from `drivers/hid/hid-input.c`: the evdev key code for
+/// each keyboard usage, 0 where there is none.
+const HID_KEYBOARD: [u8; 256] = [
+      0,  0,  0,  0, 30, 48, 46, 32, 18, 33, 34, 35, 23, 36, 37, 38,
+     50, 49, 24, 25, 16, 19, 31, 20, 22, 47, 17, 45, 21, 44,  2,  3,
+      4,  5,  6,  7,  8,  9, 10, 11, 28,  1, 14, 15, 57, 12, 13, 26,
+     27, 43, 43, 39, 40, 41, 51, 52, 53, 58, 59, 60, 61, 62, 63, 64,
+     65, 66, 67, 68, 87, 88, 99, 70,119,110,102,104,111,107,109,106,
+    105,108,103, 69, 98, 55, 74, 78, 96, 79, 80, 81, 75, 76, 77, 71,
+     72, 73, 82, 83, 86,127,116,117,183,184,185,186,187,188,189,190,
+    191,192,193,194,134,138,130,132,128,129,131,137,133,135,136,113,
+    115,114,  0,  0,  0,121,  0, 89, 93,124, 92, 94, 95,  0,  0,  0,
+    122,123, 90, 91, 85,  0,  0,  0,  0,  0,  0,  0,111,  0,  0,  0,
+      0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,
+      0,  0,  0,  0,  0,  0,179,180,  0,  0,  0,  0,  0,  0,  0,  0,
+      0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,  0,
+      0,  0,  0,  0,  0,  0,  0,  0,111,  0,  0,  0,  0,  0,  0,  0,
+     29, 42, 56,125, 97, 54,100,126,164,166,165,163,161,115,114,113,
+    150,158,159,128,136,177,178,176,142,152,173,140,  0,  0,  0,  0,
+];
+
+/// The keyboard usage the kernel would have translated to `code`.
+///
+/// Where several usages share a key code the lowest wins, so Delete maps back to 0x4C rather
+/// than Clear. Usages from 0xE8 up are reserved and never returned.
+pub(crate) fn hid_usage(code: u16) -> Option<u16> {
+    if code == 0 || code > 0xFF {
+        return None;
+    }
+    HID_KEYBOARD[..0xE8].iter()
+        .position(|&key| key as u16 == code)
+        .map(|usage| usage as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::hid_usage;
+    use crate::linux::input::{BTN_LEFT, KEY_A, KEY_SPACE, KEY_Z};
+
+    #[test]
+    fn evdev_to_usage() {
+        assert_eq!(hid_usage(KEY_A), Some(0x04));
+        assert_eq!(hid_usage(KEY_Z), Some(0x1D));
+        assert_eq!(hid_usage(KEY_SPACE), Some(0x2C));
+        // KEY_BACKSLASH is shared with the non-US hash key
+        assert_eq!(hid_usage(43), Some(0x31));
+        // KEY_LEFTMETA and KEY_RIGHTALT
+        assert_eq!(hid_usage(125), Some(0xE3));
+        assert_eq!(hid_usage(100), Some(0xE6));
+        assert_eq!(hid_usage(BTN_LEFT), None);
+        assert_eq!(hid_usage(0), None);
+    }
+}
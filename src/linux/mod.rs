@@ -0,0 +1,70 @@
+use std::{ffi::CStr, io, os::fd::{AsRawFd, FromRawFd, OwnedFd}};
+
+use libc::c_void;
+
+use crate::{device::stable_id, DeviceId};
+
+mod ioctl;
+pub(crate) mod input;
+mod keymap;
+mod evdev;
+mod hidraw;
+
+pub use evdev::*;
+pub use hidraw::*;
+
+/// A non-blocking pipe, used to wake an input thread up on shutdown.
+fn create_pipe() -> io::Result<(OwnedFd, OwnedFd)> {
+    let mut fds = [0 as libc::c_int; 2];
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC | libc::O_NONBLOCK) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    unsafe { Ok((OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1]))) }
+}
+
+fn wake(fd: &OwnedFd) {
+    let byte = 1_u8;
+    unsafe { libc::write(fd.as_raw_fd(), &byte as *const u8 as *const c_void, 1) };
+}
+
+/// Read and discard everything pending on a non-blocking descriptor.
+fn drain(fd: &OwnedFd) {
+    let mut buffer = [0_u8; 4096];
+    while unsafe { libc::read(fd.as_raw_fd(), buffer.as_mut_ptr() as *mut c_void, buffer.len()) } > 0 {}
+}
+
+/// The id of a Linux device, see `DeviceId`. Nodes of one interface share its physical path, like
+/// `usb-0000:00:14.0-2/input0`, so `name` tells them apart. With a serial number, only the interface
+/// at the end of the path is kept.
+fn linux_id(backend: &str, vendor_id: u16, product_id: u16, serial_number: &str, location: &str, name: &str) -> DeviceId {
+    let interface = match location.rsplit_once('/') {
+        Some((_, interface)) if !serial_number.is_empty() => format!("{}/{}", interface, name),
+        _ => name.to_string(),
+    };
+    stable_id(backend, vendor_id, product_id, serial_number, location, &interface)
+}
+
+/// Read a string property of a device through an ioctl like `EVIOCGPHYS`, which takes the buffer
+/// length. Empty when the device doesn't have one.
+fn read_string(fd: libc::c_int, request: fn(usize) -> u32) -> String {
+    let mut buffer = [0_u8; 256];
+    if unsafe { libc::ioctl(fd, request(buffer.len()) as _, buffer.as_mut_ptr()) } < 0 {
+        return String::new();
+    }
+    CStr::from_bytes_until_nul(&buffer)
+        .map(|string| string.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// An inotify descriptor reporting nodes created in `directory` or having their permissions changed.
+/// A missing directory just means there are no devices yet, so only the inotify descriptor itself can fail.
+fn watch_directory(directory: &CStr) -> io::Result<OwnedFd> {
+    unsafe {
+        let fd = libc::inotify_init1(libc::IN_CLOEXEC | libc::IN_NONBLOCK);
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let _ = libc::inotify_add_watch(fd, directory.as_ptr(), libc::IN_CREATE | libc::IN_ATTRIB);
+        Ok(OwnedFd::from_raw_fd(fd))
+    }
+}
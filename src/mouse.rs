@@ -1,11 +1,37 @@
-#[derive(Clone, Debug)]
-pub struct Mouse {
-    pub id: String,
-    pub vender_id: u16,
-    pub product_id: u16,
-
-    pub product: String,
-    pub manufacturer: String
+/// A mouse button, by its Button page (0x09) usage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Back,
+    Forward,
+    /// Any further button, by its button number starting at 6
+    Extra(u16),
 }
 
+impl MouseButton {
+    /// The button for a Button page usage, 1 being the primary button.
+    pub fn from_usage(usage: u16) -> MouseButton {
+        match usage {
+            1 => MouseButton::Left,
+            2 => MouseButton::Right,
+            3 => MouseButton::Middle,
+            4 => MouseButton::Back,
+            5 => MouseButton::Forward,
+            number => MouseButton::Extra(number),
+        }
+    }
 
+    /// The button's Button page usage.
+    pub fn usage(&self) -> u16 {
+        match self {
+            MouseButton::Left => 1,
+            MouseButton::Right => 2,
+            MouseButton::Middle => 3,
+            MouseButton::Back => 4,
+            MouseButton::Forward => 5,
+            MouseButton::Extra(number) => *number,
+        }
+    }
+}
@@ -1,511 +1,453 @@
 
-use core::slice;
-use std::{collections::HashMap, sync::{Arc, Mutex}, thread::{self, JoinHandle}};
+use std::{collections::HashMap, path::Path, sync::Mutex, time::{Duration, Instant}};
 
-use core_foundation::{base::TCFType, runloop::{kCFRunLoopDefaultMode, CFRunLoopGetCurrent, CFRunLoopRef, CFRunLoopRun, CFRunLoopStop}};
-use libc::{c_void, c_int};
-
-use crate::{apple::iohid::{IOHIDManagerOpen, IOHIDManagerRegisterDeviceMatchingCallback, IOHIDManagerRegisterDeviceRemovalCallback, IOHIDManagerScheduleWithRunLoop, IOHIDManagerSetDeviceMatchingMultiple}, Event, Keyboard, Mouse};
+use crate::{backend::{default_backend, Backend, Context}, event_queue::{EventQueue, DEFAULT_EVENT_CAPACITY}, device::DeviceHandles, gamepad_mapping::GamepadMapper, rumble::{rumble_reports, RumbleTimer}, DeviceEvent, DeviceFilter, DeviceRemovedEvent, DeviceHandle, DeviceInfo, Event, GamepadMapping, GamepadMappings, GestureConfig, GestureRecognizer, JoystickInput, LedEvent, LedState, OverflowPolicy, Rumble, Timestamp, Usage};
 
 pub struct Pembejeo {
-    pub mice: Mutex<HashMap<String, Mouse>>,
-    pub keyboards: Mutex<HashMap<String, Keyboard>>,
-
-    pub events: Mutex<Vec<Event>>,
-    skip_checking: Mutex<bool>,
-    
-    input_thread: Option<JoinHandle<()>>,
-
-    #[cfg(target_os = "macos")]
-    iohid_manager: *mut c_void,
-    #[cfg(target_os = "macos")]
-    input_run_loop: Arc<Mutex<Option<usize>>>,
+    /// Every connected device the filters match, by id
+    pub devices: Mutex<HashMap<String, DeviceInfo>>,
+    /// The handle of every device seen this session, connected or not
+    handles: Mutex<DeviceHandles>,
+
+    events: EventQueue,
+
+    /// Turns touches into gestures when set
+    gestures: Mutex<Option<GestureRecognizer>>,
+
+    gamepad_mappings: Mutex<GamepadMappings>,
+    /// The mapping state of every gamepad that reported joystick inputs
+    gamepad_mappers: Mutex<HashMap<DeviceHandle, GamepadMapper>>,
+
+    /// Started by the first rumble
+    rumble_timer: Mutex<Option<RumbleTimer>>,
+
+    /// Every device is reported while this is empty
+    filters: Mutex<Vec<DeviceFilter>>,
+
+    backend: Mutex<Box<dyn Backend>>,
 }
 
-impl Pembejeo {
-    pub fn new() -> Result<Box<Self>, crate::Error> {
-        #[cfg(target_os = "macos")]
-        let res = {
-            use core_foundation::string::CFString;
-            let iohid_manager = create_iohid_manager()?;
-            let matching_array = create_matching_array();
-
-            // Create a Pembejeo object
-            let mut res = Box::new(Pembejeo {
-                mice: Mutex::new(HashMap::new()),
-                keyboards: Mutex::new(HashMap::new()),
-
-                events: Mutex::new(Vec::new()),
-                skip_checking: Mutex::new(false),
-
-                input_thread: None,
-
-                iohid_manager,
-                input_run_loop: Arc::new(Mutex::new(None)),
-            });
-
-            // Setup the matching and callbacks
-            unsafe {
-                IOHIDManagerSetDeviceMatchingMultiple(iohid_manager, matching_array.as_CFTypeRef() as *const c_void);
-
-                IOHIDManagerRegisterDeviceMatchingCallback(iohid_manager, handle_device_matching_callback, res.as_mut() as *mut _ as *mut c_void);
-                IOHIDManagerRegisterDeviceRemovalCallback(iohid_manager, handle_device_removal_callback, res.as_mut() as *mut _ as *mut c_void);
-
-                let iohid_manager_usize_ref = res.iohid_manager as usize;
-                let input_run_loop_clone = res.input_run_loop.clone();
-                res.input_thread = Some(thread::spawn(move || {
-                    let run_loop = CFRunLoopGetCurrent();
-                    let iohid_manager = iohid_manager_usize_ref as *mut c_void;
-                    IOHIDManagerScheduleWithRunLoop(iohid_manager, run_loop, CFString::wrap_under_get_rule(kCFRunLoopDefaultMode));
-                    IOHIDManagerOpen(iohid_manager, 0x00);
-
-                    // Set the run loop
-                    {
-                        let mut input_run_loop = input_run_loop_clone.lock().unwrap();
-                        *input_run_loop = Some(run_loop as usize);
-                    }
-
-                    CFRunLoopRun();
-                }));
-            }
+/// Configures a `Pembejeo` before its backend starts looking for devices.
+#[derive(Default)]
+pub struct PembejeoBuilder {
+    filters: Vec<DeviceFilter>,
+    backend: Option<Box<dyn Backend>>,
+    event_capacity: Option<usize>,
+    overflow_policy: OverflowPolicy,
+}
+
+impl PembejeoBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only report the devices one of the filters matches.
+    pub fn filter(mut self, filter: DeviceFilter) -> Self {
+        self.filters.push(filter);
+        self
+    }
 
+    pub fn filters(mut self, filters: impl IntoIterator<Item = DeviceFilter>) -> Self {
+        self.filters.extend(filters);
+        self
+    }
+
+    /// Drive the Pembejeo with `backend` instead of the platform default.
+    pub fn backend(mut self, backend: Box<dyn Backend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Keep at most `capacity` events for the application, 4096 unless set.
+    pub fn event_capacity(mut self, capacity: usize) -> Self {
+        self.event_capacity = Some(capacity);
+        self
+    }
+
+    /// What to do with events while the queue is full, `OverflowPolicy::CoalesceMotion` unless set.
+    pub fn overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+        self.overflow_policy = policy;
+        self
+    }
 
-            res
+    pub fn build(self) -> Result<Box<Pembejeo>, crate::Error> {
+        let backend = match self.backend {
+            Some(backend) => backend,
+            None => default_backend()?,
         };
 
+        // Create a Pembejeo object
+        let res = Box::new(Pembejeo {
+            devices: Mutex::new(HashMap::new()),
+            handles: Mutex::new(DeviceHandles::default()),
+
+            events: EventQueue::new(self.event_capacity.unwrap_or(DEFAULT_EVENT_CAPACITY), self.overflow_policy),
+
+            gestures: Mutex::new(None),
+
+            gamepad_mappings: Mutex::new(GamepadMappings::default()),
+            gamepad_mappers: Mutex::new(HashMap::new()),
+
+            rumble_timer: Mutex::new(None),
+
+            filters: Mutex::new(self.filters),
+
+            backend: Mutex::new(backend),
+        });
+
+        // The box keeps the address stable for the backend's callbacks and threads
+        let context = Context::new(res.as_ref());
+        res.backend.lock().unwrap().start(context)?;
+
         Ok(res)
     }
+}
 
-    pub fn poll(&self, event: &mut Event) -> bool {
-        let mut events = self.events.lock().unwrap();
-        if events.len() == 0 {
-            *event = Event::Empty;
-            return false;
+impl Pembejeo {
+    pub fn new() -> Result<Box<Self>, crate::Error> {
+        PembejeoBuilder::new().build()
+    }
+
+    /// Create a Pembejeo driven by `backend` instead of the platform default.
+    pub fn with_backend(backend: Box<dyn Backend>) -> Result<Box<Self>, crate::Error> {
+        PembejeoBuilder::new().backend(backend).build()
+    }
+
+    pub fn builder() -> PembejeoBuilder {
+        PembejeoBuilder::new()
+    }
+
+    /// The connected devices that move the pointer.
+    pub fn mice(&self) -> Vec<DeviceInfo> {
+        self.devices.lock().unwrap().values().filter(|info| info.is_mouse()).cloned().collect()
+    }
+
+    /// The connected devices with keys.
+    pub fn keyboards(&self) -> Vec<DeviceInfo> {
+        self.devices.lock().unwrap().values().filter(|info| info.is_keyboard()).cloned().collect()
+    }
+
+    /// A connected device by its handle.
+    pub fn device(&self, handle: DeviceHandle) -> Option<DeviceInfo> {
+        let handles = self.handles.lock().unwrap();
+        self.devices.lock().unwrap().get(handles.id(handle)?).cloned()
+    }
+
+    /// The handle of a device seen this session.
+    pub fn device_handle(&self, device_id: &str) -> Option<DeviceHandle> {
+        self.handles.lock().unwrap().get(device_id)
+    }
+
+    /// The handle of a device, giving it one if it is new this session. Backends that need the
+    /// handle in place before announcing the device take it here.
+    pub fn assign_handle(&self, device_id: &str) -> DeviceHandle {
+        self.handles.lock().unwrap().assign(device_id)
+    }
+
+    /// Record a device a backend matched, give it its handle and announce it with
+    /// `Event::DeviceAdded`. Returns the handle for the backend's events.
+    pub fn add_device(&self, mut info: DeviceInfo) -> DeviceHandle {
+        info.handle = self.assign_handle(&info.id);
+        let handle = info.handle;
+
+        self.devices.lock().unwrap().insert(info.id.clone(), info.clone());
+        self.push_event(Event::DeviceAdded(DeviceEvent { info, timestamp: Timestamp::now() }));
+        handle
+    }
+
+    /// Forget a device a backend lost, along with its gamepad mapping state, and announce it with
+    /// `Event::DeviceRemoved`.
+    pub fn remove_device(&self, device_id: &str) {
+        let info = self.devices.lock().unwrap().remove(device_id);
+        if let Some(handle) = self.device_handle(device_id) {
+            self.gamepad_mappers.lock().unwrap().remove(&handle);
         }
 
-        *event = events.get(0).unwrap().clone();
+        // Devices that were never added aren't announced either
+        if let Some(info) = info {
+            self.push_event(Event::DeviceRemoved(DeviceRemovedEvent { device: info.handle, id: info.id, timestamp: Timestamp::now() }));
+        }
+    }
 
-        // Remove the event at the top of the list
-        events.remove(0);
-        true
+    /// The filters devices are matched against.
+    pub fn filters(&self) -> Vec<DeviceFilter> {
+        self.filters.lock().unwrap().clone()
     }
 
-    pub fn wait(&self, event: &mut Event) -> bool {
-        // Make a new scope for the lock or multiple threads will hang
+    /// Whether a device should be reported: with no filters every device is, otherwise the ones a
+    /// filter matches.
+    pub fn matches_device(&self, info: &DeviceInfo) -> bool {
+        let filters = self.filters.lock().unwrap();
+        filters.is_empty() || filters.iter().any(|filter| filter.matches(info))
+    }
+
+    /// Whether a device's raw input reports should be reported, which a filter matching it has
+    /// to ask for with `DeviceFilter::hid_reports`.
+    pub fn wants_hid_reports(&self, info: &DeviceInfo) -> bool {
+        self.filters.lock().unwrap().iter().any(|filter| filter.hid_reports && filter.matches(info))
+    }
+
+    /// Start reporting the devices `filter` matches as well. Devices are matched again in the
+    /// background, announced through `Event::DeviceAdded` and `Event::DeviceRemoved`.
+    pub fn add_filter(&self, filter: DeviceFilter) -> Result<(), crate::Error> {
+        self.filters.lock().unwrap().push(filter);
+        self.apply_filters()
+    }
+
+    /// Stop reporting the devices only `filter` matched, returning false when it wasn't one of the
+    /// filters. Removing the last filter reports every device again.
+    pub fn remove_filter(&self, filter: &DeviceFilter) -> Result<bool, crate::Error> {
         {
-            let mut skip = self.skip_checking.lock().unwrap();
-            if *skip == true {
-                *skip = false;
-                return false;
-            }
+            let mut filters = self.filters.lock().unwrap();
+            let Some(index) = filters.iter().position(|found| found == filter) else {
+                return Ok(false);
+            };
+            filters.remove(index);
         }
 
-        loop {
-            let events = self.events.lock().unwrap();
-            if events.len() != 0 {
-                break;
-            }
-        }
+        self.apply_filters()?;
+        Ok(true)
+    }
 
-        let mut events = self.events.lock().unwrap();
-        *event = events.get(0).unwrap().clone();
+    fn apply_filters(&self) -> Result<(), crate::Error> {
+        // Backends can wait for their own threads as well
+        self.lock_backend(|backend| backend.apply_filters())
+    }
 
-        // Remove the event at the top of the list
-        events.remove(0);
+    /// Send a feature report to a HID device.
+    /// The first byte of `report` is the report ID, or 0 for devices that don't use report IDs.
+    pub fn send_feature_report(&self, device_id: &str, report: &[u8]) -> Result<(), crate::Error> {
+        self.lock_backend(|backend| backend.set_feature_report(device_id, report))
+    }
 
-        let mut skip = self.skip_checking.lock().unwrap();
-        if events.len() == 0 { *skip = true }
+    /// Send an output report to a HID device, report ID byte first like `send_feature_report`.
+    pub fn send_output_report(&self, device_id: &str, report: &[u8]) -> Result<(), crate::Error> {
+        self.lock_backend(|backend| backend.set_output_report(device_id, report))
+    }
 
-        true
+    /// Read a feature report from a HID device into `report`, returning its length.
+    /// The first byte of `report` selects the report ID, or 0 for devices that don't use report IDs.
+    pub fn get_feature_report(&self, device_id: &str, report: &mut [u8]) -> Result<usize, crate::Error> {
+        self.lock_backend(|backend| backend.get_feature_report(device_id, report))
     }
 
-    pub fn push_event(&self, event: &Event) {
-        let mut events = self.events.lock().unwrap();
-        let mut skip  = self.skip_checking.lock().unwrap();
-        
-        events.push(event.clone());
-        *skip = false;
+    /// Turn on the Resolution Multipliers of a mouse that advertises them, so `Event::Scroll` reports
+    /// fractions of a detent in `vertical_v120` and `horizontal_v120`.
+    ///
+    /// Only devices whose reports this crate decodes itself can be switched. The Linux kernel already
+    /// enables high resolution scrolling on the mice it drives, and evdev scroll events carry it.
+    pub fn set_high_resolution_scroll(&self, device_id: &str, enabled: bool) -> Result<(), crate::Error> {
+        self.lock_backend(|backend| backend.set_high_resolution_scroll(device_id, enabled))
     }
-}
 
-impl Drop for Pembejeo {
-    fn drop(&mut self) {
-        // Loop until the run loop has a value
-        // We need to end the run loop before joining the thread to prevent a hang.
-        loop {
-            let input_run_loop = self.input_run_loop.lock().unwrap();
-            if let Some(_) = *input_run_loop {
-                break;
-            }
-        }
+    /// Backends hold their device state while they push, so a producer blocked on a full queue
+    /// has to give way before the application can reach the backend.
+    fn lock_backend<T>(&self, f: impl FnOnce(&mut dyn Backend) -> T) -> T {
+        self.events.without_blocking(|| f(self.backend.lock().unwrap().as_mut()))
+    }
+
+    /// Switch a Precision Touchpad from mouse emulation to reporting its contacts as `Event::Touch`.
+    /// Touchpads start out as mice until the host sets their Input Mode, which Windows always does.
+    pub fn enable_touch_reports(&self, device_id: &str) -> Result<(), crate::Error> {
+        let descriptor = match self.devices.lock().unwrap().get(device_id) {
+            Some(info) => info.parse_report_descriptor()?,
+            None => return Err(crate::Error::DeviceNotFound(device_id.to_string())),
+        };
 
-        // Stop the run loop
-        unsafe {
-            let input_run_loop_guard = self.input_run_loop.lock().unwrap();
-            let input_run_loop_usize = (*input_run_loop_guard).unwrap();
-            let input_run_loop = input_run_loop_usize as CFRunLoopRef;
-            CFRunLoopStop(input_run_loop);
+        // Digitizer Input Mode 3 selects touchpad reports, 0 is the mouse
+        let reports = descriptor.feature_reports_setting(Usage::new(0x0D, 0x52), |_| 3, |report| self.get_feature_report(device_id, report).is_ok());
+        if reports.is_empty() {
+            return Err(crate::Error::NotSupported(format!("{} has no input mode", device_id)));
         }
-        
 
-        if let Some(thread) = self.input_thread.take() {
-            thread.join().unwrap();
+        for report in reports {
+            self.send_feature_report(device_id, &report)?;
         }
+        Ok(())
+    }
 
-        //unsafe { IOHIDManagerClose(self.iohid_manager, 0x00) };
+    /// Run a gamepad's rumble motors for `rumble.duration`, replacing whatever rumble it was
+    /// playing. A rumble that is off stops them.
+    ///
+    /// Linux drives the motors through evdev force feedback. Devices reached as plain HID get
+    /// output reports, in the format of DualShock 4 and DualSense controllers over USB or from a
+    /// Physical Interface Device descriptor.
+    pub fn rumble(&self, device_id: &str, rumble: Rumble) -> Result<(), crate::Error> {
+        let rumble = if rumble.is_off() { Rumble::default() } else { rumble };
+
+        // Scheduled first, so a rumble ending right now can't stop this one
+        let deadline = (!rumble.is_off()).then(|| Instant::now() + rumble.duration);
+        let mut timer = self.rumble_timer.lock().unwrap();
+        let context = Context::new(self);
+        timer.get_or_insert_with(|| RumbleTimer::new(move |device_id| {
+            let _ = context.play_rumble(device_id, &Rumble::default());
+        })).schedule(device_id, deadline);
+        drop(timer);
+
+        self.play_rumble(device_id, &rumble)
     }
-}
 
-#[cfg(target_os = "macos")]
-fn create_iohid_manager() -> Result<*mut c_void, crate::Error> {
-    use core_foundation::base::kCFAllocatorDefault;
+    fn play_rumble(&self, device_id: &str, rumble: &Rumble) -> Result<(), crate::Error> {
+        self.lock_backend(|backend| {
+            match backend.rumble(device_id, rumble) {
+                Err(crate::Error::DeviceNotFound(_)) => {},
+                res => return res,
+            }
 
-    use crate::apple::iohid::IOHIDManagerCreate;
-    let manager = unsafe { IOHIDManagerCreate(kCFAllocatorDefault, 0x00) };
-    if manager == std::ptr::null_mut() {
-        return Err(crate::Error::FailedCreatingPembejeo("IOHIDManagerCreate returned nullptr!".to_string()));
+            let reports = match self.devices.lock().unwrap().get(device_id) {
+                Some(info) => rumble_reports(info, &info.parse_report_descriptor()?, rumble)?,
+                None => return Err(crate::Error::DeviceNotFound(device_id.to_string())),
+            };
+            for report in reports {
+                backend.set_output_report(device_id, &report)?;
+            }
+            Ok(())
+        })
     }
-    Ok(manager)
-}
 
-#[cfg(target_os = "macos")]
-fn create_matching_dictionary(page: u16, usage: Option<u16>) ->
-    core_foundation::dictionary::CFDictionary<
-    core_foundation::string::CFString,
-    core_foundation::number::CFNumber>
-{
-    use std::str::FromStr;
+    /// Light a keyboard's lock LEDs. Linux sets them through evdev, other keyboards get the LED
+    /// output report their descriptor declares.
+    pub fn set_leds(&self, device_id: &str, leds: LedState) -> Result<(), crate::Error> {
+        self.lock_backend(|backend| {
+            match backend.set_leds(device_id, leds) {
+                Err(crate::Error::DeviceNotFound(_)) => {},
+                res => return res,
+            }
 
-    use core_foundation::{dictionary::{CFDictionary, CFMutableDictionary}, number::CFNumber, string::CFString};
+            let report = match self.devices.lock().unwrap().get(device_id) {
+                Some(info) => info.parse_report_descriptor()?.led_report(&leds),
+                None => return Err(crate::Error::DeviceNotFound(device_id.to_string())),
+            };
+            let Some(report) = report else {
+                return Err(crate::Error::NotSupported(format!("{} has no LEDs", device_id)));
+            };
+            backend.set_output_report(device_id, &report)?;
+
+            // Nothing else reports the LEDs of devices reached as plain HID. The application is the
+            // one that would make room, so this doesn't wait for it either.
+            self.push_event(Event::LedChanged(LedEvent {
+                device: self.device_handle(device_id).unwrap_or_default(),
+                timestamp: Timestamp::now(),
+                leds,
+            }));
+            Ok(())
+        })
+    }
 
-    let mut dict = CFMutableDictionary::<CFString, CFNumber>::new();
+    /// Take the oldest event. Returns false and sets `Event::Empty` when there is none.
+    pub fn poll(&self, event: &mut Event) -> bool {
+        Self::take(self.events.pop(), event)
+    }
 
-    let cf_page = CFNumber::from(page as i32);
-    dict.set(CFString::from_str("DeviceUsagePage").unwrap(), cf_page);
+    /// Take the oldest event, sleeping until there is one. Returns false and sets `Event::Empty`
+    /// when `wake` is called first.
+    pub fn wait(&self, event: &mut Event) -> bool {
+        Self::take(self.events.wait(None), event)
+    }
 
-    if let Some(usage) = usage {
-        let cf_usage = CFNumber::from(usage as i32);
-        dict.set(CFString::from_str("DeviceUsage").unwrap(), cf_usage);
+    /// Like `wait`, but also gives up after `timeout`.
+    pub fn wait_timeout(&self, event: &mut Event, timeout: Duration) -> bool {
+        Self::take(self.events.wait(Instant::now().checked_add(timeout)), event)
     }
 
-    dict.to_immutable()
-}
+    /// Like `wait`, but also gives up at `deadline`, such as when the next frame is due.
+    pub fn wait_until(&self, event: &mut Event, deadline: Instant) -> bool {
+        Self::take(self.events.wait(Some(deadline)), event)
+    }
 
-#[cfg(target_os = "macos")]
-fn create_matching_array() -> 
-    core_foundation::array::CFArray<
-        core_foundation::dictionary::CFDictionary<
-            core_foundation::string::CFString,
-            core_foundation::number::CFNumber,
-        >
-    >
-{
-    use core_foundation::{array::CFArray, dictionary::CFDictionary, number::CFNumber, string::CFString};
-
-    let mouse_dict = create_matching_dictionary(0xFF00, Some(0x0C));
-    //let keyboard_dict = create_matching_dictionary(0x01, Some(0x06));
-    let array: CFArray<CFDictionary<CFString, CFNumber>> = CFArray::from_CFTypes(&[mouse_dict/*, keyboard_dict*/]);
-
-    array
-}
+    /// Make a thread sleeping in `wait` return false, or the next call to wait when none is.
+    pub fn wake(&self) {
+        self.events.wake();
+    }
 
-#[cfg(target_os = "macos")]
-fn handle_device_matching_callback(in_context: *mut c_void, _in_return: c_int, _sender: *mut c_void, device: *mut c_void) {
-    use crate::apple::iohid::{IOHIDDeviceGetProperty, IOHIDDeviceRegisterInputReportCallback, IOHIDDeviceRegisterInputValueCallback, IOHIDDeviceSetReport};
-    use core_foundation::{data::{CFData, CFDataRef}, number::{CFNumber, CFNumberRef}, string::{CFString, CFStringRef}};
-
-    let pembejeo = unsafe { &*(in_context as *mut Pembejeo) };
-    
-    // Send a feature report to enable multitouch
-    unsafe{
-        let mut report_data = [0x02_u8, 0x01_u8, 0x01u8];
-        let res = IOHIDDeviceSetReport(
-            device, 
-            2,
-            0x02,
-            report_data.as_mut_ptr(),
-            report_data.len() as isize
-        );
-        if res != 0x00 {
-            eprintln!("Failed to send a feature report!");
-        } else {
-            println!("Succesfully send feature report!"); 
+    fn take(taken: Option<Event>, event: &mut Event) -> bool {
+        match taken {
+            Some(taken) => {
+                *event = taken;
+                true
+            },
+            None => {
+                *event = Event::Empty;
+                false
+            },
         }
     }
 
-    // Get the device's id
-    let id = format!("0x{:x}", device as usize);
-
-    // Get the device's usage property
-    let usage = unsafe {
-        let usage_ref: CFNumberRef = IOHIDDeviceGetProperty(device, CFString::from("PrimaryUsage")) as CFNumberRef;
-        let usage = CFNumber::wrap_under_get_rule(usage_ref);
-        usage.to_i32().unwrap() as u16
-    };
-
-    // Get the device's product id
-    let vendor_id = unsafe {
-        let vendor_id_ref: CFNumberRef = IOHIDDeviceGetProperty(device, CFString::from("VendorID")) as CFNumberRef;
-        let vendor = CFNumber::wrap_under_get_rule(vendor_id_ref);
-        vendor.to_i32().unwrap() as u16
-    };
-
-    // Get the device's product id
-    let product_id = unsafe {
-        let product_id_ref: CFNumberRef = IOHIDDeviceGetProperty(device, CFString::from("ProductID")) as CFNumberRef;
-        let product = CFNumber::wrap_under_get_rule(product_id_ref);
-        product.to_i32().unwrap() as u16
-    };
-
-    // Get the device's product name
-    let product = unsafe {
-        let product_ref: CFStringRef = IOHIDDeviceGetProperty(device, CFString::new("Product")) as CFStringRef;
-        if product_ref != std::ptr::null() {
-            let product = CFString::wrap_under_get_rule(product_ref);
-            product.to_string()
-        } else {
-            "".to_string()
-        }
-        
-    };
-
-    // Get the device's manufacturer name
-    let manufacturer = unsafe {
-        let manufacturer_ref: CFStringRef = IOHIDDeviceGetProperty(device, CFString::new("Manufacturer")) as CFStringRef;
-        if manufacturer_ref != std::ptr::null() {
-            let manufacturer = CFString::wrap_under_get_rule(manufacturer_ref);
-            manufacturer.to_string()
-        } else {
-            "".to_string()
-        }
-    };
+    /// Recognize gestures from touch surfaces with `config`, pushing `Event::Gesture` after the
+    /// touch frames that complete them. `None` turns recognition off.
+    pub fn set_gestures(&self, config: Option<GestureConfig>) {
+        // Recognized gestures are pushed with the recognizer held
+        self.events.without_blocking(|| *self.gestures.lock().unwrap() = config.map(GestureRecognizer::new));
+    }
 
-    // Get the device's report descriptor
-    unsafe {
-        let desc_ref: CFDataRef = IOHIDDeviceGetProperty(device, CFString::new("ReportDescriptor")) as CFDataRef;
-        let desc_data = CFData::wrap_under_get_rule(desc_ref);
-        let desc = slice::from_raw_parts(desc_data.as_ptr(), desc_data.len() as usize);
+    /// Add SDL `gamecontrollerdb.txt` mappings, returning how many there were. Gamepads they cover
+    /// report through them from their next input on, instead of through the built-in layout.
+    pub fn add_gamepad_mappings(&self, text: &str) -> Result<usize, crate::Error> {
+        let count = self.gamepad_mappings.lock().unwrap().add_mappings(text)?;
 
-        println!("Report Descriptor:");
-        for i in 0..desc.len() {
-            print!("0x{:02X} ", desc[i]);
-        }
-        println!();
-    };
-
-    match usage {
-        // Mouse or Trackpad
-        0x02 => {
-            // Add the mouse to the list
-            let mouse = Mouse {
-                id: id.clone(),
-                vender_id: vendor_id.clone(),
-                product_id: product_id.clone(),
-                product: product.clone(),
-                manufacturer: manufacturer.clone()
-            };
+        // Look every device up again. Mapped inputs are pushed with the mappers held.
+        self.events.without_blocking(|| self.gamepad_mappers.lock().unwrap().clear());
+        Ok(count)
+    }
 
-            let mut mice = pembejeo.mice.lock().unwrap();
-            (*mice).insert(id.clone(), mouse);
-
-        },
-        // Keyboards
-        0x06 => {
-            let keyboard = Keyboard {
-                id: id.clone(),
-                vender_id: vendor_id.clone(),
-                product_id: product_id.clone(),
-                product: product.clone(),
-                manufacturer: manufacturer.clone()
-            };
-            let mut keyboards = pembejeo.keyboards.lock().unwrap();
-            (*keyboards).insert(id.clone(), keyboard);
-        },
-        _ => {}
-    }
-
-    // Setup the callbacks
-    unsafe {
-        IOHIDDeviceRegisterInputValueCallback(device, handle_input_value_callback, in_context);
-
-        let report_size = 64_usize;
-        let report_buffer: *mut u8 = libc::malloc(report_size) as *mut u8;
-        IOHIDDeviceRegisterInputReportCallback(device, report_buffer, report_size, handle_hid_report, in_context); 
-    };
-
-    // Debugging and checking information
-    println!("Device Matched:");
-    println!("\tID: {}", id);
-    println!("\tVendor ID: {}", vendor_id);
-    println!("\tProduct ID: {}", product_id);
-    println!("\tProduct: {}", product);
-    println!("\tManufacturer: {}", manufacturer);
-    println!();
-}
+    /// Add the SDL mappings in a `gamecontrollerdb.txt` file.
+    pub fn load_gamepad_mappings(&self, path: impl AsRef<Path>) -> Result<usize, crate::Error> {
+        let count = self.gamepad_mappings.lock().unwrap().load_mappings(path)?;
+        self.events.without_blocking(|| self.gamepad_mappers.lock().unwrap().clear());
+        Ok(count)
+    }
 
-#[cfg(target_os = "macos")]
-fn handle_device_removal_callback(in_context: *mut c_void, _in_return: c_int, _sender: *mut c_void, device: *mut c_void) {
-    use core_foundation::{number::{CFNumber, CFNumberRef}, string::CFString};
-    use crate::apple::iohid::IOHIDDeviceGetProperty;
-
-    let pembejeo = unsafe { &*(in_context as *mut Pembejeo) };
-
-    // Get the device's id
-    let id = format!("0x{:x}", device as usize);
-
-    // Get the device's usage property
-    let usage = unsafe {
-        let usage_ref: CFNumberRef = IOHIDDeviceGetProperty(device, CFString::from("PrimaryUsage")) as CFNumberRef;
-        let usage = CFNumber::wrap_under_get_rule(usage_ref);
-        usage.to_i32().unwrap() as u16
-    };
-
-    match usage {
-        // Mice or trackpads
-        0x02 => {
-            let mut mice = pembejeo.mice.lock().unwrap();
-            let _ = (*mice).remove(&id);
-        },
-        // Keyboards
-        0x06 => {
-            let mut keyboards = pembejeo.keyboards.lock().unwrap();
-            let _ = (*keyboards).remove(&id);
-        },
-        _ => {}
+    /// The mapping a gamepad reports through, if any.
+    pub fn gamepad_mapping(&self, device_id: &str) -> Option<GamepadMapping> {
+        let guid = self.devices.lock().unwrap().get(device_id)?.guid?;
+        self.gamepad_mappings.lock().unwrap().find(&guid).cloned()
     }
-}
 
-#[cfg(target_os = "macos")]
-fn handle_input_value_callback(in_context: *mut c_void, in_return: c_int, sender: *mut c_void, iohid_value: *mut c_void) {
-    use crate::{apple::iohid::{IOHIDDeviceCopyReports, IOHIDDeviceGetReport, IOHIDElementGetUsage, IOHIDElementGetUsagePage, IOHIDValueGetBytePtr, IOHIDValueGetElement, IOHIDValueGetIntegerValue, IOHIDValueGetLength}, mouse, Event, MouseMotionEvent};
-
-    // let mut report_size = 16_isize;
-    // let mut input_report_buffer: [u8; 64] = [0; 64]; 
-
-    // unsafe {
-    //     let res = IOHIDDeviceGetReport(sender, 0, 63, input_report_buffer.as_mut_ptr(), &mut report_size as *mut _);
-    //     if res == 0x0 {
-    //         print!("Received Report INPUT: ");
-    //         for i in 0..report_size {
-    //             print!("{:02X} ", input_report_buffer[i as usize]);
-    //         }
-    //         println!();
-    //     } else {
-    //         println!("failed to get the input report: {:02X}", res);
-    //     }
-    // }
-
-    let reports = unsafe { IOHIDDeviceCopyReports(sender) };
-    println!("REPORT ARRAY SIZE: {}", reports.len());
-         
-        
-    if in_return != 0 {
-        return;
-    }
-
-    let pembejeo = unsafe { &*(in_context as *mut Pembejeo) };
-    let id = format!("0x{:x}", sender as usize);
-
-    // Get the page, usage, and value 
-    let (page, usage, value) = unsafe { 
-        let element = IOHIDValueGetElement(iohid_value);
-        let page = IOHIDElementGetUsagePage(element);
-        let usage = IOHIDElementGetUsage(element);
-        let value = IOHIDValueGetIntegerValue(iohid_value);
-        (page, usage, value)
-    };
-
-    match page {
-        // Generic Desktop
-        0x01 => {
-            // Mouse moved on the X or Y axis
-            if usage == 0x30 || usage == 0x31 {
-                // Create a event
-                let mouse_motion_event =  MouseMotionEvent {
-                    device_id: id,
-                    x: if usage == 0x30 { value as i16 } else { 0 },
-                    y: if usage == 0x31 { value as i16 } else { 0 }
-                };
-                if mouse_motion_event.x != 0 || mouse_motion_event.y != 0 {
-                    // Add the event to the list
-                    let event = Event::MouseMotion(mouse_motion_event);
-                    pembejeo.push_event(&event);
-                }
-            }
-            else {
-                println!("Generic Desktop Event but unrecognized usage!");
-                println!("\tUsage: 0x{:x}", usage);
-                println!("\tValue: {}", value);
-            }
-        },
+    /// Report a raw joystick input of a gamepad through its SDL mapping. Returns false when the
+    /// device has none, and the backend should report the input in the built-in layout instead.
+    pub fn map_joystick_input(&self, device: DeviceHandle, input: JoystickInput, timestamp: Timestamp) -> bool {
+        let guid = {
+            let handles = self.handles.lock().unwrap();
+            let devices = self.devices.lock().unwrap();
+            let Some(guid) = handles.id(device).and_then(|device_id| devices.get(device_id)?.guid) else {
+                return false;
+            };
+            guid
+        };
 
+        let mut mappers = self.gamepad_mappers.lock().unwrap();
+        // A device can come back with another GUID, after a firmware update for one
+        if mappers.get(&device).is_none_or(|mapper| mapper.guid != guid) {
+            let mapping = self.gamepad_mappings.lock().unwrap().find(&guid).cloned();
+            mappers.insert(device, GamepadMapper::new(guid, mapping));
+        }
 
-        _ => {
-            println!("Alternative page: 0x{:x}", page);
-            println!("\tUsage: 0x{:x}", usage);
-            println!("\tValue: {}", value);
+        mappers.get_mut(&device).unwrap().handle_input(device, input, timestamp, |event| self.push_event(event))
+    }
+
+    pub fn push_event(&self, event: Event) {
+        // Touch events own no memory, so keeping a copy for the gestures doesn't allocate
+        let touch = matches!(event, Event::Touch(_) | Event::TouchFrame(_)).then(|| event.clone());
+        self.events.push(event);
+
+        if let Some(event) = touch {
+            if let Some(recognizer) = self.gestures.lock().unwrap().as_mut() {
+                recognizer.handle_event(&event, |gesture| self.push_event(gesture));
+            }
         }
     }
 }
 
-#[cfg(target_os = "macos")]
-fn handle_hid_report(
-    _context: *mut c_void,
-    _result: i32,
-    sender: *mut c_void,
-    _type: u32, _report_id: u32,
-    report: *mut u8,
-    report_length: i32
-) {
-    use core_foundation::{data::{CFData, CFDataRef}, number::{CFNumber, CFNumberRef}, string::CFString};
-    use crate::apple::iohid::{IOHIDDeviceGetProperty, IOHIDDeviceGetReport};
- 
-    unsafe { 
-        //let mut report_size = 64_isize;
-        //let mut input_report_buffer: [u8; 64] = [0; 64]; 
-        //let mut output_report_buffer: [u8; 64] = [0; 64]; 
-        //let mut feature_report_buffer: [u8; 64] = [0; 64]; 
-
-        println!("Received Report IN: ");
-        for i in 0..report_length {
-            print!("{:02X} ", *report.offset(i as isize));
+impl Drop for Pembejeo {
+    fn drop(&mut self) {
+        // The timer rumbles through the backend
+        if let Some(mut timer) = self.rumble_timer.lock().unwrap().take() {
+            timer.shutdown();
         }
-        println!();
-
-        //let _ = IOHIDDeviceGetReport(sender, 0, 63, input_report_buffer.as_mut_ptr(), &mut report_size as *mut _);
-        //print!("Received Report INPUT: ");
-        //for i in 0..report_size {
-        //    print!("{:02X} ", output_report_buffer[i as usize]);
-        //}
-        //println!(); 
-
-        //let _ = IOHIDDeviceGetReport(sender, 1, 0x02, output_report_buffer.as_mut_ptr(), &mut report_size as *mut _);
-        //    print!("Received Report OUTPUT: ");
-        //    for i in 0..report_size {
-        //        print!("{:02X} ", output_report_buffer[i as usize]);
-        //    }
-        //println!(); 
-
-        //let _ = IOHIDDeviceGetReport(sender, 2, 0x02, feature_report_buffer.as_mut_ptr(), &mut report_size as *mut _);
-        //    print!("Received Report INPUT: ");
-        //    for i in 0..report_size {
-        //        print!("{:02X} ", feature_report_buffer[i as usize]);
-        //    }
-        //println!();
-
-        //let mut i = 3;
-        //while (i < u8::MAX) {
-        //    let mut report_buffer: [u8; 64] = [0; 64]; 
-        //    let res = IOHIDDeviceGetReport(sender, 2 as u32, i, report_buffer.as_mut_ptr(), &mut report_size as *mut _);
-
-
-        //    if res == 0x00 {
-        //        break;
-        //    }
-        //    i += 1;
-        //}
-        //println!("Loop stopped at {}", i)
-
-         
+
+        // Stop the backend before the fields it delivers into are freed, without letting it wait
+        // for room that will never come
+        self.events.close();
+        self.backend.lock().unwrap().shutdown();
     }
 }
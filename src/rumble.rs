@@ -0,0 +1,275 @@
+use std::{collections::HashMap, sync::{Arc, Condvar, Mutex}, thread::{self, JoinHandle}, time::{Duration, Instant}};
+
+use crate::{DeviceInfo, ReportDescriptor, ReportKind, Usage};
+
+/// Physical Interface Device reports and the usages set in them
+const SET_EFFECT: Usage = Usage::new(0x0F, 0x21);
+const SET_PERIODIC: Usage = Usage::new(0x0F, 0x6E);
+const EFFECT_OPERATION: Usage = Usage::new(0x0F, 0x77);
+const EFFECT_BLOCK_INDEX: u16 = 0x22;
+const ET_SINE: u16 = 0x30;
+const DURATION: u16 = 0x50;
+const GAIN: u16 = 0x52;
+const MAGNITUDE: u16 = 0x70;
+const PERIOD: u16 = 0x72;
+const OP_EFFECT_START: u16 = 0x79;
+const OP_EFFECT_STOP: u16 = 0x7B;
+const LOOP_COUNT: u16 = 0x7C;
+
+const SONY: u16 = 0x054C;
+
+/// Both rumble motors of a gamepad running for a while.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rumble {
+    /// The low frequency motor, from 0.0 to 1.0
+    pub strong: f64,
+    /// The high frequency motor, from 0.0 to 1.0
+    pub weak: f64,
+    pub duration: Duration,
+}
+
+impl Rumble {
+    pub fn new(strong: f64, weak: f64, duration: Duration) -> Self {
+        Rumble { strong, weak, duration }
+    }
+
+    /// Whether the motors stand still.
+    pub fn is_off(&self) -> bool {
+        (self.strong <= 0.0 && self.weak <= 0.0) || self.duration.is_zero()
+    }
+
+    /// Scale a magnitude to 0..=`maximum`.
+    pub(crate) fn scale(magnitude: f64, maximum: i32) -> i32 {
+        (magnitude.clamp(0.0, 1.0) * maximum as f64).round() as i32
+    }
+}
+
+/// The output reports that play `rumble` on a HID device, report ID byte first, or stop its
+/// motors when `rumble` is off.
+///
+/// DualShock 4 and DualSense controllers connected over USB have their own report. Anything else
+/// needs a Physical Interface Device descriptor, and gets a sine effect in block 1 at the stronger
+/// motor's magnitude, as PID has no notion of two motors. The block isn't allocated through Create
+/// New Effect first, which simple devices don't ask for.
+pub(crate) fn rumble_reports(device: &DeviceInfo, descriptor: &ReportDescriptor, rumble: &Rumble) -> Result<Vec<Vec<u8>>, crate::Error> {
+    let (strong, weak) = if rumble.is_off() { (0, 0) } else { (Rumble::scale(rumble.strong, 255) as u8, Rumble::scale(rumble.weak, 255) as u8) };
+
+    if device.vendor_id == SONY {
+        // DualShock 4 report 5: only the rumble flag, so the light bar keeps its color
+        if let Some(report) = descriptor.report(ReportKind::Output, 0x05).filter(|_| matches!(device.product_id, 0x05C4 | 0x09CC | 0x0BA0)) {
+            let mut data = vec![0; report.byte_length()];
+            data[..6].copy_from_slice(&[0x05, 0x01, 0x00, 0x00, weak, strong]);
+            return Ok(vec![data]);
+        }
+
+        // DualSense report 2: rumble in compatibility mode, like older controllers
+        if let Some(report) = descriptor.report(ReportKind::Output, 0x02).filter(|_| matches!(device.product_id, 0x0CE6 | 0x0DF2)) {
+            let mut data = vec![0; report.byte_length()];
+            data[..5].copy_from_slice(&[0x02, 0x03, 0x00, weak, strong]);
+            return Ok(vec![data]);
+        }
+    }
+
+    let operation = |operation: u16| descriptor.output_report(EFFECT_OPERATION, |usage, _| match usage.id {
+        EFFECT_BLOCK_INDEX | LOOP_COUNT => Some(1),
+        id if id == operation => Some(1),
+        _ => None,
+    });
+    let reports = if rumble.is_off() {
+        operation(OP_EFFECT_STOP).map(|report| vec![report])
+    } else {
+        let duration = rumble.duration.as_millis().min(i32::MAX as u128) as i32;
+        let magnitude = rumble.strong.max(rumble.weak);
+        let set_effect = descriptor.output_report(SET_EFFECT, |usage, field| match usage.id {
+            EFFECT_BLOCK_INDEX | ET_SINE => Some(1),
+            DURATION => Some(duration),
+            GAIN => Some(field.logical_maximum),
+            _ => None,
+        });
+        let set_periodic = descriptor.output_report(SET_PERIODIC, |usage, field| match usage.id {
+            EFFECT_BLOCK_INDEX => Some(1),
+            MAGNITUDE => Some(Rumble::scale(magnitude, field.logical_maximum)),
+            // A 50 Hz buzz, in milliseconds
+            PERIOD => Some(20),
+            _ => None,
+        });
+        set_effect.zip(set_periodic).zip(operation(OP_EFFECT_START)).map(|((set_effect, set_periodic), start)| vec![set_effect, set_periodic, start])
+    };
+    reports.ok_or_else(|| crate::Error::NotSupported(format!("{} has no rumble output report", device.id)))
+}
+
+/// Stops the rumble of devices when its duration is up, on a thread of its own.
+pub(crate) struct RumbleTimer {
+    state: Arc<(Mutex<TimerState>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+
+#[derive(Default)]
+struct TimerState {
+    /// When the rumble of each device ends
+    deadlines: HashMap<String, Instant>,
+    shutdown: bool,
+}
+
+impl RumbleTimer {
+    /// Start the thread, which calls `stop` with the device id of every rumble that ends.
+    pub(crate) fn new(stop: impl Fn(&str) + Send + 'static) -> Self {
+        let state = Arc::new((Mutex::new(TimerState::default()), Condvar::new()));
+        let thread_state = state.clone();
+        let thread = thread::spawn(move || {
+            let (lock, condvar) = &*thread_state;
+            let mut timers = lock.lock().unwrap();
+            while !timers.shutdown {
+                let now = Instant::now();
+                // Stopping under the lock keeps a rumble started meanwhile from being cut short
+                timers.deadlines.retain(|device_id, deadline| {
+                    if *deadline <= now {
+                        stop(device_id);
+                    }
+                    *deadline > now
+                });
+
+                timers = match timers.deadlines.values().min().copied() {
+                    Some(deadline) => condvar.wait_timeout(timers, deadline - now).unwrap().0,
+                    None => condvar.wait(timers).unwrap(),
+                };
+            }
+        });
+
+        RumbleTimer { state, thread: Some(thread) }
+    }
+
+    /// Stop the rumble of `device_id` at `deadline`, replacing its previous one. `None` cancels it.
+    pub(crate) fn schedule(&self, device_id: &str, deadline: Option<Instant>) {
+        let (lock, condvar) = &*self.state;
+        let mut timers = lock.lock().unwrap();
+        match deadline {
+            Some(deadline) => timers.deadlines.insert(device_id.to_string(), deadline),
+            None => timers.deadlines.remove(device_id),
+        };
+        condvar.notify_one();
+    }
+
+    /// Stop the thread, leaving any rumble still playing.
+    pub(crate) fn shutdown(&mut self) {
+        let (lock, condvar) = &*self.state;
+        lock.lock().unwrap().shutdown = true;
+        condvar.notify_one();
+
+        if let Some(thread) = self.thread.take() {
+            thread.join().unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::{mpsc, Mutex}, time::{Duration, Instant}};
+
+    use super::{rumble_reports, Rumble, RumbleTimer};
+    use crate::{hid::fixtures::PID_JOYSTICK, Backend, Context, DeviceInfo, Pembejeo, ReportDescriptor};
+
+    /// Hands the output reports it is asked to send to the test.
+    struct OutputBackend(Mutex<mpsc::Sender<Vec<u8>>>);
+
+    impl Backend for OutputBackend {
+        fn start(&mut self, _context: Context) -> Result<(), crate::Error> {
+            Ok(())
+        }
+
+        fn shutdown(&mut self) {}
+
+        fn set_output_report(&mut self, _device_id: &str, report: &[u8]) -> Result<(), crate::Error> {
+            self.0.lock().unwrap().send(report.to_vec()).unwrap();
+            Ok(())
+        }
+    }
+
+    fn device(vendor_id: u16, product_id: u16, report_descriptor: &[u8]) -> DeviceInfo {
+        DeviceInfo {
+            id: "gamepad".to_string(),
+            vendor_id,
+            product_id,
+            report_descriptor: report_descriptor.to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sony_reports() {
+        // A vendor page output report 5 of 31 bytes, like the DualShock 4 declares over USB
+        let dualshock = device(0x054C, 0x09CC, &[
+            0x06, 0x00, 0xFF, 0x09, 0x01, 0xA1, 0x01, 0x85, 0x05, 0x09, 0x02, 0x15, 0x00, 0x26, 0xFF, 0x00,
+            0x75, 0x08, 0x95, 0x1F, 0x91, 0x02, 0xC0,
+        ]);
+        let descriptor = dualshock.parse_report_descriptor().unwrap();
+
+        let reports = rumble_reports(&dualshock, &descriptor, &Rumble::new(1.0, 0.5, Duration::from_millis(200))).unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].len(), 32);
+        assert_eq!(reports[0][..6], [0x05, 0x01, 0x00, 0x00, 128, 255]);
+
+        let reports = rumble_reports(&dualshock, &descriptor, &Rumble::default()).unwrap();
+        assert_eq!(reports[0][..6], [0x05, 0x01, 0x00, 0x00, 0, 0]);
+
+        // Other vendors need the Physical Interface Device page
+        let other = device(0x1234, 0x09CC, &dualshock.report_descriptor);
+        assert!(rumble_reports(&other, &descriptor, &Rumble::default()).is_err());
+    }
+
+    #[test]
+    fn pid_reports() {
+        let joystick = device(0x1234, 0x5678, &PID_JOYSTICK);
+        let descriptor = ReportDescriptor::parse(&PID_JOYSTICK).unwrap();
+
+        let reports = rumble_reports(&joystick, &descriptor, &Rumble::new(0.25, 0.5, Duration::from_millis(300))).unwrap();
+        assert_eq!(reports, vec![
+            // Block 1, a sine, 300 ms at full gain
+            vec![0x02, 0x01, 0x02, 0x2C, 0x01, 0xFF],
+            // Half of the 10000 magnitude, every 20 ms
+            vec![0x03, 0x01, 0x88, 0x13, 0x14, 0x00],
+            // Start once
+            vec![0x04, 0x01, 0x01, 0x01],
+        ]);
+
+        let reports = rumble_reports(&joystick, &descriptor, &Rumble::new(1.0, 1.0, Duration::ZERO)).unwrap();
+        assert_eq!(reports, vec![vec![0x04, 0x01, 0x03, 0x01]]);
+    }
+
+    #[test]
+    fn timer() {
+        let (sender, receiver) = mpsc::channel();
+        let mut timer = RumbleTimer::new(move |device_id| sender.send(device_id.to_string()).unwrap());
+
+        let start = Instant::now();
+        timer.schedule("cancelled", Some(start + Duration::from_millis(10)));
+        timer.schedule("later", Some(start + Duration::from_millis(40)));
+        timer.schedule("sooner", Some(start + Duration::from_millis(20)));
+        timer.schedule("cancelled", None);
+
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)).unwrap(), "sooner");
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)).unwrap(), "later");
+        assert!(start.elapsed() >= Duration::from_millis(40));
+
+        timer.shutdown();
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn rumble_hid_device() {
+        let (sender, receiver) = mpsc::channel();
+        let pembejeo = Pembejeo::with_backend(Box::new(OutputBackend(Mutex::new(sender)))).unwrap();
+        assert!(matches!(pembejeo.rumble("joystick", Rumble::default()), Err(crate::Error::DeviceNotFound(_))));
+
+        let mut joystick = device(0x1234, 0x5678, &PID_JOYSTICK);
+        joystick.id = "joystick".to_string();
+        pembejeo.add_device(joystick);
+
+        pembejeo.rumble("joystick", Rumble::new(1.0, 0.0, Duration::from_millis(20))).unwrap();
+        let reports = receiver.try_iter().map(|report| report[0]).collect::<Vec<_>>();
+        assert_eq!(reports, vec![0x02, 0x03, 0x04]);
+
+        // Stopped once the duration is up
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)).unwrap(), vec![0x04, 0x01, 0x03, 0x01]);
+    }
+}
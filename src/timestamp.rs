@@ -0,0 +1,79 @@
+use std::time::{Duration, Instant};
+
+/// When an event happened, in nanoseconds on the system's monotonic clock: `CLOCK_MONOTONIC` on
+/// Linux and `mach_absolute_time` on macOS.
+///
+/// Backends take it from the input itself where the system provides one, so it is when the input
+/// arrived rather than when the event was queued or polled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Timestamp(u64);
+
+impl Timestamp {
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn now() -> Self {
+        // CLOCK_UPTIME_RAW is mach_absolute_time in nanoseconds
+        #[cfg(target_os = "linux")]
+        const CLOCK: libc::clockid_t = libc::CLOCK_MONOTONIC;
+        #[cfg(target_os = "macos")]
+        const CLOCK: libc::clockid_t = libc::CLOCK_UPTIME_RAW;
+
+        let mut time: libc::timespec = unsafe { std::mem::zeroed() };
+        unsafe { libc::clock_gettime(CLOCK, &mut time) };
+        Timestamp::from_nanos(time.tv_sec as u64 * 1_000_000_000 + time.tv_nsec as u64)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    pub fn now() -> Self {
+        // No device reports a time here, so any start will do
+        static START: std::sync::OnceLock<Instant> = std::sync::OnceLock::new();
+        Timestamp::from_nanos(START.get_or_init(Instant::now).elapsed().as_nanos() as u64)
+    }
+
+    pub fn from_nanos(nanos: u64) -> Self {
+        Timestamp(nanos)
+    }
+
+    pub fn as_nanos(&self) -> u64 {
+        self.0
+    }
+
+    /// The time from `earlier` to this timestamp, zero when `earlier` is later.
+    pub fn duration_since(&self, earlier: Timestamp) -> Duration {
+        Duration::from_nanos(self.0.saturating_sub(earlier.0))
+    }
+
+    /// The time since this timestamp.
+    pub fn elapsed(&self) -> Duration {
+        Timestamp::now().duration_since(*self)
+    }
+
+    /// The same moment as an `Instant`, to compare with times the application measures itself.
+    pub fn to_instant(&self) -> Instant {
+        // An Instant can't be made from a clock reading, so count back from now
+        let now = Instant::now();
+        now.checked_sub(self.elapsed()).unwrap_or(now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::Timestamp;
+
+    #[test]
+    fn instants() {
+        let before = Instant::now();
+        let timestamp = Timestamp::now();
+        let after = Instant::now();
+
+        // Reading the two clocks apart can't be off by much
+        let instant = timestamp.to_instant();
+        assert!(instant + Duration::from_millis(1) >= before && instant <= after + Duration::from_millis(1));
+
+        let later = Timestamp::from_nanos(timestamp.as_nanos() + 1_500);
+        assert_eq!(later.duration_since(timestamp), Duration::from_nanos(1_500));
+        assert_eq!(timestamp.duration_since(later), Duration::ZERO);
+        assert!(Timestamp::now() >= timestamp);
+    }
+}
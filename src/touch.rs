@@ -0,0 +1,308 @@
+use std::iter;
+
+use crate::{DeviceHandle, Event, FieldValue, Pembejeo, Report, ReportDescriptor, ScaledMotion, TouchEvent, TouchFrameEvent, TouchPhase, Timestamp, Usage};
+
+/// Digitizer Finger, the logical collection around each contact of a Precision Touchpad report
+const FINGER: Usage = Usage::new(0x0D, 0x22);
+/// Digitizer Contact Count, only set in the first report of a frame
+const CONTACT_COUNT: Usage = Usage::new(0x0D, 0x54);
+
+/// One contact as a report describes it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Contact {
+    /// The Finger collection it was read from
+    collection: usize,
+
+    id: u32,
+    tip: bool,
+    confident: bool,
+    x: f64,
+    y: f64,
+    millimetres: Option<ScaledMotion>,
+    width: Option<f64>,
+    height: Option<f64>,
+    pressure: Option<f64>,
+}
+
+impl Contact {
+    fn new(collection: usize) -> Self {
+        Contact {
+            collection,
+            id: 0,
+            tip: false,
+            // Devices without a Confidence usage never flag palms
+            confident: true,
+            x: 0.0,
+            y: 0.0,
+            millimetres: None,
+            width: None,
+            height: None,
+            pressure: None,
+        }
+    }
+
+    /// Whether anything a `Moved` event would carry differs.
+    fn moved_from(&self, other: &Contact) -> bool {
+        (self.x, self.y, self.width, self.height, self.pressure) != (other.x, other.y, other.width, other.height, other.pressure)
+    }
+}
+
+/// Assembles Precision Touchpad style Digitizer reports into frames of contacts.
+#[derive(Debug, Default)]
+pub(crate) struct TouchInterpreter {
+    frame: u64,
+
+    /// Contacts on the surface as of the last frame
+    contacts: Vec<Contact>,
+    /// Contacts of the frame being assembled. In hybrid mode a frame spans several reports.
+    pending: Vec<Contact>,
+    /// How many more contacts the frame being assembled expects
+    remaining: usize,
+    /// The contacts of the report being handled
+    slots: Vec<Contact>,
+}
+
+impl TouchInterpreter {
+    pub(crate) fn handle_report(&mut self, pembejeo: &Pembejeo, device: DeviceHandle, timestamp: Timestamp, descriptor: &ReportDescriptor, report: &Report, values: &[FieldValue]) {
+        self.slots.clear();
+        let mut contact_count = None;
+
+        for value in values {
+            if value.usage == CONTACT_COUNT {
+                contact_count = Some(value.value.max(0) as usize);
+                continue;
+            }
+
+            let Some(finger) = finger_collection(descriptor, value.collection) else {
+                continue;
+            };
+            if self.slots.last().is_none_or(|contact| contact.collection != finger) {
+                self.slots.push(Contact::new(finger));
+            }
+            let contact = self.slots.last_mut().unwrap();
+
+            let field = &report.fields[value.field];
+            match (value.usage.page, value.usage.id) {
+                // Tip Switch, Confidence and Contact Identifier
+                (0x0D, 0x42) => contact.tip = value.value != 0,
+                (0x0D, 0x47) => contact.confident = value.value != 0,
+                (0x0D, 0x51) => contact.id = value.value as u32,
+                // Generic Desktop X and Y
+                (0x01, 0x30) => {
+                    contact.x = field.normalized(value.value);
+                    if let Some(millimetres) = field.millimetres(value.value) {
+                        contact.millimetres.get_or_insert_with(ScaledMotion::default).x = millimetres;
+                    }
+                },
+                (0x01, 0x31) => {
+                    contact.y = field.normalized(value.value);
+                    if let Some(millimetres) = field.millimetres(value.value) {
+                        contact.millimetres.get_or_insert_with(ScaledMotion::default).y = millimetres;
+                    }
+                },
+                // Width, Height and Tip Pressure
+                (0x0D, 0x48) => contact.width = field.millimetres(value.value),
+                (0x0D, 0x49) => contact.height = field.millimetres(value.value),
+                (0x0D, 0x30) => contact.pressure = Some(field.normalized(value.value)),
+                _ => {}
+            }
+        }
+
+        if self.slots.is_empty() {
+            return;
+        }
+
+        match contact_count {
+            // The first report of a frame says how many contacts it has
+            Some(count) if count > 0 => {
+                if !self.pending.is_empty() {
+                    self.finish_frame(pembejeo, device, timestamp);
+                }
+                self.remaining = count;
+            },
+            // Later reports of a hybrid frame carry 0
+            Some(_) => {},
+            // Without a count every report is a frame of its own
+            None => {
+                self.pending.clear();
+                self.remaining = self.slots.len();
+            },
+        }
+
+        // Slots past the contact count are padding
+        let taken = self.remaining.min(self.slots.len());
+        self.pending.extend_from_slice(&self.slots[..taken]);
+        self.remaining -= taken;
+
+        if self.remaining == 0 {
+            self.finish_frame(pembejeo, device, timestamp);
+        }
+    }
+
+    /// Push the frame's events, at the time of the report that completed it.
+    fn finish_frame(&mut self, pembejeo: &Pembejeo, device: DeviceHandle, timestamp: Timestamp) {
+        self.frame += 1;
+        let frame = self.frame;
+        let mut changed = false;
+
+        let mut push = |contact: &Contact, phase: TouchPhase| {
+            changed = true;
+            pembejeo.push_event(Event::Touch(TouchEvent {
+                device,
+                timestamp,
+                frame,
+                contact_id: contact.id,
+                phase,
+                x: contact.x,
+                y: contact.y,
+                millimetres: contact.millimetres,
+                width: contact.width,
+                height: contact.height,
+                pressure: contact.pressure,
+            }));
+        };
+
+        for contact in &self.pending {
+            let existing = self.contacts.iter().position(|existing| existing.id == contact.id);
+            match existing {
+                None if contact.tip && contact.confident => {
+                    push(contact, TouchPhase::Began);
+                    self.contacts.push(*contact);
+                },
+                Some(index) if contact.tip && contact.confident => {
+                    if contact.moved_from(&self.contacts[index]) {
+                        push(contact, TouchPhase::Moved);
+                    }
+                    self.contacts[index] = *contact;
+                },
+                Some(index) => {
+                    push(contact, if contact.confident { TouchPhase::Ended } else { TouchPhase::Cancelled });
+                    self.contacts.remove(index);
+                },
+                None => {},
+            }
+        }
+
+        // Contacts missing from the frame were lifted without the device saying so
+        let pending = &self.pending;
+        self.contacts.retain(|contact| {
+            let kept = pending.iter().any(|reported| reported.id == contact.id);
+            if !kept {
+                push(contact, TouchPhase::Ended);
+            }
+            kept
+        });
+
+        if changed || !self.contacts.is_empty() {
+            pembejeo.push_event(Event::TouchFrame(TouchFrameEvent {
+                device,
+                timestamp,
+                frame,
+                contacts: self.contacts.len() as u32,
+            }));
+        }
+
+        self.pending.clear();
+        self.remaining = 0;
+    }
+}
+
+/// The Finger collection `collection` is in, if any.
+fn finger_collection(descriptor: &ReportDescriptor, collection: Option<usize>) -> Option<usize> {
+    iter::successors(collection, |index| descriptor.collections[*index].parent)
+        .find(|index| descriptor.collections[*index].usage == FINGER)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{hid::fixtures::TOUCHPAD, interpreter::ReportInterpreter, DeviceHandle, Event, NullBackend, Pembejeo, ReportDescriptor, Timestamp, TouchPhase};
+
+    /// Input report 1 with up to two contacts of `(id, tip, confident, x, y)`.
+    fn report(contacts: &[(u8, bool, bool, u16, u16)], contact_count: u8) -> Vec<u8> {
+        let mut report = vec![0x01];
+        for slot in 0..2 {
+            match contacts.get(slot) {
+                Some(&(id, tip, confident, x, y)) => {
+                    report.push(confident as u8 | (tip as u8) << 1);
+                    report.push(id);
+                    report.extend_from_slice(&x.to_le_bytes());
+                    report.extend_from_slice(&y.to_le_bytes());
+                    report.push(0x80);
+                },
+                None => report.extend_from_slice(&[0; 7]),
+            }
+        }
+        report.push(contact_count);
+        report.push(0);
+        report
+    }
+
+    /// `(frame, contact id, phase)` for every touch and `(frame, u32::MAX, contacts)` for every frame end.
+    fn touches(pembejeo: &Pembejeo) -> Vec<(u64, u32, Option<TouchPhase>)> {
+        let mut touches = Vec::new();
+        let mut event = Event::default();
+        while pembejeo.poll(&mut event) {
+            match &event {
+                Event::Touch(touch_event) => touches.push((touch_event.frame, touch_event.contact_id, Some(touch_event.phase))),
+                Event::TouchFrame(frame_event) => touches.push((frame_event.frame, frame_event.contacts, None)),
+                _ => {}
+            }
+        }
+        touches
+    }
+
+    #[test]
+    fn contact_positions() {
+        let pembejeo = Pembejeo::with_backend(Box::new(NullBackend)).unwrap();
+        let descriptor = ReportDescriptor::parse(&TOUCHPAD).unwrap();
+        let mut interpreter = ReportInterpreter::default();
+
+        interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, &report(&[(4, true, true, 250, 500)], 1));
+
+        let mut event = Event::default();
+        assert!(pembejeo.poll(&mut event));
+        let Event::Touch(touch_event) = event else {
+            panic!("expected a touch, got {:?}", event);
+        };
+        assert_eq!((touch_event.contact_id, touch_event.phase), (4, TouchPhase::Began));
+        assert_eq!((touch_event.x, touch_event.y), (0.25, 1.0));
+        let millimetres = touch_event.millimetres.unwrap();
+        assert!((millimetres.x - 25.0).abs() < 1e-9 && (millimetres.y - 50.0).abs() < 1e-9);
+        assert_eq!(touch_event.pressure, Some(128.0 / 255.0));
+        assert_eq!(touch_event.width, None);
+    }
+
+    #[test]
+    fn frames_and_phases() {
+        let pembejeo = Pembejeo::with_backend(Box::new(NullBackend)).unwrap();
+        let descriptor = ReportDescriptor::parse(&TOUCHPAD).unwrap();
+        let mut interpreter = ReportInterpreter::default();
+        let mut handle = |contacts: &[(u8, bool, bool, u16, u16)], contact_count| {
+            interpreter.handle_report(&pembejeo, DeviceHandle(1), Timestamp::default(), &descriptor, &report(contacts, contact_count));
+        };
+
+        // 1: a finger lands. 2: it moves and a second one lands.
+        handle(&[(1, true, true, 100, 100)], 1);
+        handle(&[(1, true, true, 110, 100), (2, true, true, 500, 200)], 2);
+        // 3: hybrid mode, three contacts over two reports, finger 1 didn't move
+        handle(&[(1, true, true, 110, 100), (2, true, true, 510, 200)], 3);
+        handle(&[(3, true, true, 900, 400)], 0);
+        // 4: finger 2 turns out to be a palm and finger 3 lifts, finger 1 is simply gone
+        handle(&[(2, true, false, 510, 200), (3, false, true, 900, 400)], 2);
+
+        assert_eq!(touches(&pembejeo), vec![
+            (1, 1, Some(TouchPhase::Began)),
+            (1, 1, None),
+            (2, 1, Some(TouchPhase::Moved)),
+            (2, 2, Some(TouchPhase::Began)),
+            (2, 2, None),
+            (3, 2, Some(TouchPhase::Moved)),
+            (3, 3, Some(TouchPhase::Began)),
+            (3, 3, None),
+            (4, 2, Some(TouchPhase::Cancelled)),
+            (4, 3, Some(TouchPhase::Ended)),
+            (4, 1, Some(TouchPhase::Ended)),
+            (4, 0, None),
+        ]);
+    }
+}